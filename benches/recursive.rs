@@ -1,14 +1,14 @@
 use std::{fs::read_to_string, path};
 
 use criterion::{criterion_group, criterion_main, Criterion};
-use tinylang_rs::{interpreter, lexer::lex::Lexer, parser::parse::Parser};
+use tinylang_rs::{host::CapturedHost, interpreter, lexer::lex::Lexer, parser::parse::Parser};
 fn criterion_benchmark(c: &mut Criterion) {
     let path = path::Path::new("benches/corpus/fibonacci.cm");
     let source_code = read_to_string(path).unwrap();
     let mut lex = Lexer::new(&source_code);
     let list = lex.lex();
     // println!("{:?}", list);
-    let mut parser = Parser::new(list, &source_code);
+    let mut parser = Parser::new(list, "fibonacci.cm", &source_code);
     let mut res = match parser.parse_program() {
         Ok(prog) => prog,
         Err(()) => {
@@ -30,7 +30,7 @@ fn criterion_benchmark(c: &mut Criterion) {
         let mut lex = Lexer::new(&source_code);
         let list = lex.lex();
         b.iter(|| {
-            let mut parser = Parser::new(list.clone(), &source_code);
+            let mut parser = Parser::new(list.clone(), "fibonacci.cm", &source_code);
             let mut _res = match parser.parse_program() {
                 Ok(prog) => prog,
                 Err(()) => {
@@ -41,7 +41,7 @@ fn criterion_benchmark(c: &mut Criterion) {
         });
     });
     c.bench_function("fib 20", |b| {
-        b.iter(|| interpreter::interpret(&mut res, false))
+        b.iter(|| interpreter::interpret(&mut res, Box::new(CapturedHost::default())))
     });
 }
 
@@ -15,9 +15,10 @@ fn main() -> Result<()> {
     let content = read_to_string(path)?;
     let mut lex = lex::Lexer::new(&content);
     let source_code = read_to_string(path)?;
+    let file_name = path.to_string_lossy().to_string();
     let list = lex.lex();
     // println!("{:?}", list);
-    let mut parser = Parser::new(list, &source_code);
+    let mut parser = Parser::new(list, &file_name, &source_code);
     let now = Instant::now();
     let mut program = match parser.parse_program() {
         Ok(prog) => prog,
@@ -1,5 +1,5 @@
 use codespan_reporting::{
-    diagnostic::{Diagnostic, Label, LabelStyle},
+    diagnostic::{Diagnostic, Label, LabelStyle, Severity},
     files::SimpleFiles,
     term::termcolor::ColorChoice,
     term::{
@@ -8,60 +8,169 @@ use codespan_reporting::{
     },
 };
 use fxhash::FxHashMap;
+use std::{borrow::Cow, ops::Range};
 
-use std::ops::Range;
+use super::{FileId, FileSpan};
+
+/// collect independent diagnostics, every `add_diagnostic` starts a new one, labels and notes
+/// added afterwards are attached to the last diagnostic
 pub struct ErrorReporter<'a> {
-    files: SimpleFiles<&'a str, String>,
+    // the name of an included file is only known while preprocessing, so it may be owned
+    files: SimpleFiles<Cow<'a, str>, String>,
     file_name_id_map: FxHashMap<String, usize>,
-    diagnostic: Diagnostic<usize>,
+    // the severity of diagnostics added by `add_diagnostic`
+    severity: Severity,
+    diagnostics: Vec<Diagnostic<usize>>,
 }
 
 impl<'a> ErrorReporter<'a> {
     pub fn new() -> ErrorReporter<'a> {
+        Self::with_severity(Severity::Error)
+    }
+
+    /// a reporter whose diagnostics are rendered as warnings instead of errors
+    pub fn warning() -> ErrorReporter<'a> {
+        Self::with_severity(Severity::Warning)
+    }
+
+    fn with_severity(severity: Severity) -> ErrorReporter<'a> {
         ErrorReporter {
             files: SimpleFiles::new(),
-            diagnostic: Diagnostic::error(),
             file_name_id_map: FxHashMap::default(),
+            severity,
+            diagnostics: vec![],
         }
     }
 
-    pub fn add_file(&mut self, name: &'a str, source: String) -> usize {
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.diagnostics.len()
+    }
+
+    pub fn diagnostics(&self) -> &[Diagnostic<usize>] {
+        &self.diagnostics
+    }
+
+    /// whether there is a diagnostic with `Error` or `Bug` severity
+    pub fn has_error(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity >= Severity::Error)
+    }
+
+    /// add a source file, diagnostics refer to it through the returned id. Files added with the
+    /// same name are distinct, `file_id` returns the last one
+    pub fn add_file(&mut self, name: impl Into<Cow<'a, str>>, source: String) -> FileId {
+        let name = name.into();
+        let key = name.to_string();
         let id = self.files.add(name, source);
-        self.file_name_id_map.insert(name.to_string(), id);
-        id
+        self.file_name_id_map.insert(key, id);
+        FileId(id)
+    }
+
+    pub fn file_id(&self, name: &str) -> Option<FileId> {
+        self.file_name_id_map.get(name).copied().map(FileId)
+    }
+
+    pub fn file_name(&self, file_id: FileId) -> Option<&str> {
+        self.files.get(file_id.0).map(|file| file.name().as_ref())
+    }
+
+    /// start a new diagnostic with `message` whose primary label points at `span`
+    pub fn add_diagnostic(&mut self, span: FileSpan, message: String) {
+        self.add_labeled_diagnostic(span, message, String::new());
+    }
+
+    /// like `add_diagnostic`, the primary label has its own message
+    pub fn add_labeled_diagnostic(&mut self, span: FileSpan, message: String, label: String) {
+        self.push_diagnostic(self.severity, span, message, label);
+    }
+
+    /// start a new diagnostic with `Warning` severity, whatever the reporter's severity is
+    pub fn add_warning(&mut self, span: FileSpan, message: String) {
+        self.push_diagnostic(Severity::Warning, span, message, String::new());
     }
 
-    pub fn add_diagnostic(
+    fn push_diagnostic(
         &mut self,
-        file_name: &str,
-        range: impl Into<Range<usize>>,
+        severity: Severity,
+        span: FileSpan,
         message: String,
+        label: String,
     ) {
-        if let Some(file_id) = self.file_name_id_map.get(file_name) {
-            self.diagnostic
-                .labels
-                .push(Label::new(LabelStyle::Primary, *file_id, range).with_message(message));
+        self.diagnostics.push(
+            Diagnostic::new(severity)
+                .with_message(message)
+                .with_labels(vec![Label::new(
+                    LabelStyle::Primary,
+                    span.file_id.0,
+                    span.range(),
+                )
+                .with_message(label)]),
+        );
+    }
+
+    /// attach a secondary label to the last diagnostic, it may point into another file
+    pub fn add_secondary_diagnostic(&mut self, span: FileSpan, message: String) {
+        if let Some(diagnostic) = self.diagnostics.last_mut() {
+            diagnostic.labels.push(
+                Label::new(LabelStyle::Secondary, span.file_id.0, span.range())
+                    .with_message(message),
+            );
+        }
+    }
+
+    /// attach a note to the last diagnostic
+    pub fn add_note(&mut self, note: String) {
+        if let Some(diagnostic) = self.diagnostics.last_mut() {
+            diagnostic.notes.push(note);
         }
     }
 
-    pub fn pop_diagnostic(&mut self, file_name: &str) {
-        if let Some(_) = self.file_name_id_map.get(file_name) {
-            self.diagnostic.labels.pop();
+    /// move every label in `file_id` to the span `map` returns for its range, a label `map`
+    /// can't place is kept
+    pub fn remap_file(&mut self, file_id: FileId, map: impl Fn(Range<usize>) -> Option<FileSpan>) {
+        let labels = self
+            .diagnostics
+            .iter_mut()
+            .flat_map(|diagnostic| diagnostic.labels.iter_mut())
+            .filter(|label| label.file_id == file_id.0);
+        for label in labels {
+            if let Some(span) = map(label.range.clone()) {
+                label.file_id = span.file_id.0;
+                label.range = span.range();
+            }
         }
     }
+
+    pub fn pop_diagnostic(&mut self) {
+        self.diagnostics.pop();
+    }
+
+    /// drop the diagnostics added after the reporter had `len` diagnostics, used when the
+    /// parser backtracks
+    pub fn truncate(&mut self, len: usize) {
+        self.diagnostics.truncate(len);
+    }
+
     pub fn emit_std(&mut self) -> Result<(), std::io::Error> {
         let writer = StandardStream::stderr(ColorChoice::Always);
         let config = codespan_reporting::term::Config::default();
-        term::emit(&mut writer.lock(), &config, &self.files, &self.diagnostic)?;
+        for diagnostic in self.diagnostics.iter() {
+            term::emit(&mut writer.lock(), &config, &self.files, diagnostic)?;
+        }
         Ok(())
     }
 
     pub fn emit_string(&self) -> String {
         let mut writer = Buffer::no_color();
         let config = codespan_reporting::term::Config::default();
-
-        term::emit(&mut writer, &config, &self.files, &self.diagnostic).unwrap();
-        // println!("{}", std::str::from_utf8(writer.as_slice()).unwrap());
-        format!("{}", std::str::from_utf8(writer.as_slice()).unwrap())
+        for diagnostic in self.diagnostics.iter() {
+            term::emit(&mut writer, &config, &self.files, diagnostic).unwrap();
+        }
+        std::str::from_utf8(writer.as_slice()).unwrap().to_string()
     }
 }
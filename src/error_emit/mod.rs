@@ -1,2 +1,4 @@
 pub mod error_emit;
-pub use error_emit::ErrorReporter;
\ No newline at end of file
+pub mod span;
+pub use error_emit::ErrorReporter;
+pub use span::{FileId, FileSpan};
@@ -0,0 +1,29 @@
+use std::ops::Range;
+
+/// the id of a file added to an `ErrorReporter`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FileId(pub(crate) usize);
+
+/// a byte range in the file `file_id`, diagnostics are attached to it so that spans from
+/// different source files print with the right path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileSpan {
+    pub file_id: FileId,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl FileSpan {
+    pub fn new(file_id: FileId, range: impl Into<Range<usize>>) -> FileSpan {
+        let range = range.into();
+        FileSpan {
+            file_id,
+            start: range.start,
+            end: range.end,
+        }
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+}
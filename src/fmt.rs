@@ -0,0 +1,706 @@
+use std::ops::Range;
+
+use crate::{
+    error_emit::ErrorReporter,
+    lexer::{lex::Lexer, token::TokenType},
+    parser::{
+        ast::{
+            Accessor, CompoundStatement, Declaration, Expression, Factor, Initializer, Operation,
+            Params, Parameter, SelectionStatement, Statement, SwitchStatement, Target,
+            TypeSpecifierKind, Var, VarDeclaration,
+        },
+        parse::Parser,
+        Codespan,
+    },
+};
+
+const INDENT: &str = "    ";
+
+/// how tightly an expression binds, from the loosest. An operand binding looser than its place
+/// in the grammar allows is parenthesized, the other parentheses of the source are dropped
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+enum Precedence {
+    Assignment,
+    Conditional,
+    Or,
+    And,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Relational,
+    Shift,
+    Additive,
+    Multiplicative,
+    Unary,
+    Primary,
+}
+
+impl Precedence {
+    fn of(expression: &Expression) -> Self {
+        match expression {
+            Expression::Assignment(_) => Precedence::Assignment,
+            Expression::Conditional(_) => Precedence::Conditional,
+            Expression::LogicExpression(expr) => match expr.operation {
+                Operation::OR(..) => Precedence::Or,
+                _ => Precedence::And,
+            },
+            Expression::BinaryExpression(expr) => match expr.operation {
+                Operation::BITOR(..) => Precedence::BitOr,
+                Operation::BITXOR(..) => Precedence::BitXor,
+                Operation::BITAND(..) => Precedence::BitAnd,
+                Operation::SHL(..) | Operation::SHR(..) => Precedence::Shift,
+                Operation::PLUS(..) | Operation::MINUS(..) => Precedence::Additive,
+                Operation::MULTIPLY(..) | Operation::DIVIDE(..) | Operation::MOD(..) => {
+                    Precedence::Multiplicative
+                }
+                _ => Precedence::Relational,
+            },
+            Expression::UnaryExpression(_) => Precedence::Unary,
+            Expression::Factor(Factor::Expression(expr)) => Precedence::of(expr),
+            Expression::UpdateExpression(_)
+            | Expression::Address(_)
+            | Expression::Dereference(_)
+            | Expression::Factor(_) => Precedence::Primary,
+        }
+    }
+
+    /// the precedence of the right operand of a left associative operator
+    fn tighter(self) -> Self {
+        match self {
+            Precedence::Assignment => Precedence::Conditional,
+            Precedence::Conditional => Precedence::Or,
+            Precedence::Or => Precedence::And,
+            Precedence::And => Precedence::BitOr,
+            Precedence::BitOr => Precedence::BitXor,
+            Precedence::BitXor => Precedence::BitAnd,
+            Precedence::BitAnd => Precedence::Relational,
+            Precedence::Relational => Precedence::Shift,
+            Precedence::Shift => Precedence::Additive,
+            Precedence::Additive => Precedence::Multiplicative,
+            Precedence::Multiplicative => Precedence::Unary,
+            Precedence::Unary | Precedence::Primary => Precedence::Primary,
+        }
+    }
+}
+
+/// a comment, or a preprocessor directive which is kept on its own line
+#[derive(Debug, Clone)]
+struct Comment {
+    range: Range<usize>,
+    directive: bool,
+}
+
+/// print a program back to canonical cminus: four spaces of indentation, the opening brace on
+/// the line of its statement, a space around binary operators and no more parentheses than the
+/// precedence needs. Comments and directives are kept between the declarations and the
+/// statements they were found between, at most one blank line is kept between two of them
+pub struct Formatter<'a> {
+    file_name: &'a str,
+    source: &'a str,
+    comments: Vec<Comment>,
+    // the index of the first comment not printed yet
+    next_comment: usize,
+    indent: usize,
+    output: String,
+    pub error_reporter: ErrorReporter<'a>,
+}
+
+impl<'a> Formatter<'a> {
+    /// `file_name` is the path printed in diagnostics
+    pub fn new(file_name: &'a str, source: &'a str) -> Self {
+        Self {
+            file_name,
+            source,
+            comments: vec![],
+            next_comment: 0,
+            indent: 0,
+            output: String::new(),
+            error_reporter: ErrorReporter::new(),
+        }
+    }
+
+    /// the formatted source, the syntax errors are left in `error_reporter`. Formatting the
+    /// result again gives it back unchanged
+    pub fn format(&mut self) -> Result<String, ()> {
+        // the directive lines are blanked out so the parser doesn't see them
+        let directives = directive_lines(self.source);
+        let mut source = self.source.to_string();
+        for range in directives.iter() {
+            source.replace_range(range.clone(), &" ".repeat(range.len()));
+        }
+        let tokens = Lexer::new(&source).lex();
+        self.comments = tokens
+            .iter()
+            .filter(|token| token.token_type == TokenType::Comment)
+            .map(|token| Comment {
+                range: token.start_index..token.end_index,
+                directive: false,
+            })
+            .chain(directives.into_iter().map(|range| Comment {
+                range,
+                directive: true,
+            }))
+            .collect();
+        self.comments.sort_by_key(|comment| comment.range.start);
+        self.next_comment = 0;
+        self.indent = 0;
+        self.output.clear();
+
+        let mut parser = Parser::new(tokens, self.file_name, self.source);
+        let program = match parser.parse_program() {
+            Ok(program) => program,
+            Err(_) => {
+                self.error_reporter = parser.error_reporter;
+                return Err(());
+            }
+        };
+        for (index, decl) in program.declarations.iter().enumerate() {
+            let simple = !matches!(
+                decl,
+                Declaration::FunctionDeclaration(_) | Declaration::StructDeclaration(_)
+            );
+            self.leading_comments(decl.start(), simple.then(|| decl.end()), index == 0);
+            self.declaration(decl);
+            self.trailing_comment(decl.end());
+        }
+        self.comments_before(usize::MAX, program.declarations.is_empty());
+        Ok(std::mem::take(&mut self.output))
+    }
+
+    fn declaration(&mut self, decl: &Declaration) {
+        match decl {
+            Declaration::FunctionDeclaration(func) => {
+                self.begin_line();
+                let params = self.params(&func.params);
+                let declarator = declarator(&func.type_specifier.kind, &func.id.value);
+                self.output += &format!("{}({}) ", declarator, params);
+                self.block(&func.body);
+                self.end_line();
+            }
+            Declaration::FunctionPrototype(func) => {
+                let params = self.params(&func.params);
+                let declarator = declarator(&func.type_specifier.kind, &func.id.value);
+                self.line(&format!("{}({});", declarator, params));
+            }
+            Declaration::VarDeclaration(decl) => self.line(&self.var_declaration(decl)),
+            Declaration::StructDeclaration(decl) => {
+                self.line(&format!("struct {} {{", decl.id.value));
+                self.indent += 1;
+                for (index, field) in decl.fields.iter().enumerate() {
+                    self.leading_comments(field.start, Some(field.end), index == 0);
+                    self.line(&self.var_declaration(field));
+                    self.trailing_comment(field.end);
+                }
+                self.comments_before(decl.end, decl.fields.is_empty());
+                self.indent -= 1;
+                self.line("};");
+            }
+            Declaration::Error(node) => self.line(&self.source[node.start..node.end]),
+        }
+    }
+
+    fn var_declaration(&self, decl: &VarDeclaration) -> String {
+        let mut text = declarator(&decl.type_specifier.kind, &decl.id.value);
+        if decl.is_const {
+            text.insert_str(0, "const ");
+        }
+        for dimension in decl.dimensions.iter() {
+            // a dimension may be the name of a constant
+            text += &format!("[{}]", &self.source[dimension.start()..dimension.end()]);
+        }
+        if let Some(ref initializer) = decl.initializer {
+            text += " = ";
+            text += &self.expression(initializer);
+        }
+        if let Some(ref elements) = decl.array_initializer {
+            text += " = ";
+            text += &self.initializer_list(elements);
+        }
+        text + ";"
+    }
+
+    fn initializer_list(&self, elements: &[Initializer]) -> String {
+        // a string initializing a `char` array is lowered to chars sharing the string's span
+        if let Some(Initializer::Expression(Expression::Factor(Factor::CharLiteral(literal)))) =
+            elements.first()
+        {
+            let text = &self.source[literal.start..literal.end];
+            if text.starts_with('"') {
+                return text.to_string();
+            }
+        }
+        let elements: Vec<String> = elements
+            .iter()
+            .map(|element| match element {
+                Initializer::Expression(expr) => self.expression(expr),
+                Initializer::List(list) => self.initializer_list(&list.elements),
+            })
+            .collect();
+        format!("{{{}}}", elements.join(", "))
+    }
+
+    fn params(&self, params: &Params) -> String {
+        match params {
+            Params::Void { .. } => "void".into(),
+            Params::ParamsList { params } => params
+                .iter()
+                .map(|param| self.param(param))
+                .collect::<Vec<String>>()
+                .join(", "),
+        }
+    }
+
+    fn param(&self, param: &Parameter) -> String {
+        let mut text = declarator(&param.type_specifier.kind, &param.id.value);
+        if param.is_array {
+            text += "[]";
+        }
+        for dimension in param.dimensions.iter() {
+            text += &format!("[{}]", &self.source[dimension.start()..dimension.end()]);
+        }
+        text
+    }
+
+    /// `{`, the declarations and the statements of the block, and `}` which is left on the
+    /// current line, so `else` or `while` may follow it
+    fn block(&mut self, block: &CompoundStatement) {
+        let empty = block.local_declaration.is_empty() && block.statement_list.is_empty();
+        if empty && !self.has_comment_before(block.end) {
+            self.output += "{}";
+            return;
+        }
+        self.output += "{";
+        self.end_line();
+        self.indent += 1;
+        for (index, decl) in block.local_declaration.iter().enumerate() {
+            self.leading_comments(decl.start, Some(decl.end), index == 0);
+            self.line(&self.var_declaration(decl));
+            self.trailing_comment(decl.end);
+        }
+        self.statements(&block.statement_list, block.local_declaration.is_empty());
+        self.comments_before(block.end, empty);
+        self.indent -= 1;
+        self.begin_line();
+        self.output += "}";
+    }
+
+    fn statements(&mut self, statements: &[Statement], mut first: bool) {
+        for statement in statements {
+            // the comments in a statement without a body are moved before it
+            let simple = matches!(
+                statement,
+                Statement::ExpressionStatement(_)
+                    | Statement::ReturnStatement(_)
+                    | Statement::BreakStatement(_)
+                    | Statement::ContinueStatement(_)
+                    | Statement::Error(_)
+            );
+            self.leading_comments(statement.start(), simple.then(|| statement.end()), first);
+            self.statement(statement);
+            self.trailing_comment(statement.end());
+            first = false;
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        self.begin_line();
+        match statement {
+            Statement::CompoundStatement(block) => {
+                self.block(block);
+                self.end_line();
+            }
+            Statement::ExpressionStatement(stmt) => {
+                if let Some(ref expr) = stmt.expression {
+                    self.output += &self.expression(expr);
+                }
+                self.output += ";";
+                self.end_line();
+            }
+            Statement::SelectionStatement(stmt) => self.selection(stmt),
+            Statement::IterationStatement(stmt) => {
+                self.output += &format!("while ({})", self.expression(&stmt.test));
+                if self.body(&stmt.body) {
+                    self.end_line();
+                }
+            }
+            Statement::ForStatement(stmt) => {
+                let part = |expr: &Option<Expression>, before: &str| {
+                    expr.as_ref()
+                        .map_or(String::new(), |expr| before.to_string() + &self.expression(expr))
+                };
+                self.output += &format!(
+                    "for ({};{};{})",
+                    part(&stmt.init, ""),
+                    part(&stmt.test, " "),
+                    part(&stmt.update, " ")
+                );
+                if self.body(&stmt.body) {
+                    self.end_line();
+                }
+            }
+            Statement::DoWhileStatement(stmt) => {
+                self.output += "do";
+                if self.body(&stmt.body) {
+                    self.output += " ";
+                } else {
+                    self.begin_line();
+                }
+                self.output += &format!("while ({});", self.expression(&stmt.test));
+                self.end_line();
+            }
+            Statement::SwitchStatement(stmt) => self.switch(stmt),
+            Statement::BreakStatement(_) => {
+                self.output += "break;";
+                self.end_line();
+            }
+            Statement::ContinueStatement(_) => {
+                self.output += "continue;";
+                self.end_line();
+            }
+            Statement::ReturnStatement(stmt) => {
+                self.output += "return";
+                if let Some(ref expr) = stmt.expression {
+                    self.output += " ";
+                    self.output += &self.expression(expr);
+                }
+                self.output += ";";
+                self.end_line();
+            }
+            Statement::Error(node) => {
+                self.output += &self.source[node.start..node.end];
+                self.end_line();
+            }
+        }
+    }
+
+    /// `else if` stays on the line of the `}` closing the consequent
+    fn selection(&mut self, stmt: &SelectionStatement) {
+        self.output += &format!("if ({})", self.expression(&stmt.test));
+        let open = self.body(&stmt.consequent);
+        let alternative = match stmt.alternative {
+            Some(ref alternative) => alternative,
+            None => {
+                if open {
+                    self.end_line();
+                }
+                return;
+            }
+        };
+        if open {
+            self.output += " else";
+        } else {
+            self.begin_line();
+            self.output += "else";
+        }
+        match **alternative {
+            Statement::SelectionStatement(ref stmt) => {
+                self.output += " ";
+                self.selection(stmt);
+            }
+            ref alternative => {
+                if self.body(alternative) {
+                    self.end_line();
+                }
+            }
+        }
+    }
+
+    /// the labels are indented like the `switch`, the statements one level more
+    fn switch(&mut self, stmt: &SwitchStatement) {
+        self.output += &format!("switch ({}) {{", self.expression(&stmt.test));
+        self.end_line();
+        for (index, case) in stmt.cases.iter().enumerate() {
+            self.leading_comments(case.start, None, index == 0);
+            let (label, label_end) = match case.label {
+                Some(ref label) => (format!("case {}:", label.value), label.end),
+                None => ("default:".to_string(), case.start + "default".len()),
+            };
+            self.line(&label);
+            let colon = self.source[label_end..].find(':').map_or(label_end, |index| {
+                label_end + index + 1
+            });
+            self.trailing_comment(colon);
+            self.indent += 1;
+            self.statements(&case.statements, true);
+            self.indent -= 1;
+        }
+        self.indent += 1;
+        self.comments_before(stmt.end, stmt.cases.is_empty());
+        self.indent -= 1;
+        self.begin_line();
+        self.output += "}";
+        self.end_line();
+    }
+
+    /// the body of `if`, `else` or a loop: a block stays on the current line, which is left
+    /// open after its `}` and `true` is returned. Another statement goes on the next line,
+    /// indented
+    fn body(&mut self, statement: &Statement) -> bool {
+        if let Statement::CompoundStatement(block) = statement {
+            self.output += " ";
+            self.block(block);
+            return true;
+        }
+        self.end_line();
+        self.indent += 1;
+        self.statement(statement);
+        self.indent -= 1;
+        false
+    }
+
+    fn expression(&self, expression: &Expression) -> String {
+        match expression {
+            Expression::Assignment(expr) => {
+                let operation = expr.operation.as_ref().map_or("", symbol);
+                format!(
+                    "{} {}= {}",
+                    self.target(&expr.lhs),
+                    operation,
+                    self.expression(&expr.rhs)
+                )
+            }
+            Expression::BinaryExpression(expr) => {
+                let precedence = Precedence::of(expression);
+                // comparisons don't chain, so neither side may be another comparison
+                let left = match precedence {
+                    Precedence::Relational => Precedence::Shift,
+                    precedence => precedence,
+                };
+                format!(
+                    "{} {} {}",
+                    self.operand(&expr.left, left),
+                    symbol(&expr.operation),
+                    self.operand(&expr.right, precedence.tighter())
+                )
+            }
+            Expression::LogicExpression(expr) => {
+                let precedence = Precedence::of(expression);
+                format!(
+                    "{} {} {}",
+                    self.operand(&expr.left, precedence),
+                    symbol(&expr.operation),
+                    self.operand(&expr.right, precedence.tighter())
+                )
+            }
+            Expression::Conditional(expr) => format!(
+                "{} ? {} : {}",
+                self.operand(&expr.test, Precedence::Or),
+                self.expression(&expr.consequent),
+                self.operand(&expr.alternative, Precedence::Conditional)
+            ),
+            Expression::UnaryExpression(expr) => {
+                let symbol = symbol(&expr.operation);
+                let mut operand = self.operand(&expr.expression, Precedence::Primary);
+                // `-(--a)` would be lexed as `--` and `-a` without them
+                if operand.starts_with(symbol) {
+                    operand = format!("({})", operand);
+                }
+                format!("{}{}", symbol, operand)
+            }
+            Expression::UpdateExpression(expr) => {
+                let symbol = match expr.operation {
+                    Operation::PLUS(..) => "++",
+                    _ => "--",
+                };
+                if expr.prefix {
+                    format!("{}{}", symbol, self.target(&expr.target))
+                } else {
+                    format!("{}{}", self.target(&expr.target), symbol)
+                }
+            }
+            Expression::Address(expr) => format!("&{}", self.var(&expr.var)),
+            Expression::Dereference(expr) => {
+                format!("*{}", self.operand(&expr.pointer, Precedence::Unary))
+            }
+            Expression::Factor(factor) => match factor {
+                Factor::Expression(expr) => self.expression(expr),
+                Factor::Var(var) => self.var(var),
+                Factor::CallExpression(call) => {
+                    let arguments: Vec<String> =
+                        call.arguments.iter().map(|arg| self.expression(arg)).collect();
+                    format!("{}({})", call.id.value, arguments.join(", "))
+                }
+                Factor::NumberLiteral(literal) => literal.value.to_string(),
+                Factor::BooleanLiteral(literal) => literal.value.to_string(),
+                // the quoted literals are printed as written, with their escape sequences. The
+                // span of a parenthesized literal covers the parentheses
+                factor => self.source[factor.start()..factor.end()]
+                    .trim_start_matches(|c: char| c == '(' || c.is_whitespace())
+                    .trim_end_matches(|c: char| c == ')' || c.is_whitespace())
+                    .to_string(),
+            },
+        }
+    }
+
+    /// the expression, parenthesized if it binds looser than `precedence`
+    fn operand(&self, expression: &Expression, precedence: Precedence) -> String {
+        if Precedence::of(expression) < precedence {
+            format!("({})", self.expression(expression))
+        } else {
+            self.expression(expression)
+        }
+    }
+
+    fn target(&self, target: &Target) -> String {
+        match target {
+            Target::Var(var) => self.var(var),
+            Target::Dereference(expr) => {
+                format!("*{}", self.operand(&expr.pointer, Precedence::Unary))
+            }
+        }
+    }
+
+    fn var(&self, var: &Var) -> String {
+        let mut text = var.id.value.to_string();
+        for accessor in var.accessors.iter() {
+            match accessor {
+                Accessor::Index(expr) => text += &format!("[{}]", self.expression(expr)),
+                Accessor::Member(id) => text += &format!(".{}", id.value),
+            }
+        }
+        text
+    }
+
+    fn has_comment_before(&self, offset: usize) -> bool {
+        self.comments
+            .get(self.next_comment)
+            .map_or(false, |comment| comment.range.start < offset)
+    }
+
+    /// the comments before `start`, or before `end` for an item whose inner comments have no
+    /// statement to stay with, and the blank line before the item. The first item of a block
+    /// isn't preceded by a blank line
+    fn leading_comments(&mut self, start: usize, end: Option<usize>, first: bool) {
+        let first = self.comments_before(end.unwrap_or(start), first);
+        if !first && self.blank_line_before(start) {
+            self.output += "\n";
+        }
+    }
+
+    /// print the comments starting before `offset` on their own lines, `first` is whether
+    /// nothing was printed in the block yet, the same after the comments
+    fn comments_before(&mut self, offset: usize, mut first: bool) -> bool {
+        while self.has_comment_before(offset) {
+            let comment = self.comments[self.next_comment].clone();
+            self.next_comment += 1;
+            if !first && self.blank_line_before(comment.range.start) {
+                self.output += "\n";
+            }
+            let text = &self.source[comment.range];
+            if comment.directive {
+                self.output += text.trim();
+                self.end_line();
+            } else {
+                self.line(text);
+            }
+            first = false;
+        }
+        first
+    }
+
+    /// a comment following the item on its last line stays there, the span of an expression
+    /// statement doesn't cover its `;`
+    fn trailing_comment(&mut self, end: usize) {
+        let comment = match self.comments.get(self.next_comment) {
+            Some(comment) if !comment.directive && comment.range.start >= end => comment,
+            _ => return,
+        };
+        let gap = &self.source[end..comment.range.start];
+        if (gap.trim().is_empty() || gap.trim() == ";") && !gap.contains('\n') {
+            let text = &self.source[comment.range.clone()];
+            self.output.pop();
+            self.output += &format!(" {}\n", text);
+            self.next_comment += 1;
+        }
+    }
+
+    fn blank_line_before(&self, offset: usize) -> bool {
+        let before = &self.source[..offset];
+        let spaces = &before[before.trim_end().len()..];
+        spaces.matches('\n').count() > 1
+    }
+
+    fn begin_line(&mut self) {
+        for _ in 0..self.indent {
+            self.output += INDENT;
+        }
+    }
+
+    fn end_line(&mut self) {
+        self.output += "\n";
+    }
+
+    fn line(&mut self, text: &str) {
+        self.begin_line();
+        self.output += text;
+        self.end_line();
+    }
+}
+
+/// the ranges of the lines starting with `#` outside of comments, from the `#` to the end of
+/// the line
+fn directive_lines(source: &str) -> Vec<Range<usize>> {
+    let comments: Vec<Range<usize>> = Lexer::new(source)
+        .lex()
+        .into_iter()
+        .filter(|token| token.token_type == TokenType::Comment)
+        .map(|token| token.start_index..token.end_index)
+        .collect();
+    let mut directives = vec![];
+    let mut line_start = 0;
+    for line in source.split_inclusive('\n') {
+        let content = line.trim_end_matches('\n').trim_end();
+        let directive = content.trim_start();
+        let start = line_start + content.len() - directive.len();
+        let in_comment = comments.iter().any(|comment| comment.contains(&start));
+        if directive.starts_with('#') && !in_comment {
+            directives.push(start..line_start + content.len());
+        }
+        line_start += line.len();
+    }
+    directives
+}
+
+/// the type and the name, `int *p` puts the `*`s of a pointer type next to the name
+fn declarator(kind: &TypeSpecifierKind, name: &str) -> String {
+    let mut pointers = String::new();
+    let mut kind = kind;
+    while let TypeSpecifierKind::Pointer(pointee) = kind {
+        pointers.push('*');
+        kind = pointee;
+    }
+    let base = match kind {
+        TypeSpecifierKind::Int => "int".to_string(),
+        TypeSpecifierKind::Void => "void".into(),
+        TypeSpecifierKind::Boolean => "bool".into(),
+        TypeSpecifierKind::Char => "char".into(),
+        TypeSpecifierKind::Struct(name) => format!("struct {}", name),
+        TypeSpecifierKind::Pointer(_) => unreachable!(),
+    };
+    format!("{} {}{}", base, pointers, name)
+}
+
+fn symbol(operation: &Operation) -> &'static str {
+    match operation {
+        Operation::GT(..) => ">",
+        Operation::LT(..) => "<",
+        Operation::GE(..) => ">=",
+        Operation::LE(..) => "<=",
+        Operation::EQ(..) => "==",
+        Operation::NE(..) => "!=",
+        Operation::PLUS(..) | Operation::POS(..) => "+",
+        Operation::MINUS(..) | Operation::NEG(..) => "-",
+        Operation::MULTIPLY(..) => "*",
+        Operation::DIVIDE(..) => "/",
+        Operation::MOD(..) => "%",
+        Operation::AND(..) => "&&",
+        Operation::OR(..) => "||",
+        Operation::BITAND(..) => "&",
+        Operation::BITOR(..) => "|",
+        Operation::BITXOR(..) => "^",
+        Operation::SHL(..) => "<<",
+        Operation::SHR(..) => ">>",
+        Operation::BITNOT(..) => "~",
+        Operation::NOT(..) => "!",
+    }
+}
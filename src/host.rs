@@ -0,0 +1,92 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    fmt::Debug,
+    io::{self, BufRead, Write},
+    rc::Rc,
+};
+
+/// the environment a program talks to, `print`, `println` and `input` of both the interpreter
+/// and the vm are routed through it
+pub trait Host: Debug {
+    fn write(&mut self, output: &str);
+    fn flush(&mut self);
+    /// `None` if there is no more input or the input is not an int
+    fn read_int(&mut self) -> Option<i32>;
+}
+
+/// collect the output into a buffer, the buffer is shared between clones, so the output can
+/// still be read after the host is moved into the interpreter or the vm
+#[derive(Debug, Clone, Default)]
+pub struct CapturedHost {
+    buffer: Rc<RefCell<String>>,
+}
+
+impl CapturedHost {
+    pub fn output(&self) -> String {
+        self.buffer.borrow().clone()
+    }
+}
+
+impl Host for CapturedHost {
+    fn write(&mut self, output: &str) {
+        self.buffer.borrow_mut().push_str(output);
+    }
+
+    fn flush(&mut self) {}
+
+    fn read_int(&mut self) -> Option<i32> {
+        None
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct StdioHost;
+
+impl Host for StdioHost {
+    fn write(&mut self, output: &str) {
+        // there is nothing the program could do if stdout is closed
+        let _ = io::stdout().write_all(output.as_bytes());
+    }
+
+    fn flush(&mut self) {
+        let _ = io::stdout().flush();
+    }
+
+    fn read_int(&mut self) -> Option<i32> {
+        self.flush();
+        let mut line = String::new();
+        io::stdin().lock().read_line(&mut line).ok()?;
+        line.trim().parse().ok()
+    }
+}
+
+/// answer `input` from a fixed list of ints, the output goes to the wrapped host
+#[derive(Debug)]
+pub struct ScriptedHost<H: Host> {
+    input: VecDeque<i32>,
+    host: H,
+}
+
+impl<H: Host> ScriptedHost<H> {
+    pub fn new(input: impl IntoIterator<Item = i32>, host: H) -> Self {
+        Self {
+            input: input.into_iter().collect(),
+            host,
+        }
+    }
+}
+
+impl<H: Host> Host for ScriptedHost<H> {
+    fn write(&mut self, output: &str) {
+        self.host.write(output);
+    }
+
+    fn flush(&mut self) {
+        self.host.flush();
+    }
+
+    fn read_int(&mut self) -> Option<i32> {
+        self.input.pop_front()
+    }
+}
@@ -1,4 +1,8 @@
-use crate::parser::ast::FunctionDeclaration;
+use crate::{
+    host::Host,
+    native::Natives,
+    parser::ast::{FunctionDeclaration, StructDeclaration},
+};
 use enum_as_inner::EnumAsInner;
 use fxhash::FxHashMap;
 use smol_str::SmolStr;
@@ -8,6 +12,7 @@ use std::{cell::RefCell, rc::Rc};
 pub enum LiteralType {
     Boolean(bool),
     Number(i32),
+    Char(char),
 }
 
 pub trait IntoLiteral {
@@ -24,11 +29,17 @@ impl IntoLiteral for bool {
         LiteralType::Boolean(*self)
     }
 }
+impl IntoLiteral for char {
+    fn get_literal(&self) -> LiteralType {
+        LiteralType::Char(*self)
+    }
+}
 impl IntoLiteral for Binding {
     fn get_literal(&self) -> LiteralType {
         match self {
             Binding::NumberLiteral(n) => LiteralType::Number(*n),
             Binding::BooleanLiteral(b) => LiteralType::Boolean(*b),
+            Binding::CharLiteral(c) => LiteralType::Char(*c),
             _ => {
                 unimplemented!() // TODO
             }
@@ -45,9 +56,176 @@ pub enum ArrayType {
         length: usize,
         array: Rc<RefCell<Vec<i32>>>,
     },
+    Char {
+        length: usize,
+        array: Rc<RefCell<Vec<char>>>,
+    },
+    /// the rows of a multi-dimensional array, `m[i]` shares the row with `m`
+    Array {
+        length: usize,
+        array: Rc<RefCell<Vec<ArrayType>>>,
+    },
+    /// `name` tells the type of the elements even if the array is empty
+    Struct {
+        length: usize,
+        name: SmolStr,
+        array: Rc<RefCell<Vec<StructType>>>,
+    },
+}
+
+/// the fields of a struct in declaration order. A struct is copied when it's stored, so unlike
+/// an array it's never shared by two variables
+#[derive(Debug, Clone)]
+pub struct StructType {
+    pub name: SmolStr,
+    pub fields: Rc<RefCell<Vec<(SmolStr, Binding)>>>,
+}
+
+impl StructType {
+    pub fn field_index(&self, name: &str) -> Option<usize> {
+        self.fields
+            .borrow()
+            .iter()
+            .position(|(field, _)| field == name)
+    }
+
+    /// the arrays in the struct are copied as well
+    pub fn deep_copy(&self) -> StructType {
+        let fields = self
+            .fields
+            .borrow()
+            .iter()
+            .map(|(name, binding)| {
+                let binding = match binding {
+                    Binding::Array(arr) => Binding::Array(arr.deep_copy()),
+                    binding => binding.stored(),
+                };
+                (name.clone(), binding)
+            })
+            .collect();
+        StructType {
+            name: self.name.clone(),
+            fields: Rc::new(RefCell::new(fields)),
+        }
+    }
 }
 
 impl ArrayType {
+    pub fn length(&self) -> usize {
+        match self {
+            ArrayType::Boolean { length, .. }
+            | ArrayType::Number { length, .. }
+            | ArrayType::Char { length, .. }
+            | ArrayType::Array { length, .. }
+            | ArrayType::Struct { length, .. } => *length,
+        }
+    }
+
+    /// a new array with the same elements, used when a struct holding it is copied
+    pub fn deep_copy(&self) -> ArrayType {
+        fn copy<T: Clone>(array: &Rc<RefCell<Vec<T>>>) -> Rc<RefCell<Vec<T>>> {
+            Rc::new(RefCell::new(array.borrow().clone()))
+        }
+        match self {
+            ArrayType::Boolean { length, array } => ArrayType::Boolean {
+                length: *length,
+                array: copy(array),
+            },
+            ArrayType::Number { length, array } => ArrayType::Number {
+                length: *length,
+                array: copy(array),
+            },
+            ArrayType::Char { length, array } => ArrayType::Char {
+                length: *length,
+                array: copy(array),
+            },
+            ArrayType::Array { length, array } => ArrayType::Array {
+                length: *length,
+                array: Rc::new(RefCell::new(
+                    array.borrow().iter().map(ArrayType::deep_copy).collect(),
+                )),
+            },
+            ArrayType::Struct {
+                length,
+                name,
+                array,
+            } => ArrayType::Struct {
+                length: *length,
+                name: name.clone(),
+                array: Rc::new(RefCell::new(
+                    array.borrow().iter().map(StructType::deep_copy).collect(),
+                )),
+            },
+        }
+    }
+
+    pub fn get_struct(&self, i: usize) -> Result<StructType, String> {
+        match self {
+            ArrayType::Struct { length, array, .. } => {
+                if i >= *length {
+                    Err("the index should less than array length and greater than 0".into())
+                } else {
+                    Ok(array.borrow()[i].clone())
+                }
+            }
+            _ => Err("only an array of structs has structs".into()),
+        }
+    }
+
+    /// `value` is stored as it is, the caller copies it
+    pub fn set_struct(&mut self, i: usize, value: StructType) -> Result<(), String> {
+        match self {
+            ArrayType::Struct {
+                length,
+                name,
+                array,
+            } if *name == value.name => {
+                if i >= *length {
+                    Err("the index should less than array length and greater than 0".into())
+                } else {
+                    array.borrow_mut()[i] = value;
+                    Ok(())
+                }
+            }
+            _ => Err("type doesn't matched".into()),
+        }
+    }
+
+    /// whether both are the same array rather than two arrays with equal elements
+    pub fn same_array(&self, other: &ArrayType) -> bool {
+        match (self, other) {
+            (ArrayType::Boolean { array: a, .. }, ArrayType::Boolean { array: b, .. }) => {
+                Rc::ptr_eq(a, b)
+            }
+            (ArrayType::Number { array: a, .. }, ArrayType::Number { array: b, .. }) => {
+                Rc::ptr_eq(a, b)
+            }
+            (ArrayType::Char { array: a, .. }, ArrayType::Char { array: b, .. }) => {
+                Rc::ptr_eq(a, b)
+            }
+            (ArrayType::Array { array: a, .. }, ArrayType::Array { array: b, .. }) => {
+                Rc::ptr_eq(a, b)
+            }
+            (ArrayType::Struct { array: a, .. }, ArrayType::Struct { array: b, .. }) => {
+                Rc::ptr_eq(a, b)
+            }
+            _ => false,
+        }
+    }
+
+    pub fn get_row(&self, i: usize) -> Result<ArrayType, String> {
+        match self {
+            ArrayType::Array { length, array } => {
+                if i >= *length {
+                    Err("the index should less than array length and greater than 0".into())
+                } else {
+                    Ok(array.borrow()[i].clone())
+                }
+            }
+            _ => Err("only a multi-dimensional array has rows".into()),
+        }
+    }
+
     pub fn get(&self, i: usize) -> Result<LiteralType, String> {
         match self {
             ArrayType::Boolean {
@@ -70,6 +248,18 @@ impl ArrayType {
                     Ok(LiteralType::Number(value.borrow()[i]))
                 }
             }
+            ArrayType::Char {
+                length,
+                array: value,
+            } => {
+                if i >= *length {
+                    Err("the index should less than array length and greater than 0".into())
+                } else {
+                    Ok(LiteralType::Char(value.borrow()[i]))
+                }
+            }
+            ArrayType::Array { .. } => Err("the element is a row, use `get_row`".into()),
+            ArrayType::Struct { .. } => Err("the element is a struct, use `get_struct`".into()),
         }
     }
 
@@ -95,29 +285,157 @@ impl ArrayType {
                     Ok(())
                 }
             }
+            ArrayType::Char { length, array } if matches!(data, LiteralType::Char(_)) => {
+                if i >= *length {
+                    Err("the index should less than array length and greater than 0".into())
+                } else {
+                    if let LiteralType::Char(v) = data {
+                        array.borrow_mut()[i] = v;
+                    }
+                    Ok(())
+                }
+            }
             _ => Err("type doesn't matched".into()),
         }
     }
 }
+/// what a pointer points to, the offset is only checked when the pointer is dereferenced, so
+/// a pointer may move past the end of an array like `a + 3`
+#[derive(Debug, Clone)]
+pub struct Pointer {
+    pub address: Address,
+    pub offset: i32,
+}
+
+#[derive(Debug, Clone)]
+pub enum Address {
+    Null,
+    /// `scope` is the id of the scope declaring the variable, the pointer dangles once the
+    /// scope is popped since ids are never reused
+    Variable { scope: usize, name: SmolStr },
+    Element(ArrayType),
+}
+
+impl Pointer {
+    pub fn null() -> Self {
+        Self {
+            address: Address::Null,
+            offset: 0,
+        }
+    }
+
+    pub fn element(array: ArrayType, offset: i32) -> Self {
+        Self {
+            address: Address::Element(array),
+            offset,
+        }
+    }
+
+    pub fn offset_by(&self, n: i32) -> Self {
+        Self {
+            address: self.address.clone(),
+            offset: self.offset.wrapping_add(n),
+        }
+    }
+
+    /// only the pointers to the same variable or the same array can be subtracted and ordered
+    pub fn same_target(&self, other: &Pointer) -> bool {
+        match (&self.address, &other.address) {
+            (Address::Null, Address::Null) => true,
+            (
+                Address::Variable { scope, name },
+                Address::Variable {
+                    scope: other_scope,
+                    name: other_name,
+                },
+            ) => scope == other_scope && name == other_name,
+            (Address::Element(a), Address::Element(b)) => a.same_array(b),
+            _ => false,
+        }
+    }
+}
+
+impl PartialEq for Pointer {
+    fn eq(&self, other: &Self) -> bool {
+        self.same_target(other) && self.offset == other.offset
+    }
+}
+
 #[derive(Debug, Clone, EnumAsInner)]
 pub enum Binding {
     NumberLiteral(i32),
     BooleanLiteral(bool),
+    CharLiteral(char),
     Array(ArrayType),
+    Struct(StructType),
+    Pointer(Pointer),
     FunctionDeclaration(Rc<FunctionDeclaration>),
     Variable(String),
     Void,
 }
+impl Binding {
+    /// the binding saved by an assignment, a declaration or a call, a struct is copied while
+    /// an array is passed by reference
+    pub fn stored(&self) -> Binding {
+        match self {
+            Binding::Struct(value) => Binding::Struct(value.deep_copy()),
+            binding => binding.clone(),
+        }
+    }
+
+    /// an array is converted to the pointer to its first element when it's used as a pointer
+    pub fn to_pointer(&self) -> Option<Pointer> {
+        match self {
+            Binding::Pointer(pointer) => Some(pointer.clone()),
+            Binding::Array(arr) => Some(Pointer::element(arr.clone(), 0)),
+            _ => None,
+        }
+    }
+}
 pub type Scope = FxHashMap<SmolStr, Binding>;
 #[derive(Debug)]
 pub struct Environment {
     pub(crate) scope_stack: Vec<Scope>,
+    // the id of every scope in `scope_stack`, the global scope is 0
+    pub(crate) scope_ids: Vec<usize>,
+    next_scope_id: usize,
     pub(crate) call_expression_binding: Vec<(SmolStr, Binding)>,
-    pub(crate) std_io: bool,
-    pub(crate) std_simulator: Vec<String>,
+    pub(crate) structs: FxHashMap<SmolStr, Rc<StructDeclaration>>,
+    pub(crate) host: Box<dyn Host>,
+    pub(crate) natives: Natives,
+    pub(crate) main_result: Option<i32>,
+    // the number of calls being evaluated, limited by `MAX_CALL_DEPTH`
+    pub(crate) call_depth: usize,
 }
 
 impl Environment {
+    pub fn new(host: Box<dyn Host>, natives: Natives) -> Self {
+        Self {
+            scope_stack: vec![FxHashMap::default()],
+            scope_ids: vec![0],
+            next_scope_id: 0,
+            call_expression_binding: Vec::new(),
+            structs: FxHashMap::default(),
+            host,
+            natives,
+            main_result: None,
+            call_depth: 0,
+        }
+    }
+
+    /// drop the scopes left by a runtime error, the globals are kept
+    pub(crate) fn unwind(&mut self) {
+        self.scope_stack.truncate(1);
+        self.scope_ids.truncate(1);
+        self.call_expression_binding.clear();
+        self.call_depth = 0;
+    }
+
+    /// the int returned by `main`, `None` if `main` returns nothing
+    pub fn main_result(&self) -> Option<i32> {
+        self.main_result
+    }
+
     pub fn get(&self, name: &SmolStr) -> Option<&Binding> {
         for scope in self.scope_stack.iter().rev() {
             if let Some(binding) = scope.get(name) {
@@ -127,6 +445,32 @@ impl Environment {
         None
     }
 
+    pub fn push_scope(&mut self, scope: Scope) {
+        self.next_scope_id += 1;
+        self.scope_ids.push(self.next_scope_id);
+        self.scope_stack.push(scope);
+    }
+
+    pub fn pop_scope(&mut self) {
+        self.scope_ids.pop();
+        self.scope_stack.pop();
+    }
+
+    /// the id of the innermost scope declaring `name`
+    pub fn scope_id_of(&self, name: &SmolStr) -> Option<usize> {
+        self.scope_stack
+            .iter()
+            .zip(self.scope_ids.iter())
+            .rev()
+            .find(|(scope, _)| scope.contains_key(name))
+            .map(|(_, id)| *id)
+    }
+
+    /// the position in `scope_stack` of the scope with `id`, none if the scope is popped
+    pub fn scope_index(&self, id: usize) -> Option<usize> {
+        self.scope_ids.iter().rposition(|scope_id| *scope_id == id)
+    }
+
     pub fn get_func(&self, name: &SmolStr) -> Option<&Binding> {
         if let Some(binding) = self.scope_stack.first().unwrap().get(name) {
             return Some(binding);
@@ -152,9 +496,6 @@ impl Environment {
         }
         Err(())
     }
-    pub fn get_std_simulator_string(&self) -> String {
-        format!("{}", self.std_simulator.join(""))
-    }
     // pub fn update(&mut self, name: &String) {
 
     // }
@@ -0,0 +1,135 @@
+use std::fmt::Display;
+
+use smol_str::SmolStr;
+
+use crate::error_emit::{ErrorReporter, FileSpan};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuntimeErrorKind {
+    TypeMismatch,
+    UndefinedVariable,
+    UndefinedFunction,
+    UndefinedStruct,
+    Redeclaration,
+    ArgumentMismatch,
+    IndexOutOfBounds,
+    NullPointer,
+    DanglingPointer,
+    DivisionByZero,
+    InvalidInput,
+    NativeError,
+    MissingMain,
+    StackOverflow,
+}
+
+impl Display for RuntimeErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let string = match self {
+            RuntimeErrorKind::TypeMismatch => "type mismatch",
+            RuntimeErrorKind::UndefinedVariable => "undefined variable",
+            RuntimeErrorKind::UndefinedFunction => "undefined function",
+            RuntimeErrorKind::UndefinedStruct => "undefined struct",
+            RuntimeErrorKind::Redeclaration => "redeclaration",
+            RuntimeErrorKind::ArgumentMismatch => "argument mismatch",
+            RuntimeErrorKind::IndexOutOfBounds => "index out of bounds",
+            RuntimeErrorKind::NullPointer => "null pointer dereference",
+            RuntimeErrorKind::DanglingPointer => "dangling pointer",
+            RuntimeErrorKind::DivisionByZero => "division by zero",
+            RuntimeErrorKind::InvalidInput => "invalid input",
+            RuntimeErrorKind::NativeError => "native function failed",
+            RuntimeErrorKind::MissingMain => "missing main function",
+            RuntimeErrorKind::StackOverflow => "stack overflow",
+        };
+        f.write_str(string)
+    }
+}
+
+/// a function call which was active when the error occurred
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackFrame {
+    pub function: SmolStr,
+    // span of the call expression
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct RuntimeError {
+    pub kind: RuntimeErrorKind,
+    pub message: String,
+    pub start: usize,
+    pub end: usize,
+    // innermost call first
+    pub backtrace: Vec<StackFrame>,
+}
+
+impl RuntimeError {
+    pub fn new(kind: RuntimeErrorKind, message: String, start: usize, end: usize) -> Self {
+        Self {
+            kind,
+            message,
+            start,
+            end,
+            backtrace: vec![],
+        }
+    }
+
+    pub(crate) fn with_frame(mut self, function: SmolStr, start: usize, end: usize) -> Self {
+        self.backtrace.push(StackFrame {
+            function,
+            start,
+            end,
+        });
+        self
+    }
+
+    /// render the error and the call sites in the backtrace against the source file,
+    /// `file_name` is the path printed in the report
+    pub fn report<'a>(&self, file_name: &'a str, source_file: &'a str) -> ErrorReporter<'a> {
+        let mut error_reporter = ErrorReporter::new();
+        let file_id = error_reporter.add_file(file_name, source_file.to_string());
+        error_reporter.add_labeled_diagnostic(
+            FileSpan::new(file_id, self.start..self.end),
+            format!("runtime error: {}", self.kind),
+            self.message.clone(),
+        );
+        for (frame, count) in self.collapsed_backtrace() {
+            error_reporter.add_secondary_diagnostic(
+                FileSpan::new(file_id, frame.start..frame.end),
+                format!("in the call to `{}`{}", frame.function, repeated(count)),
+            );
+        }
+        error_reporter
+    }
+
+    // consecutive frames with the same call site, so a recursion is listed once with its count
+    fn collapsed_backtrace(&self) -> Vec<(&StackFrame, usize)> {
+        let mut frames: Vec<(&StackFrame, usize)> = vec![];
+        for frame in self.backtrace.iter() {
+            match frames.last_mut() {
+                Some((last, count)) if *last == frame => *count += 1,
+                _ => frames.push((frame, 1)),
+            }
+        }
+        frames
+    }
+}
+
+fn repeated(count: usize) -> String {
+    if count > 1 {
+        format!(" ({} times)", count)
+    } else {
+        String::new()
+    }
+}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at {}..{}", self.message, self.start, self.end)?;
+        for (frame, count) in self.collapsed_backtrace() {
+            write!(f, "\n  in `{}` called at {}..{}", frame.function, frame.start, frame.end)?;
+            f.write_str(&repeated(count))?;
+        }
+        Ok(())
+    }
+}
@@ -1,29 +1,38 @@
 use std::{cell::RefCell, rc::Rc};
 
-use super::env::{ArrayType, Binding, Environment, IntoLiteral, LiteralType};
-use crate::{parser::ast::*, util::variant_eq};
+use super::env::{
+    Address, ArrayType, Binding, Environment, IntoLiteral, LiteralType, Pointer, StructType,
+};
+use super::error::{RuntimeError, RuntimeErrorKind};
+use crate::{
+    native::{NativeError, NativeValue},
+    parser::{ast::*, Codespan},
+    util::variant_eq,
+    MAX_CALL_DEPTH,
+};
 use fxhash::FxHashMap;
 use smol_str::SmolStr;
 pub trait Evaluate {
-    fn evaluate(&self, env: &mut Environment) -> Result<Binding, ()>;
+    fn evaluate(&self, env: &mut Environment) -> Result<Binding, RuntimeError>;
 }
 
 impl Evaluate for Program {
-    fn evaluate(&self, env: &mut Environment) -> Result<Binding, ()> {
-        for decl in self.declarations.iter() {
-            match decl.evaluate(env) {
-                Ok(_) => {}
-                Err(_) => {
-                    return Err(());
-                }
-            }
+    fn evaluate(&self, env: &mut Environment) -> Result<Binding, RuntimeError> {
+        // functions are defined ahead, so a global initializer can call the one declared after
+        // it, like the vm's function table
+        let (functions, others): (Vec<_>, Vec<_>) = self
+            .declarations
+            .iter()
+            .partition(|decl| matches!(decl, Declaration::FunctionDeclaration(_)));
+        for decl in functions.into_iter().chain(others) {
+            decl.evaluate(env)?;
         }
         Ok(Binding::Void)
     }
 }
 
 impl Evaluate for Declaration {
-    fn evaluate(&self, env: &mut Environment) -> Result<Binding, ()> {
+    fn evaluate(&self, env: &mut Environment) -> Result<Binding, RuntimeError> {
         match self {
             Declaration::FunctionDeclaration(func) => {
                 // match func.id.value.as_ref() {
@@ -36,241 +45,418 @@ impl Evaluate for Declaration {
                 env.define(
                     func.id.value.clone(),
                     Binding::FunctionDeclaration(std::rc::Rc::new(func.clone())),
-                )?;
+                )
+                .map_err(|_| {
+                    RuntimeError::new(
+                        RuntimeErrorKind::Redeclaration,
+                        format!("function `{}` has already defined", func.id.value),
+                        func.id.start,
+                        func.id.end,
+                    )
+                })?;
             }
+            // the checker matches a prototype with the definition, which is called instead
+            Declaration::FunctionPrototype(_) => {}
             Declaration::VarDeclaration(var) => {
                 var.evaluate(env)?;
             }
+            Declaration::StructDeclaration(decl) => {
+                if env.structs.contains_key(&decl.id.value) {
+                    return Err(RuntimeError::new(
+                        RuntimeErrorKind::Redeclaration,
+                        format!("struct `{}` has already defined", decl.id.value),
+                        decl.id.start,
+                        decl.id.end,
+                    ));
+                }
+                env.structs
+                    .insert(decl.id.value.clone(), Rc::new(decl.clone()));
+            }
+            Declaration::Error(_) => unreachable!("syntax errors are reported by the parser"),
         }
         Ok(Binding::Void)
     }
 }
 
 impl VarDeclaration {
-    fn evaluate(&self, env: &mut Environment) -> Result<Binding, ()> {
-        // let local_scope = env.scope_stack.last_mut().unwrap();
+    fn evaluate(&self, env: &mut Environment) -> Result<Binding, RuntimeError> {
         if env
             .scope_stack
             .last_mut()
             .unwrap()
             .contains_key(&self.id.value)
         {
-            println!("Can not declare a variable twice");
-            return Err(());
-        } else {
-            // TODO: need type checking here
-            match self.num {
-                None => match self.type_specifier.kind {
-                    TypeSpecifierKind::Int => {
-                        let init = if let Some(ref initializer) = self.initializer {
-                            initializer.evaluate(env)?
-                        } else {
-                            Binding::NumberLiteral(0)
-                        };
-                        env.scope_stack
-                            .last_mut()
-                            .unwrap()
-                            .insert(self.id.value.clone(), init);
-                    }
-                    TypeSpecifierKind::Boolean => {
-                        let init = if let Some(ref initializer) = self.initializer {
-                            initializer.evaluate(env)?
-                        } else {
-                            Binding::BooleanLiteral(false)
-                        };
-                        env.scope_stack
-                            .last_mut()
-                            .unwrap()
-                            .insert(self.id.value.clone(), init);
-                    }
-                    TypeSpecifierKind::Void => {
-                        let init = if let Some(ref initializer) = self.initializer {
-                            initializer.evaluate(env)?
-                        } else {
-                            Binding::Void
-                        };
-                        env.scope_stack
-                            .last_mut()
-                            .unwrap()
-                            .insert(self.id.value.clone(), init);
-                    }
-                },
-                Some(ref num) => {
-                    // TODO: here also need to handle with declaration with initializer
-                    let length = num.value as usize;
-
-                    match self.type_specifier.kind {
-                        TypeSpecifierKind::Int => {
-                            let initialized_array = self
-                                .get_initialized_array(env, &self.type_specifier.kind, length)?
-                                .into_iter()
-                                .map(|item| match item {
-                                    LiteralType::Boolean(_) => {
-                                        unreachable!();
-                                    }
-                                    LiteralType::Number(n) => n,
-                                })
-                                .collect::<Vec<_>>();
-                            env.scope_stack.last_mut().unwrap().insert(
-                                self.id.value.clone(),
-                                Binding::Array(ArrayType::Number {
-                                    length,
-                                    array: Rc::new(RefCell::new(initialized_array)),
-                                }),
-                            );
-                        }
-                        TypeSpecifierKind::Boolean => {
-                            let initialized_array = self
-                                .get_initialized_array(env, &self.type_specifier.kind, length)?
-                                .into_iter()
-                                .map(|item| match item {
-                                    LiteralType::Boolean(n) => n,
-                                    LiteralType::Number(_) => {
-                                        unreachable!();
-                                    }
-                                })
-                                .collect::<Vec<_>>();
-                            env.scope_stack.last_mut().unwrap().insert(
-                                self.id.value.clone(),
-                                Binding::Array(ArrayType::Boolean {
-                                    length,
-                                    array: Rc::new(RefCell::new(initialized_array)),
-                                }),
-                            );
-                        }
-                        _ => {
-                            return Err(());
-                        }
+            return Err(RuntimeError::new(
+                RuntimeErrorKind::Redeclaration,
+                format!("can not declare variable `{}` twice", self.id.value),
+                self.id.start,
+                self.id.end,
+            ));
+        }
+        let binding = if self.dimensions.is_empty() {
+            let default = self.default_binding(env)?;
+            match self.initializer {
+                Some(ref initializer) => {
+                    let init = initializer.evaluate(env)?;
+                    if !binding_matches(&default, &init) {
+                        return Err(type_mismatch(&default, &init, initializer));
                     }
+                    converted(&default, &init)
                 }
+                None => default,
             }
+        } else {
+            Binding::Array(self.new_array(
+                env,
+                &self.dimensions,
+                self.array_initializer.as_deref(),
+            )?)
+        };
+        env.scope_stack
+            .last_mut()
+            .unwrap()
+            .insert(self.id.value.clone(), binding);
+        Ok(Binding::Void)
+    }
+
+    fn default_binding(&self, env: &mut Environment) -> Result<Binding, RuntimeError> {
+        Ok(match self.type_specifier.kind {
+            TypeSpecifierKind::Int => Binding::NumberLiteral(0),
+            TypeSpecifierKind::Boolean => Binding::BooleanLiteral(false),
+            TypeSpecifierKind::Char => Binding::CharLiteral('\0'),
+            TypeSpecifierKind::Void => Binding::Void,
+            TypeSpecifierKind::Struct(ref name) => {
+                Binding::Struct(self.default_struct(env, name)?)
+            }
+            TypeSpecifierKind::Pointer(_) => Binding::Pointer(Pointer::null()),
+        })
+    }
+
+    /// every field is set to its default value, the arrays in the struct are new arrays
+    fn default_struct(
+        &self,
+        env: &mut Environment,
+        name: &SmolStr,
+    ) -> Result<StructType, RuntimeError> {
+        let decl = match env.structs.get(name) {
+            Some(decl) => decl.clone(),
+            None => {
+                return Err(RuntimeError::new(
+                    RuntimeErrorKind::UndefinedStruct,
+                    format!("cannot find struct `{}` in this scope", name),
+                    self.type_specifier.start,
+                    self.type_specifier.end,
+                ));
+            }
+        };
+        let mut fields = Vec::with_capacity(decl.fields.len());
+        for field in decl.fields.iter() {
+            let binding = if field.dimensions.is_empty() {
+                field.default_binding(env)?
+            } else {
+                Binding::Array(field.new_array(env, &field.dimensions, None)?)
+            };
+            fields.push((field.id.value.clone(), binding));
         }
+        Ok(StructType {
+            name: name.clone(),
+            fields: Rc::new(RefCell::new(fields)),
+        })
+    }
 
-        Ok(Binding::Void)
+    /// every row of a multi-dimensional array is a new array, so the rows don't share elements
+    fn new_array(
+        &self,
+        env: &mut Environment,
+        dimensions: &[ArraySize],
+        initializer: Option<&[Initializer]>,
+    ) -> Result<ArrayType, RuntimeError> {
+        // a size only names a global, a local with the same name is ignored
+        let length = match &dimensions[0] {
+            ArraySize::Number(num) => num.value,
+            ArraySize::Constant(id) => match env.scope_stack[0].get(&id.value) {
+                Some(Binding::NumberLiteral(value)) => *value,
+                _ => {
+                    return Err(RuntimeError::new(
+                        RuntimeErrorKind::UndefinedVariable,
+                        format!("`{}` isn't a global `const int`", id.value),
+                        id.start,
+                        id.end,
+                    ));
+                }
+            },
+        } as usize;
+        if dimensions.len() > 1 {
+            let mut rows = Vec::with_capacity(length);
+            for i in 0..length {
+                let row_initializer = match initializer.and_then(|init| init.get(i)) {
+                    Some(Initializer::List(list)) => Some(&list.elements[..]),
+                    Some(Initializer::Expression(_)) => {
+                        unreachable!("rows are initialized by lists")
+                    }
+                    None => None,
+                };
+                rows.push(self.new_array(env, &dimensions[1..], row_initializer)?);
+            }
+            return Ok(ArrayType::Array {
+                length,
+                array: Rc::new(RefCell::new(rows)),
+            });
+        }
+        if let TypeSpecifierKind::Struct(ref name) = self.type_specifier.kind {
+            return Ok(ArrayType::Struct {
+                length,
+                name: name.clone(),
+                array: Rc::new(RefCell::new(
+                    self.get_initialized_structs(env, name, length, initializer)?,
+                )),
+            });
+        }
+        let initialized_array = self
+            .get_initialized_array(env, length, initializer)?
+            .into_iter();
+        Ok(match self.type_specifier.kind {
+            TypeSpecifierKind::Int => ArrayType::Number {
+                length,
+                array: Rc::new(RefCell::new(
+                    initialized_array
+                        .map(|item| match item {
+                            LiteralType::Number(n) => n,
+                            _ => unreachable!(),
+                        })
+                        .collect(),
+                )),
+            },
+            TypeSpecifierKind::Boolean => ArrayType::Boolean {
+                length,
+                array: Rc::new(RefCell::new(
+                    initialized_array
+                        .map(|item| match item {
+                            LiteralType::Boolean(b) => b,
+                            _ => unreachable!(),
+                        })
+                        .collect(),
+                )),
+            },
+            TypeSpecifierKind::Char => ArrayType::Char {
+                length,
+                array: Rc::new(RefCell::new(
+                    initialized_array
+                        .map(|item| match item {
+                            LiteralType::Char(c) => c,
+                            _ => unreachable!(),
+                        })
+                        .collect(),
+                )),
+            },
+            TypeSpecifierKind::Void
+            | TypeSpecifierKind::Struct(_)
+            | TypeSpecifierKind::Pointer(_) => unreachable!(),
+        })
     }
-    fn get_initialized_array(
+
+    /// the elements are copies of the initializers, the rest are default structs
+    fn get_initialized_structs(
         &self,
         env: &mut Environment,
-        typ: &TypeSpecifierKind,
+        name: &SmolStr,
         length: usize,
-    ) -> Result<Vec<LiteralType>, ()> {
-        if let Some(ref init) = self.array_initializer {
-            // TODO: if the initializer length is greater than the length , should warn
-            let mut vec = vec![0.get_literal(); length];
-            let len = length.min(init.len());
-            match typ {
-                TypeSpecifierKind::Int => {
-                    for i in 0..len {
-                        vec[i] = init[i].evaluate(env)?.get_literal();
+        initializer: Option<&[Initializer]>,
+    ) -> Result<Vec<StructType>, RuntimeError> {
+        let mut vec = Vec::with_capacity(length);
+        for i in 0..length {
+            let default = self.default_struct(env, name)?;
+            let element = match initializer.and_then(|init| init.get(i)) {
+                Some(Initializer::Expression(expr)) => match expr.evaluate(env)? {
+                    Binding::Struct(value) if value.name == *name => value.deep_copy(),
+                    binding => {
+                        return Err(type_mismatch(&Binding::Struct(default), &binding, expr));
                     }
-                    Ok(vec)
-                }
-                TypeSpecifierKind::Boolean => {
-                    return Err(());
-                }
-                _ => {
-                    return Err(());
+                },
+                Some(Initializer::List(_)) => {
+                    unreachable!("elements are initialized by expressions")
                 }
-            }
-        } else {
-            match typ {
-                TypeSpecifierKind::Int => Ok(vec![0.get_literal(); length]),
-                TypeSpecifierKind::Boolean => Ok(vec![false.get_literal(); length]),
-                _ => {
-                    println!("the array only support int and boolean now!");
-                    Err(())
+                None => default,
+            };
+            vec.push(element);
+        }
+        Ok(vec)
+    }
+
+    fn get_initialized_array(
+        &self,
+        env: &mut Environment,
+        length: usize,
+        initializer: Option<&[Initializer]>,
+    ) -> Result<Vec<LiteralType>, RuntimeError> {
+        let default = self.default_binding(env)?;
+        if let Binding::Void | Binding::Pointer(_) = default {
+            return Err(RuntimeError::new(
+                RuntimeErrorKind::TypeMismatch,
+                "the array only support int, boolean and char now!".into(),
+                self.type_specifier.start,
+                self.type_specifier.end,
+            ));
+        }
+        let mut vec = vec![default.get_literal(); length];
+        if let Some(init) = initializer {
+            // the type checker rejects more initializers than elements
+            for (item, init) in vec.iter_mut().zip(init.iter()) {
+                let expr = match init {
+                    Initializer::Expression(expr) => expr,
+                    Initializer::List(_) => unreachable!("elements are initialized by expressions"),
+                };
+                let binding = expr.evaluate(env)?;
+                if !variant_eq(&binding, &default) {
+                    return Err(type_mismatch(&default, &binding, expr));
                 }
+                *item = binding.get_literal();
             }
         }
+        Ok(vec)
     }
 }
 
-// TODO: check the nested returnStatement return binding
+/// how a statement completed, the jumps are propagated up to the loop or the function that
+/// handles them
+#[derive(Debug)]
+pub(crate) enum Completion {
+    Normal,
+    Return(Binding),
+    Break,
+    Continue,
+}
+
 impl Statement {
-    fn evaluate(&self, env: &mut Environment) -> Result<Option<Binding>, ()> {
+    pub(crate) fn evaluate(&self, env: &mut Environment) -> Result<Completion, RuntimeError> {
         match self {
             Statement::CompoundStatement(stmt) => stmt.evaluate(env),
             Statement::ExpressionStatement(stmt) => {
-                if let Err(_) = stmt.evaluate(env) {
-                    Err(())
-                } else {
-                    Ok(None)
-                }
+                stmt.evaluate(env)?;
+                Ok(Completion::Normal)
             }
             Statement::SelectionStatement(stmt) => {
-                if let Ok(binding) = stmt.test.evaluate(env) {
-                    match binding {
-                        Binding::BooleanLiteral(value) => {
-                            if value {
-                                return stmt.consequent.evaluate(env);
-                            } else if let Some(alternative) = &stmt.alternative {
-                                return alternative.evaluate(env);
-                            } else {
-                                return Ok(None);
-                            }
-                        }
-                        _ => {
-                            println!("test expression should be an boolean expression");
-                            return Err(());
-                        }
-                    }
+                if evaluate_test(&stmt.test, env)? {
+                    stmt.consequent.evaluate(env)
+                } else if let Some(alternative) = &stmt.alternative {
+                    alternative.evaluate(env)
                 } else {
-                    return Err(());
-                };
+                    Ok(Completion::Normal)
+                }
             }
             Statement::IterationStatement(stmt) => {
-                while let Ok(binding) = stmt.test.evaluate(env) {
-                    match binding {
-                        Binding::BooleanLiteral(value) => {
-                            if value {
-                                match stmt.body.evaluate(env) {
-                                    Ok(option_binding) => {
-                                        if let Some(binding) = option_binding {
-                                            return Ok(Some(binding));
-                                        }
-                                    }
-                                    Err(_) => {
-                                        return Err(());
-                                    }
-                                }
-                            } else {
-                                break;
-                            }
-                        }
-                        _ => {
-                            println!("while test expression should be an boolean expression");
-                            return Err(());
+                while evaluate_test(&stmt.test, env)? {
+                    match stmt.body.evaluate(env)? {
+                        Completion::Break => break,
+                        Completion::Normal | Completion::Continue => {}
+                        completion @ Completion::Return(_) => return Ok(completion),
+                    }
+                }
+                Ok(Completion::Normal)
+            }
+            Statement::ForStatement(stmt) => {
+                if let Some(ref init) = stmt.init {
+                    init.evaluate(env)?;
+                }
+                loop {
+                    if let Some(ref test) = stmt.test {
+                        if !evaluate_test(test, env)? {
+                            break;
                         }
                     }
+                    match stmt.body.evaluate(env)? {
+                        Completion::Break => break,
+                        Completion::Normal | Completion::Continue => {}
+                        completion @ Completion::Return(_) => return Ok(completion),
+                    }
+                    if let Some(ref update) = stmt.update {
+                        update.evaluate(env)?;
+                    }
                 }
-                Ok(None)
+                Ok(Completion::Normal)
             }
-            Statement::ReturnStatement(stmt) => {
-                if let Ok(binding) = stmt.evaluate(env) {
-                    Ok(Some(binding))
-                } else {
-                    Err(())
+            Statement::DoWhileStatement(stmt) => {
+                loop {
+                    match stmt.body.evaluate(env)? {
+                        Completion::Break => break,
+                        Completion::Normal | Completion::Continue => {}
+                        completion @ Completion::Return(_) => return Ok(completion),
+                    }
+                    if !evaluate_test(&stmt.test, env)? {
+                        break;
+                    }
                 }
+                Ok(Completion::Normal)
             }
+            Statement::SwitchStatement(stmt) => {
+                let value = match stmt.test.evaluate(env)? {
+                    Binding::NumberLiteral(value) => value,
+                    binding => {
+                        return Err(RuntimeError::new(
+                            RuntimeErrorKind::TypeMismatch,
+                            format!(
+                                "switch expression should be an int expression, found {}",
+                                type_name(&binding)
+                            ),
+                            stmt.test.start(),
+                            stmt.test.end(),
+                        ))
+                    }
+                };
+                let matched = stmt
+                    .cases
+                    .iter()
+                    .position(|case| matches!(case.label, Some(ref label) if label.value == value))
+                    .or_else(|| stmt.cases.iter().position(|case| case.label.is_none()));
+                if let Some(first) = matched {
+                    // the statements after the matched label run until `break`
+                    for case in stmt.cases[first..].iter() {
+                        for statement in case.statements.iter() {
+                            match statement.evaluate(env)? {
+                                Completion::Normal => {}
+                                Completion::Break => return Ok(Completion::Normal),
+                                completion => return Ok(completion),
+                            }
+                        }
+                    }
+                }
+                Ok(Completion::Normal)
+            }
+            Statement::BreakStatement(_) => Ok(Completion::Break),
+            Statement::ContinueStatement(_) => Ok(Completion::Continue),
+            Statement::ReturnStatement(stmt) => Ok(Completion::Return(stmt.evaluate(env)?)),
+            Statement::Error(_) => unreachable!("syntax errors are reported by the parser"),
         }
     }
 }
 
+/// the test expression of `if` and the loops must be evaluated to a boolean
+fn evaluate_test(test: &Expression, env: &mut Environment) -> Result<bool, RuntimeError> {
+    match test.evaluate(env)? {
+        Binding::BooleanLiteral(value) => Ok(value),
+        binding => Err(RuntimeError::new(
+            RuntimeErrorKind::TypeMismatch,
+            format!(
+                "test expression should be an boolean expression, found {}",
+                type_name(&binding)
+            ),
+            test.start(),
+            test.end(),
+        )),
+    }
+}
+
 impl Evaluate for ExpressionStatement {
-    fn evaluate(&self, env: &mut Environment) -> Result<Binding, ()> {
+    fn evaluate(&self, env: &mut Environment) -> Result<Binding, RuntimeError> {
         if let Some(ref expr) = self.expression {
-            if let Ok(binding) = expr.evaluate(env) {
-                return Ok(binding);
-            } else {
-                return Err(());
-            }
+            expr.evaluate(env)
         } else {
-            return Ok(Binding::Void);
+            Ok(Binding::Void)
         }
     }
 }
 impl Evaluate for ReturnStatement {
-    fn evaluate(&self, env: &mut Environment) -> Result<Binding, ()> {
+    fn evaluate(&self, env: &mut Environment) -> Result<Binding, RuntimeError> {
         if let Some(expr) = &self.expression {
             expr.evaluate(env)
         } else {
@@ -279,137 +465,147 @@ impl Evaluate for ReturnStatement {
     }
 }
 impl CompoundStatement {
-    pub fn evaluate(&self, env: &mut Environment) -> Result<Option<Binding>, ()> {
+    pub(crate) fn evaluate(&self, env: &mut Environment) -> Result<Completion, RuntimeError> {
         // before every callExpression we add the binding to env.call_expression_binging, after every compoundStatement we
         // extend the params binding and clear the env.call_expression binding
         let scope = {
             let mut map = FxHashMap::default();
             while let Some((key, binding)) = env.call_expression_binding.pop() {
-                map.insert(SmolStr::from(key), binding);
+                map.insert(key, binding);
             }
             map
         };
-        env.scope_stack.push(scope);
+        env.push_scope(scope);
+        let result = self.evaluate_in_scope(env);
+        // println!("{}:{:?}", env.scope_stack.len(), env.scope_stack.last());
+        env.pop_scope();
+        result
+    }
+
+    fn evaluate_in_scope(&self, env: &mut Environment) -> Result<Completion, RuntimeError> {
         for decl in self.local_declaration.iter() {
-            if let Err(_) = decl.evaluate(env) {
-                return Err(());
-            }
+            decl.evaluate(env)?;
         }
-        let mut option_binding = None;
         for stat in self.statement_list.iter() {
-            match stat.evaluate(env) {
-                Ok(None) => {}
-                Ok(Some(binding)) => {
-                    option_binding = Some(binding);
-                    break;
-                }
-                _ => {
-                    env.scope_stack.pop();
-                    return Err(());
-                }
+            match stat.evaluate(env)? {
+                Completion::Normal => {}
+                completion => return Ok(completion),
             }
         }
-        // println!("{}:{:?}", env.scope_stack.len(), env.scope_stack.last());
-        env.scope_stack.pop();
-        Ok(option_binding)
+        Ok(Completion::Normal)
     }
 }
 
 impl Evaluate for Expression {
-    fn evaluate(&self, env: &mut Environment) -> Result<Binding, ()> {
+    fn evaluate(&self, env: &mut Environment) -> Result<Binding, RuntimeError> {
         match self {
             Expression::BinaryExpression(binary_expr) => binary_expr.evaluate(env),
             Expression::Factor(factor) => factor.evaluate(env),
             Expression::Assignment(assignment) => assignment.evaluate(env),
             Expression::LogicExpression(logic_expr) => logic_expr.evaluate(env),
+            Expression::Conditional(conditional_expr) => {
+                if evaluate_test(&conditional_expr.test, env)? {
+                    conditional_expr.consequent.evaluate(env)
+                } else {
+                    conditional_expr.alternative.evaluate(env)
+                }
+            }
             Expression::UnaryExpression(unary_expr) => unary_expr.evaluate(env),
+            Expression::UpdateExpression(update_expr) => update_expr.evaluate(env),
+            Expression::Address(address_expr) => address_expr.evaluate(env),
+            Expression::Dereference(dereference_expr) => {
+                Ok(dereference(env, dereference_expr)?.read(env))
+            }
         }
     }
 }
 
 impl Evaluate for AssignmentExpression {
-    fn evaluate(&self, env: &mut Environment) -> Result<Binding, ()> {
-        if let Some(ref expr) = self.lhs.expression {
-            let index_eval = expr.evaluate(env)?;
+    fn evaluate(&self, env: &mut Environment) -> Result<Binding, RuntimeError> {
+        // this is an assignment to an array element, a struct field or through a pointer
+        if let Some(place) = evaluate_target(env, &self.lhs)? {
+            // the indices are evaluated once by the compound assignment, the element is read
+            // before the rhs like `a[i] = a[i] + rhs`
+            let current = self.operation.as_ref().map(|_| place.read(env));
             let rhs_eval = self.rhs.evaluate(env)?;
-            if let Binding::NumberLiteral(index) = index_eval {
-                match env.get_mut(&self.lhs.id.value).ok_or_else(|| {
-                    println!(
-                        "the variable {:?} can't be found in this scope",
-                        self.lhs.id.value
-                    );
-                })? {
-                    Binding::Array(arr) => match arr {
-                        ArrayType::Boolean { array, .. }
-                            if matches!(rhs_eval, Binding::BooleanLiteral(_)) =>
-                        {
-                            array.borrow_mut()[index as usize] =
-                                rhs_eval.clone().into_boolean_literal().unwrap();
-                            Ok(rhs_eval)
-                        }
-                        ArrayType::Number { array, .. }
-                            if matches!(rhs_eval, Binding::NumberLiteral(_)) =>
-                        {
-                            array.borrow_mut()[index as usize] =
-                                rhs_eval.clone().into_number_literal().unwrap();
-                            Ok(rhs_eval)
-                        }
-                        _ => Err(()),
-                    },
-                    _ => {
-                        println!(
-                            "the variable {:?} should be found in this scope",
-                            self.lhs.id.value
-                        );
-                        Err(())
-                    }
+            let rhs_eval = match (&self.operation, current) {
+                (Some(operation), Some(current)) => {
+                    apply_operation(&current, &rhs_eval, operation, self.start, self.end)?
                 }
-            } else {
-                unimplemented!() // TODO
-            }
-        // env.get_mut();
-        // this is a array expression assignment
+                _ => rhs_eval,
+            };
+            place.write(env, &rhs_eval, self.rhs.start(), self.rhs.end())?;
+            Ok(rhs_eval)
         } else {
+            let lhs = match self.lhs {
+                Target::Var(ref var) => var,
+                Target::Dereference(_) => unreachable!("a dereference is a place"),
+            };
+            let current = match self.operation {
+                Some(_) => Some(lookup_variable(env, &lhs.id)?.clone()),
+                None => None,
+            };
             let rhs_eval = self.rhs.evaluate(env)?;
-            let lhs_binding = env.get_mut(&self.lhs.id.value).ok_or_else(|| {
-                println!(
-                    "the variable {:?} can't be found in this module",
-                    self.lhs.id.value
-                );
-            })?;
-            if variant_eq(lhs_binding, &rhs_eval) {
-                *lhs_binding = rhs_eval;
-            } else {
-                println!(
-                    "{}",
-                    format!("left is {:?} right is {:?}", lhs_binding, rhs_eval)
-                );
-                return Err(());
+            let rhs_eval = match (&self.operation, current) {
+                (Some(operation), Some(current)) => {
+                    apply_operation(&current, &rhs_eval, operation, self.start, self.end)?
+                }
+                _ => rhs_eval,
+            };
+            let lhs_binding = lookup_variable(env, &lhs.id)?;
+            if !binding_matches(lhs_binding, &rhs_eval) {
+                return Err(type_mismatch(lhs_binding, &rhs_eval, &self.rhs));
             }
+            *lhs_binding = converted(lhs_binding, &rhs_eval);
             Ok(lhs_binding.clone())
         }
     }
 }
 
+impl Evaluate for UpdateExpression {
+    fn evaluate(&self, env: &mut Environment) -> Result<Binding, RuntimeError> {
+        let one = Binding::NumberLiteral(1);
+        let (previous, updated) = if let Some(place) = evaluate_target(env, &self.target)? {
+            let previous = place.read(env);
+            let updated = apply_operation(&previous, &one, &self.operation, self.start, self.end)?;
+            // the operation only succeeds for an int or a pointer, so it fits in the place
+            place.write(env, &updated, self.start, self.end)?;
+            (previous, updated)
+        } else {
+            let var = match self.target {
+                Target::Var(ref var) => var,
+                Target::Dereference(_) => unreachable!("a dereference is a place"),
+            };
+            let binding = lookup_variable(env, &var.id)?;
+            let updated = apply_operation(binding, &one, &self.operation, self.start, self.end)?;
+            (std::mem::replace(binding, updated.clone()), updated)
+        };
+        Ok(if self.prefix { updated } else { previous })
+    }
+}
+
 impl Evaluate for BinaryExpression {
-    fn evaluate(&self, env: &mut Environment) -> Result<Binding, ()> {
+    fn evaluate(&self, env: &mut Environment) -> Result<Binding, RuntimeError> {
         let left_eval = &self.left.evaluate(env)?;
         let right_eval = &self.right.evaluate(env)?;
         match (left_eval, right_eval) {
             (Binding::NumberLiteral(_), Binding::NumberLiteral(_)) => {
-                evaluate_binary_expression_literal(left_eval, right_eval, &self.operation)
+                evaluate_binary_expression_literal(left_eval, right_eval, self)
             }
             (Binding::BooleanLiteral(_), Binding::BooleanLiteral(_)) => {
-                evaluate_binary_expression_literal(left_eval, right_eval, &self.operation)
+                evaluate_binary_expression_literal(left_eval, right_eval, self)
+            }
+            (Binding::CharLiteral(_), Binding::CharLiteral(_)) => {
+                evaluate_binary_expression_literal(left_eval, right_eval, self)
             }
             (
                 left_eval @ Binding::NumberLiteral(_) | left_eval @ Binding::BooleanLiteral(_),
                 Binding::Variable(var),
             ) => {
                 if let Some(right_var) = env.get(&SmolStr::from(var)) {
-                    evaluate_binary_expression_literal(left_eval, right_var, &self.operation)
+                    evaluate_binary_expression_literal(left_eval, right_var, self)
                 } else {
-                    Err(())
+                    Err(undefined_variable(var, self.right.start(), self.right.end()))
                 }
             }
             (
@@ -417,166 +613,219 @@ impl Evaluate for BinaryExpression {
                 right_eval @ Binding::NumberLiteral(_) | right_eval @ Binding::BooleanLiteral(_),
             ) => {
                 if let Some(left_var) = env.get(&SmolStr::from(var)) {
-                    evaluate_binary_expression_literal(left_var, right_eval, &self.operation)
+                    evaluate_binary_expression_literal(left_var, right_eval, self)
                 } else {
-                    Err(())
+                    Err(undefined_variable(var, self.left.start(), self.left.end()))
                 }
             }
-            _ => Err(()),
+            // pointer arithmetic and comparisons, other operands are reported as unsupported
+            _ => evaluate_binary_expression_literal(left_eval, right_eval, self),
         }
     }
 }
 impl Evaluate for LogicExpression {
-    fn evaluate(&self, env: &mut Environment) -> Result<Binding, ()> {
+    fn evaluate(&self, env: &mut Environment) -> Result<Binding, RuntimeError> {
+        let left_eval = evaluate_logic_operand(&self.left, env)?;
         match self.operation {
-            Operation::AND(_, _) => {
-                let left_eval = &self.left.evaluate(env)?;
-                if let Binding::BooleanLiteral(true) = left_eval {
-                    let right_eval = &self.right.evaluate(env)?;
-                    if let Binding::BooleanLiteral(true) = right_eval {
-                        return Ok(Binding::BooleanLiteral(true));
-                    } else if let Binding::BooleanLiteral(false) = right_eval {
-                        return Ok(Binding::BooleanLiteral(false));
-                    } else {
-                        return Err(());
-                    }
-                } else if let Binding::BooleanLiteral(false) = left_eval {
-                    return Ok(Binding::BooleanLiteral(false));
-                } else {
-                    return Err(());
-                }
-            }
-            Operation::OR(_, _) => {
-                let left_eval = &self.left.evaluate(env)?;
-                if let Binding::BooleanLiteral(true) = left_eval {
-                    return Ok(Binding::BooleanLiteral(true));
-                } else if let Binding::BooleanLiteral(false) = left_eval {
-                    let right_eval = &self.right.evaluate(env)?;
-                    if let Binding::BooleanLiteral(true) = right_eval {
-                        return Ok(Binding::BooleanLiteral(true));
-                    } else if let Binding::BooleanLiteral(false) = right_eval {
-                        return Ok(Binding::BooleanLiteral(false));
-                    } else {
-                        return Err(());
-                    }
-                } else {
-                    return Err(());
-                }
-            }
+            Operation::AND(_, _) if !left_eval => Ok(Binding::BooleanLiteral(false)),
+            Operation::OR(_, _) if left_eval => Ok(Binding::BooleanLiteral(true)),
+            Operation::AND(_, _) | Operation::OR(_, _) => Ok(Binding::BooleanLiteral(
+                evaluate_logic_operand(&self.right, env)?,
+            )),
             _ => unimplemented!(), // TODO
         }
     }
 }
 
+fn evaluate_logic_operand(expr: &Expression, env: &mut Environment) -> Result<bool, RuntimeError> {
+    match expr.evaluate(env)? {
+        Binding::BooleanLiteral(value) => Ok(value),
+        binding => Err(RuntimeError::new(
+            RuntimeErrorKind::TypeMismatch,
+            format!(
+                "operand of logic expression should be bool, found {}",
+                type_name(&binding)
+            ),
+            expr.start(),
+            expr.end(),
+        )),
+    }
+}
+
 impl Evaluate for UnaryExpression {
-    fn evaluate(&self, env: &mut Environment) -> Result<Binding, ()> {
-        match self.operation {
-            Operation::NEG(_, _) => {
-                let expr_eval = &self.expression.evaluate(env)?;
-                if let Binding::NumberLiteral(val) = expr_eval {
-                    Ok(Binding::NumberLiteral(-*val))
-                } else {
-                    return Err(());
-                }
+    fn evaluate(&self, env: &mut Environment) -> Result<Binding, RuntimeError> {
+        let binding = self.expression.evaluate(env)?;
+        match (&self.operation, &binding) {
+            (Operation::NOT(_, _), Binding::BooleanLiteral(val)) => {
+                Ok(Binding::BooleanLiteral(!val))
             }
-            Operation::POS(_, _) => {
-                let expr_eval = &self.expression.evaluate(env)?;
-                if let Binding::NumberLiteral(val) = expr_eval {
-                    Ok(Binding::NumberLiteral(*val))
-                } else {
-                    return Err(());
-                }
+            (Operation::NEG(_, _), Binding::NumberLiteral(val)) => {
+                Ok(Binding::NumberLiteral(val.wrapping_neg()))
             }
-            _ => unreachable!(), // TODO
+            (Operation::POS(_, _), Binding::NumberLiteral(val)) => Ok(Binding::NumberLiteral(*val)),
+            (Operation::BITNOT(_, _), Binding::NumberLiteral(val)) => {
+                Ok(Binding::NumberLiteral(!val))
+            }
+            (operation, binding) => Err(RuntimeError::new(
+                RuntimeErrorKind::TypeMismatch,
+                format!(
+                    "operand of unary expression should be {}, found {}",
+                    match operation {
+                        Operation::NOT(_, _) => "bool",
+                        _ => "int",
+                    },
+                    type_name(binding)
+                ),
+                self.expression.start(),
+                self.expression.end(),
+            )),
         }
     }
 }
 
-
 #[inline]
 fn evaluate_binary_expression_literal(
     m: &Binding,
     n: &Binding,
-    op: &Operation,
-) -> Result<Binding, ()> {
+    expr: &BinaryExpression,
+) -> Result<Binding, RuntimeError> {
+    apply_operation(m, n, &expr.operation, expr.start, expr.end)
+}
+
+/// `start` and `end` is the range of the expression, used by errors
+fn apply_operation(
+    m: &Binding,
+    n: &Binding,
+    operation: &Operation,
+    start: usize,
+    end: usize,
+) -> Result<Binding, RuntimeError> {
     match (m, n) {
-        (Binding::NumberLiteral(a), Binding::NumberLiteral(b)) => match op {
-            Operation::PLUS(_, _) => Ok(Binding::NumberLiteral(a + b)),
-            Operation::MINUS(_, _) => Ok(Binding::NumberLiteral(a - b)),
-            Operation::MULTIPLY(_, _) => Ok(Binding::NumberLiteral(a * b)),
-            Operation::DIVIDE(_, _) => Ok(Binding::NumberLiteral(a / b)),
+        (Binding::NumberLiteral(a), Binding::NumberLiteral(b)) => match operation {
+            Operation::PLUS(_, _) => Ok(Binding::NumberLiteral(a.wrapping_add(*b))),
+            Operation::MINUS(_, _) => Ok(Binding::NumberLiteral(a.wrapping_sub(*b))),
+            Operation::MULTIPLY(_, _) => Ok(Binding::NumberLiteral(a.wrapping_mul(*b))),
+            Operation::DIVIDE(_, _) if *b == 0 => Err(RuntimeError::new(
+                RuntimeErrorKind::DivisionByZero,
+                "attempt to divide by zero".into(),
+                start,
+                end,
+            )),
+            Operation::DIVIDE(_, _) => Ok(Binding::NumberLiteral(a.wrapping_div(*b))),
+            Operation::MOD(_, _) if *b == 0 => Err(RuntimeError::new(
+                RuntimeErrorKind::DivisionByZero,
+                "attempt to calculate the remainder with a divisor of zero".into(),
+                start,
+                end,
+            )),
+            Operation::MOD(_, _) => Ok(Binding::NumberLiteral(a.wrapping_rem(*b))),
+            Operation::BITAND(_, _) => Ok(Binding::NumberLiteral(a & b)),
+            Operation::BITOR(_, _) => Ok(Binding::NumberLiteral(a | b)),
+            Operation::BITXOR(_, _) => Ok(Binding::NumberLiteral(a ^ b)),
+            // the shift amount is masked to the width of int like the vm
+            Operation::SHL(_, _) => Ok(Binding::NumberLiteral(a.wrapping_shl(*b as u32))),
+            Operation::SHR(_, _) => Ok(Binding::NumberLiteral(a.wrapping_shr(*b as u32))),
+            Operation::GT(_, _) => Ok(Binding::BooleanLiteral(a > b)),
+            Operation::LT(_, _) => Ok(Binding::BooleanLiteral(a < b)),
+            Operation::GE(_, _) => Ok(Binding::BooleanLiteral(a >= b)),
+            Operation::LE(_, _) => Ok(Binding::BooleanLiteral(a <= b)),
+            Operation::EQ(_, _) => Ok(Binding::BooleanLiteral(a == b)),
+            Operation::NE(_, _) => Ok(Binding::BooleanLiteral(a != b)),
+            _ => Err(unsupported_operands(m, n, operation, start, end)),
+        },
+        (Binding::BooleanLiteral(a), Binding::BooleanLiteral(b)) => match operation {
             Operation::GT(_, _) => Ok(Binding::BooleanLiteral(a > b)),
             Operation::LT(_, _) => Ok(Binding::BooleanLiteral(a < b)),
             Operation::GE(_, _) => Ok(Binding::BooleanLiteral(a >= b)),
             Operation::LE(_, _) => Ok(Binding::BooleanLiteral(a <= b)),
             Operation::EQ(_, _) => Ok(Binding::BooleanLiteral(a == b)),
             Operation::NE(_, _) => Ok(Binding::BooleanLiteral(a != b)),
-            _ => Err(()),
+            _ => Err(unsupported_operands(m, n, operation, start, end)),
         },
-        (Binding::BooleanLiteral(a), Binding::BooleanLiteral(b)) => match op {
+        (Binding::CharLiteral(a), Binding::CharLiteral(b)) => match operation {
             Operation::GT(_, _) => Ok(Binding::BooleanLiteral(a > b)),
             Operation::LT(_, _) => Ok(Binding::BooleanLiteral(a < b)),
             Operation::GE(_, _) => Ok(Binding::BooleanLiteral(a >= b)),
             Operation::LE(_, _) => Ok(Binding::BooleanLiteral(a <= b)),
             Operation::EQ(_, _) => Ok(Binding::BooleanLiteral(a == b)),
             Operation::NE(_, _) => Ok(Binding::BooleanLiteral(a != b)),
-            _ => Err(()),
+            _ => Err(unsupported_operands(m, n, operation, start, end)),
         },
-        _ => Err(()),
+        _ => apply_pointer_operation(m, n, operation, start, end),
+    }
+}
+
+/// `p + 1`, `1 + p`, `p - 1`, `p - q` and the comparisons of pointers, an array is converted
+/// to the pointer to its first element
+fn apply_pointer_operation(
+    m: &Binding,
+    n: &Binding,
+    operation: &Operation,
+    start: usize,
+    end: usize,
+) -> Result<Binding, RuntimeError> {
+    match (m.to_pointer(), n.to_pointer(), operation) {
+        (Some(p), None, Operation::PLUS(_, _)) | (None, Some(p), Operation::PLUS(_, _)) => {
+            match (m, n) {
+                (Binding::NumberLiteral(k), _) | (_, Binding::NumberLiteral(k)) => {
+                    Ok(Binding::Pointer(p.offset_by(*k)))
+                }
+                _ => Err(unsupported_operands(m, n, operation, start, end)),
+            }
+        }
+        (Some(p), None, Operation::MINUS(_, _)) => match n {
+            Binding::NumberLiteral(k) => Ok(Binding::Pointer(p.offset_by(k.wrapping_neg()))),
+            _ => Err(unsupported_operands(m, n, operation, start, end)),
+        },
+        (Some(p), Some(q), Operation::EQ(_, _)) => Ok(Binding::BooleanLiteral(p == q)),
+        (Some(p), Some(q), Operation::NE(_, _)) => Ok(Binding::BooleanLiteral(p != q)),
+        (Some(p), Some(q), _) if !p.same_target(&q) => Err(RuntimeError::new(
+            RuntimeErrorKind::TypeMismatch,
+            format!(
+                "`{}` can't be applied to pointers to different objects",
+                operation
+            ),
+            start,
+            end,
+        )),
+        (Some(p), Some(q), operation) => match operation {
+            Operation::MINUS(_, _) => Ok(Binding::NumberLiteral(p.offset.wrapping_sub(q.offset))),
+            Operation::GT(_, _) => Ok(Binding::BooleanLiteral(p.offset > q.offset)),
+            Operation::LT(_, _) => Ok(Binding::BooleanLiteral(p.offset < q.offset)),
+            Operation::GE(_, _) => Ok(Binding::BooleanLiteral(p.offset >= q.offset)),
+            Operation::LE(_, _) => Ok(Binding::BooleanLiteral(p.offset <= q.offset)),
+            _ => Err(unsupported_operands(m, n, operation, start, end)),
+        },
+        _ => Err(unsupported_operands(m, n, operation, start, end)),
     }
 }
 
 impl Evaluate for Factor {
-    fn evaluate(&self, env: &mut Environment) -> Result<Binding, ()> {
+    fn evaluate(&self, env: &mut Environment) -> Result<Binding, RuntimeError> {
         match self {
             Factor::Expression(expr) => expr.evaluate(env),
 
             Factor::CallExpression(call_expr) => call_expr.evaluate(env),
             Factor::NumberLiteral(literal) => Ok(Binding::NumberLiteral(literal.value)),
             Factor::BooleanLiteral(literal) => Ok(Binding::BooleanLiteral(literal.value)),
-            Factor::Var(var) => {
-                // let index_eval = expr.evaluate(env)?;
-                match var.expression {
-                    Some(ref expr) => {
-                        let index_eval = expr.evaluate(env)?;
-                        if let Binding::NumberLiteral(index) = index_eval {
-                            match env.get_mut(&var.id.value).ok_or_else(|| {
-                                println!(
-                                    "the variable {:?} can't be found in this scope",
-                                    var.id.value
-                                );
-                            })? {
-                                Binding::Array(arr) => match arr {
-                                    ArrayType::Boolean { array, .. } => {
-                                        Ok(Binding::BooleanLiteral(array.borrow()[index as usize]))
-                                    }
-                                    ArrayType::Number { array, .. } => {
-                                        Ok(Binding::NumberLiteral(array.borrow()[index as usize]))
-                                    }
-                                },
-                                _ => {
-                                    panic!("only array type can be indexed");
-                                    // Err(())
-                                }
-                            }
-                        } else {
-                            panic!("index of array should be number");
-                        }
-                    }
-                    None => env
-                        .get(&var.id.value)
-                        .and_then(|bind| Some(bind.clone()))
-                        .ok_or_else(|| {}),
-                }
-
-                // // let index =
-                //
+            Factor::CharLiteral(literal) => Ok(Binding::CharLiteral(literal.value)),
+            // every evaluation creates a new array, so writing to it doesn't change the literal
+            Factor::StringLiteral(literal) => {
+                let array: Vec<char> = literal.value.chars().chain(std::iter::once('\0')).collect();
+                Ok(Binding::Array(ArrayType::Char {
+                    length: array.len(),
+                    array: Rc::new(RefCell::new(array)),
+                }))
+            }
+            Factor::Var(var) if var.accessors.is_empty() => {
+                lookup_variable(env, &var.id).map(|binding| binding.clone())
             }
+            Factor::Var(var) => Ok(evaluate_place(env, var)?.read(env)),
         }
     }
 }
 impl Evaluate for CallExpression {
-    fn evaluate(&self, env: &mut Environment) -> Result<Binding, ()> {
+    fn evaluate(&self, env: &mut Environment) -> Result<Binding, RuntimeError> {
         let func_name = &self.id.value;
         if func_name == "print" || func_name == "println" {
             let binding_list = {
@@ -599,24 +848,105 @@ impl Evaluate for CallExpression {
             }
             return Ok(Binding::Void);
         }
-        let func_decl = env.get_func(func_name).ok_or_else(|| {})?;
-        if let Binding::FunctionDeclaration(decl) = func_decl {
-            let decl = decl.clone();
-            env.call_expression_binding =
-                prepare_call_expression_binding(env, &decl.params, &self.arguments)?;
-            match decl.body.evaluate(env) {
-                Ok(Some(binding)) => {
-                    return Ok(binding);
-                }
-                Err(_) => {
-                    return Err(());
-                }
-                _ => {
-                    return Ok(Binding::Void);
-                }
+        if func_name == "input" {
+            if !self.arguments.is_empty() {
+                return Err(RuntimeError::new(
+                    RuntimeErrorKind::ArgumentMismatch,
+                    format!(
+                        "function `input` takes 0 arguments but {} were supplied",
+                        self.arguments.len()
+                    ),
+                    self.start,
+                    self.end,
+                ));
             }
-        } else {
-            return Err(());
+            return env
+                .host
+                .read_int()
+                .map(Binding::NumberLiteral)
+                .ok_or_else(|| {
+                    RuntimeError::new(
+                        RuntimeErrorKind::InvalidInput,
+                        "failed to read an int from input".into(),
+                        self.start,
+                        self.end,
+                    )
+                });
+        }
+        let decl = match env.get_func(func_name) {
+            Some(Binding::FunctionDeclaration(decl)) => decl.clone(),
+            _ if env.natives.resolve(func_name).is_some() => {
+                return self.evaluate_native(env);
+            }
+            _ => {
+                return Err(RuntimeError::new(
+                    RuntimeErrorKind::UndefinedFunction,
+                    format!("cannot find function `{}`", func_name),
+                    self.id.start,
+                    self.id.end,
+                ));
+            }
+        };
+        if env.call_depth >= MAX_CALL_DEPTH {
+            return Err(RuntimeError::new(
+                RuntimeErrorKind::StackOverflow,
+                format!("more than {} nested calls", MAX_CALL_DEPTH),
+                self.start,
+                self.end,
+            ));
+        }
+        env.call_expression_binding = prepare_call_expression_binding(env, &decl.params, self)?;
+        env.call_depth += 1;
+        let completion = decl.body.evaluate(env);
+        env.call_depth -= 1;
+        match completion {
+            Ok(Completion::Return(binding)) => Ok(binding),
+            Ok(_) => Ok(Binding::Void),
+            Err(error) => Err(error.with_frame(func_name.clone(), self.start, self.end)),
+        }
+    }
+}
+
+impl CallExpression {
+    fn evaluate_native(&self, env: &mut Environment) -> Result<Binding, RuntimeError> {
+        let native = env.natives.get(&self.id.value).unwrap().clone();
+        let mut arguments = Vec::with_capacity(self.arguments.len());
+        for arg in self.arguments.iter() {
+            let value = match arg.evaluate(env)? {
+                Binding::NumberLiteral(value) => NativeValue::Int(value),
+                Binding::BooleanLiteral(value) => NativeValue::Boolean(value),
+                Binding::Void => NativeValue::Void,
+                binding => {
+                    return Err(RuntimeError::new(
+                        RuntimeErrorKind::ArgumentMismatch,
+                        format!(
+                            "{} can't be passed to native function `{}`",
+                            type_name(&binding),
+                            self.id.value
+                        ),
+                        arg.start(),
+                        arg.end(),
+                    ));
+                }
+            };
+            arguments.push(value);
+        }
+        match native.call(&arguments) {
+            Ok(NativeValue::Int(value)) => Ok(Binding::NumberLiteral(value)),
+            Ok(NativeValue::Boolean(value)) => Ok(Binding::BooleanLiteral(value)),
+            Ok(NativeValue::Void) => Ok(Binding::Void),
+            Err(NativeError::ArgumentMismatch(message)) => Err(RuntimeError::new(
+                RuntimeErrorKind::ArgumentMismatch,
+                message,
+                self.start,
+                self.end,
+            )),
+            Err(NativeError::Failed(message)) => Err(RuntimeError::new(
+                RuntimeErrorKind::NativeError,
+                message,
+                self.start,
+                self.end,
+            )),
         }
     }
 }
@@ -624,52 +954,497 @@ impl Evaluate for CallExpression {
 fn prepare_call_expression_binding(
     env: &mut Environment,
     params: &Params,
-    arguments: &Vec<Expression>,
-) -> Result<Vec<(SmolStr, Binding)>, ()> {
-    match params {
-        Params::ParamsList { params } => {
-            if params.len() != arguments.len() {
-                return Err(());
-            }
-            let mut array = Vec::new();
-            for (param, arg) in params.iter().zip(arguments.iter()) {
-                array.push((
-                    param.id.value.clone(),
-                    generate_assignable_binding(env, param, arg)?,
-                ));
-            }
-            Ok(array)
-        }
-        Params::Void => {
-            if arguments.len() != 0 {
-                return Err(());
-            }
-            return Ok(Vec::new());
-        }
+    call: &CallExpression,
+) -> Result<Vec<(SmolStr, Binding)>, RuntimeError> {
+    let params: &[Parameter] = match params {
+        Params::ParamsList { params } => params,
+        Params::Void { .. } => &[],
+    };
+    if params.len() != call.arguments.len() {
+        return Err(RuntimeError::new(
+            RuntimeErrorKind::ArgumentMismatch,
+            format!(
+                "function `{}` takes {} arguments but {} were supplied",
+                call.id.value,
+                params.len(),
+                call.arguments.len()
+            ),
+            call.start,
+            call.end,
+        ));
     }
+    let mut array = Vec::new();
+    for (param, arg) in params.iter().zip(call.arguments.iter()) {
+        array.push((
+            param.id.value.clone(),
+            generate_assignable_binding(env, param, arg)?,
+        ));
+    }
+    Ok(array)
 }
 
 fn generate_assignable_binding(
     env: &mut Environment,
     param: &Parameter,
     arg: &Expression,
-) -> Result<Binding, ()> {
-    if !param.is_array {
-        let arg_binding = arg.evaluate(env)?;
+) -> Result<Binding, RuntimeError> {
+    let arg_binding = match (&param.type_specifier.kind, arg.evaluate(env)?) {
+        (TypeSpecifierKind::Pointer(_), binding) if !param.is_array => {
+            binding.to_pointer().map_or(binding, Binding::Pointer)
+        }
+        (_, binding) => binding,
+    };
+    let assignable = if !param.is_array {
         match (&param.type_specifier.kind, &arg_binding) {
-            (TypeSpecifierKind::Int, Binding::NumberLiteral(_)) => Ok(arg_binding),
-            (TypeSpecifierKind::Boolean, Binding::BooleanLiteral(_)) => Ok(arg_binding),
-            (TypeSpecifierKind::Void, Binding::Void) => Ok(arg_binding),
-            _ => Err(()),
+            (TypeSpecifierKind::Struct(name), Binding::Struct(value)) => *name == value.name,
+            (TypeSpecifierKind::Pointer(_), binding) => matches!(binding, Binding::Pointer(_)),
+            (kind, binding) => matches!(
+                (kind, binding),
+                (TypeSpecifierKind::Int, Binding::NumberLiteral(_))
+                    | (TypeSpecifierKind::Boolean, Binding::BooleanLiteral(_))
+                    | (TypeSpecifierKind::Char, Binding::CharLiteral(_))
+                    | (TypeSpecifierKind::Void, Binding::Void)
+            ),
         }
     } else {
-        let arg_binding = arg.evaluate(env)?;
-        match (&param.type_specifier.kind, &arg_binding) {
-            (TypeSpecifierKind::Int, Binding::Array(ArrayType::Number { .. })) => Ok(arg_binding),
-            (TypeSpecifierKind::Boolean, Binding::Array(ArrayType::Boolean { .. })) => {
-                Ok(arg_binding)
+        match arg_binding {
+            Binding::Array(ref arr) => {
+                array_matches(arr, &param.type_specifier.kind, 1 + param.dimensions.len())
+            }
+            _ => false,
+        }
+    };
+    // a struct is passed by value
+    if assignable {
+        Ok(arg_binding.stored())
+    } else {
+        Err(RuntimeError::new(
+            RuntimeErrorKind::ArgumentMismatch,
+            format!(
+                "argument of parameter `{}` has mismatched type {}",
+                param.id.value,
+                type_name(&arg_binding)
+            ),
+            arg.start(),
+            arg.end(),
+        ))
+    }
+}
+
+/// whether `arr` is an array of `kind` with the number of `dimensions`, the sizes of the rows
+/// aren't checked since every row is a separate array
+fn array_matches(arr: &ArrayType, kind: &TypeSpecifierKind, dimensions: usize) -> bool {
+    match arr {
+        ArrayType::Array { array, .. } => {
+            dimensions > 1
+                && array
+                    .borrow()
+                    .first()
+                    .map_or(true, |row| array_matches(row, kind, dimensions - 1))
+        }
+        ArrayType::Struct { name, .. } => {
+            dimensions == 1 && matches!(kind, TypeSpecifierKind::Struct(kind) if kind == name)
+        }
+        _ => {
+            dimensions == 1
+                && matches!(
+                    (kind, arr),
+                    (TypeSpecifierKind::Int, ArrayType::Number { .. })
+                        | (TypeSpecifierKind::Boolean, ArrayType::Boolean { .. })
+                        | (TypeSpecifierKind::Char, ArrayType::Char { .. })
+                )
+        }
+    }
+}
+
+/// where the accessors of `var` or a pointer lead, an element of an array, a field of a
+/// struct or a variable in the scope at `scope` of the stack. The index of an element is
+/// checked when the place is created
+enum Place {
+    Element(ArrayType, usize),
+    Field(StructType, usize),
+    Variable { scope: usize, name: SmolStr },
+}
+
+impl Place {
+    fn read(&self, env: &Environment) -> Binding {
+        match self {
+            Place::Element(arr, index) => element_binding(arr, *index),
+            Place::Field(value, index) => value.fields.borrow()[*index].1.clone(),
+            Place::Variable { scope, name } => env.scope_stack[*scope][name].clone(),
+        }
+    }
+
+    /// `start` and `end` is the range of the stored value, used by errors
+    fn write(
+        &self,
+        env: &mut Environment,
+        binding: &Binding,
+        start: usize,
+        end: usize,
+    ) -> Result<(), RuntimeError> {
+        match self {
+            Place::Element(arr, index) => {
+                let mut arr = arr.clone();
+                if !matches!(
+                    binding,
+                    Binding::NumberLiteral(_)
+                        | Binding::BooleanLiteral(_)
+                        | Binding::CharLiteral(_)
+                        | Binding::Struct(_)
+                ) {
+                    return Err(RuntimeError::new(
+                        RuntimeErrorKind::TypeMismatch,
+                        format!("{} can't be stored in an array", type_name(binding)),
+                        start,
+                        end,
+                    ));
+                }
+                let stored = match binding {
+                    Binding::Struct(value) => arr.set_struct(*index, value.deep_copy()),
+                    _ if matches!(arr, ArrayType::Struct { .. }) => Err(String::new()),
+                    _ => arr.set(*index, binding.get_literal()),
+                };
+                stored.map_err(|_| {
+                    RuntimeError::new(
+                        RuntimeErrorKind::TypeMismatch,
+                        format!(
+                            "{} can't be stored in an array of {}",
+                            type_name(binding),
+                            array_element_name(&arr)
+                        ),
+                        start,
+                        end,
+                    )
+                })
+            }
+            Place::Field(value, index) => {
+                let field = &mut value.fields.borrow_mut()[*index].1;
+                *field = checked_store(field, binding, start, end)?;
+                Ok(())
             }
-            _ => Err(()),
+            Place::Variable { scope, name } => {
+                let variable = env.scope_stack[*scope].get_mut(name).unwrap();
+                *variable = checked_store(variable, binding, start, end)?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// the binding stored in place of `current`, `start` and `end` is the range of the stored value
+fn checked_store(
+    current: &Binding,
+    binding: &Binding,
+    start: usize,
+    end: usize,
+) -> Result<Binding, RuntimeError> {
+    if !binding_matches(current, binding) {
+        return Err(RuntimeError::new(
+            RuntimeErrorKind::TypeMismatch,
+            format!(
+                "expected {}, found {}",
+                type_name(current),
+                type_name(binding)
+            ),
+            start,
+            end,
+        ));
+    }
+    Ok(converted(current, binding))
+}
+
+/// the variable is looked up first, then the accessors are evaluated from left to right, every
+/// accessor except the last one selects a row or a nested struct
+fn evaluate_place(env: &mut Environment, var: &Var) -> Result<Place, RuntimeError> {
+    let mut binding = lookup_variable(env, &var.id)?.clone();
+    let (last, accessors) = var.accessors.split_last().unwrap();
+    for accessor in accessors {
+        binding = select(env, binding, accessor, var)?.read(env);
+    }
+    select(env, binding, last, var)
+}
+
+/// none if the target is a variable without accessors, which is assigned in its scope
+fn evaluate_target(env: &mut Environment, target: &Target) -> Result<Option<Place>, RuntimeError> {
+    match target {
+        Target::Var(var) if var.accessors.is_empty() => Ok(None),
+        Target::Var(var) => evaluate_place(env, var).map(Some),
+        Target::Dereference(expr) => dereference(env, expr).map(Some),
+    }
+}
+
+/// the place a pointer points to, it must be a live variable or an element in the bounds of
+/// its array
+fn dereference(env: &mut Environment, expr: &DereferenceExpression) -> Result<Place, RuntimeError> {
+    let binding = expr.pointer.evaluate(env)?;
+    let pointer = match binding.to_pointer() {
+        Some(pointer) => pointer,
+        None => {
+            return Err(RuntimeError::new(
+                RuntimeErrorKind::TypeMismatch,
+                format!("{} can't be dereferenced", type_name(&binding)),
+                expr.start,
+                expr.end,
+            ));
+        }
+    };
+    match pointer.address {
+        Address::Null => Err(RuntimeError::new(
+            RuntimeErrorKind::NullPointer,
+            "attempt to dereference a null pointer".into(),
+            expr.start,
+            expr.end,
+        )),
+        Address::Variable { scope, name } => {
+            let scope = env.scope_index(scope).ok_or_else(|| {
+                RuntimeError::new(
+                    RuntimeErrorKind::DanglingPointer,
+                    format!("the variable `{}` pointed to has gone out of scope", name),
+                    expr.start,
+                    expr.end,
+                )
+            })?;
+            if pointer.offset != 0 {
+                return Err(RuntimeError::new(
+                    RuntimeErrorKind::IndexOutOfBounds,
+                    format!(
+                        "offset {} out of bounds for the variable `{}`",
+                        pointer.offset, name
+                    ),
+                    expr.start,
+                    expr.end,
+                ));
+            }
+            Ok(Place::Variable { scope, name })
+        }
+        Address::Element(arr) => {
+            if pointer.offset < 0 || pointer.offset as usize >= arr.length() {
+                return Err(RuntimeError::new(
+                    RuntimeErrorKind::IndexOutOfBounds,
+                    format!(
+                        "offset {} out of bounds for array of length {}",
+                        pointer.offset,
+                        arr.length()
+                    ),
+                    expr.start,
+                    expr.end,
+                ));
+            }
+            Ok(Place::Element(arr, pointer.offset as usize))
+        }
+    }
+}
+
+impl Evaluate for AddressExpression {
+    fn evaluate(&self, env: &mut Environment) -> Result<Binding, RuntimeError> {
+        let var = &self.var;
+        if var.accessors.is_empty() {
+            let scope = env
+                .scope_id_of(&var.id.value)
+                .ok_or_else(|| undefined_variable(&var.id.value, var.id.start, var.id.end))?;
+            return Ok(Binding::Pointer(Pointer {
+                address: Address::Variable {
+                    scope,
+                    name: var.id.value.clone(),
+                },
+                offset: 0,
+            }));
+        }
+        match evaluate_place(env, var)? {
+            Place::Element(arr, index) => Ok(Binding::Pointer(Pointer::element(arr, index as i32))),
+            _ => Err(RuntimeError::new(
+                RuntimeErrorKind::TypeMismatch,
+                "only a variable or an element of an array has an address".into(),
+                self.start,
+                self.end,
+            )),
+        }
+    }
+}
+
+fn select(
+    env: &mut Environment,
+    binding: Binding,
+    accessor: &Accessor,
+    var: &Var,
+) -> Result<Place, RuntimeError> {
+    match (accessor, binding) {
+        (Accessor::Index(expr), Binding::Array(arr)) => {
+            let index = evaluate_index(expr, env)?;
+            if index >= arr.length() {
+                return Err(index_out_of_bounds(&arr, index, var));
+            }
+            Ok(Place::Element(arr, index))
+        }
+        (Accessor::Index(_), binding) => Err(not_indexable(var, &type_name(&binding))),
+        (Accessor::Member(id), Binding::Struct(value)) => match value.field_index(&id.value) {
+            Some(index) => Ok(Place::Field(value, index)),
+            None => Err(no_field(id, &format!("struct {}", value.name))),
+        },
+        (Accessor::Member(id), binding) => Err(no_field(id, &type_name(&binding))),
+    }
+}
+
+/// the element of `arr` at `index`, a row of a multi-dimensional array is an array
+fn element_binding(arr: &ArrayType, index: usize) -> Binding {
+    match arr {
+        ArrayType::Array { .. } => arr.get_row(index).map(Binding::Array),
+        ArrayType::Struct { .. } => arr.get_struct(index).map(Binding::Struct),
+        _ => arr.get(index).map(literal_binding),
+    }
+    .expect("the index is checked when the place is created")
+}
+
+/// the binding of an array element
+fn literal_binding(literal: LiteralType) -> Binding {
+    match literal {
+        LiteralType::Boolean(value) => Binding::BooleanLiteral(value),
+        LiteralType::Number(value) => Binding::NumberLiteral(value),
+        LiteralType::Char(value) => Binding::CharLiteral(value),
+    }
+}
+
+fn lookup_variable<'a>(
+    env: &'a mut Environment,
+    id: &Identifier,
+) -> Result<&'a mut Binding, RuntimeError> {
+    env.get_mut(&id.value)
+        .ok_or_else(|| undefined_variable(&id.value, id.start, id.end))
+}
+
+/// negative index is casted to a huge `usize`, so it is out of bounds as well
+fn evaluate_index(expr: &Expression, env: &mut Environment) -> Result<usize, RuntimeError> {
+    match expr.evaluate(env)? {
+        Binding::NumberLiteral(index) => Ok(index as usize),
+        binding => Err(RuntimeError::new(
+            RuntimeErrorKind::TypeMismatch,
+            format!("index of array should be int, found {}", type_name(&binding)),
+            expr.start(),
+            expr.end(),
+        )),
+    }
+}
+
+fn index_out_of_bounds(arr: &ArrayType, index: usize, var: &Var) -> RuntimeError {
+    RuntimeError::new(
+        RuntimeErrorKind::IndexOutOfBounds,
+        format!(
+            "index {} out of bounds for array `{}` of length {}",
+            index as i32,
+            var.id.value,
+            arr.length()
+        ),
+        var.start,
+        var.end,
+    )
+}
+
+fn not_indexable(var: &Var, type_name: &str) -> RuntimeError {
+    RuntimeError::new(
+        RuntimeErrorKind::TypeMismatch,
+        format!("cannot index into `{}` of type {}", var.id.value, type_name),
+        var.start,
+        var.end,
+    )
+}
+
+fn no_field(id: &Identifier, type_name: &str) -> RuntimeError {
+    RuntimeError::new(
+        RuntimeErrorKind::TypeMismatch,
+        format!("no field `{}` on type {}", id.value, type_name),
+        id.start,
+        id.end,
+    )
+}
+
+fn undefined_variable(name: &str, start: usize, end: usize) -> RuntimeError {
+    RuntimeError::new(
+        RuntimeErrorKind::UndefinedVariable,
+        format!("the variable `{}` can't be found in this scope", name),
+        start,
+        end,
+    )
+}
+
+/// two structs have the same type only if they have the same name, an array can be stored in
+/// a pointer
+fn binding_matches(expected: &Binding, found: &Binding) -> bool {
+    match (expected, found) {
+        (Binding::Struct(expected), Binding::Struct(found)) => expected.name == found.name,
+        (Binding::Pointer(_), Binding::Array(_)) => true,
+        _ => variant_eq(expected, found),
+    }
+}
+
+/// the binding saved in place of `expected`, an array stored in a pointer is converted to the
+/// pointer to its first element
+fn converted(expected: &Binding, found: &Binding) -> Binding {
+    match (expected, found) {
+        (Binding::Pointer(_), Binding::Array(arr)) => {
+            Binding::Pointer(Pointer::element(arr.clone(), 0))
         }
+        _ => found.stored(),
+    }
+}
+
+fn type_mismatch(expected: &Binding, found: &Binding, expr: &Expression) -> RuntimeError {
+    RuntimeError::new(
+        RuntimeErrorKind::TypeMismatch,
+        format!(
+            "expected {}, found {}",
+            type_name(expected),
+            type_name(found)
+        ),
+        expr.start(),
+        expr.end(),
+    )
+}
+
+fn unsupported_operands(
+    left: &Binding,
+    right: &Binding,
+    operation: &Operation,
+    start: usize,
+    end: usize,
+) -> RuntimeError {
+    RuntimeError::new(
+        RuntimeErrorKind::TypeMismatch,
+        format!(
+            "`{}` can't be applied to {} and {}",
+            operation,
+            type_name(left),
+            type_name(right)
+        ),
+        start,
+        end,
+    )
+}
+
+fn array_element_name(arr: &ArrayType) -> String {
+    match arr {
+        ArrayType::Boolean { .. } => "bool".into(),
+        ArrayType::Number { .. } => "int".into(),
+        ArrayType::Char { .. } => "char".into(),
+        ArrayType::Struct { name, .. } => format!("struct {}", name),
+        // an empty array has no row to tell the type of its elements
+        ArrayType::Array { array, .. } => match array.borrow().first() {
+            Some(row) => format!("{}[]", array_element_name(row)),
+            None => "array".into(),
+        },
+    }
+}
+
+fn type_name(binding: &Binding) -> String {
+    match binding {
+        Binding::NumberLiteral(_) => "int".into(),
+        Binding::BooleanLiteral(_) => "bool".into(),
+        Binding::CharLiteral(_) => "char".into(),
+        Binding::Array(arr) => format!("{}[]", array_element_name(arr)),
+        Binding::Struct(value) => format!("struct {}", value.name),
+        Binding::Pointer(_) => "pointer".into(),
+        Binding::FunctionDeclaration(_) => "function".into(),
+        Binding::Variable(_) => "variable".into(),
+        Binding::Void => "void".into(),
     }
 }
@@ -1,89 +1,186 @@
-use crate::parser::ast::*;
+use crate::{
+    host::Host,
+    native::{NativeValue, Natives},
+    parser::ast::*,
+    semantic::FunctionSignature,
+};
 
 use self::{
     env::{Binding, Environment},
-    interpreter::Evaluate,
+    error::RuntimeErrorKind,
+    interpreter::{Completion, Evaluate},
 };
 
 pub(crate) mod env;
+pub mod error;
 mod interpreter;
 
-pub fn interpret(program: &mut Program, std_io: bool) -> Result<Environment, ()> {
-    let mut env = env::Environment {
-        scope_stack: vec![fxhash::FxHashMap::default()],
-        call_expression_binding: Vec::new(),
-        std_io,
-        std_simulator: vec![],
-    };
-    program.evaluate(&mut env)?;
-    let func = if let Some(scope) = env.scope_stack.last() {
-        match scope.get("main") {
-            Some(Binding::FunctionDeclaration(func)) => func,
-            _ => {
-                return Err(());
-            }
+pub use error::RuntimeError;
+
+/// the interpreter with the native functions registered by the embedder
+#[derive(Debug, Default)]
+pub struct Interpreter {
+    natives: Natives,
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// expose a rust closure to scripts, the arguments are checked against `signature` before
+    /// the closure is called. Pass `natives()` to `Resolver` and `TypeChecker` as well
+    pub fn register_native(
+        &mut self,
+        name: &str,
+        signature: FunctionSignature,
+        closure: impl Fn(&[NativeValue]) -> Result<NativeValue, String> + 'static,
+    ) {
+        self.natives.register(name, signature, closure);
+    }
+
+    pub fn natives(&self) -> &Natives {
+        &self.natives
+    }
+
+    pub fn interpret(
+        &self,
+        program: &Program,
+        host: Box<dyn Host>,
+    ) -> Result<Environment, RuntimeError> {
+        let mut env = Environment::new(host, self.natives.clone());
+        let result = run_main(program, &mut env);
+        env.host.flush();
+        result.map(|_| env)
+    }
+}
+
+pub fn interpret(program: &mut Program, host: Box<dyn Host>) -> Result<Environment, RuntimeError> {
+    Interpreter::new().interpret(program, host)
+}
+
+fn run_main(program: &Program, env: &mut Environment) -> Result<(), RuntimeError> {
+    program.evaluate(env)?;
+    let func = match env.scope_stack.last().and_then(|scope| scope.get("main")) {
+        Some(Binding::FunctionDeclaration(func)) => func.clone(),
+        _ => {
+            return Err(RuntimeError::new(
+                RuntimeErrorKind::MissingMain,
+                "`main` function not found in this program".into(),
+                program.start,
+                program.end,
+            ));
         }
-    } else {
-        return Err(());
     };
-    func.body.clone().evaluate(&mut env)?;
-    Ok(env)
+    let completion = func
+        .body
+        .evaluate(env)
+        .map_err(|error| error.with_frame("main".into(), func.id.start, func.id.end))?;
+    if let Completion::Return(Binding::NumberLiteral(value)) = completion {
+        env.main_result = Some(value);
+    }
+    Ok(())
+}
+
+/// run a statement of the REPL in the global scope, the result is the value of an expression
+/// statement or of a `return`
+pub(crate) fn evaluate_statement(
+    statement: &Statement,
+    env: &mut Environment,
+) -> Result<Binding, RuntimeError> {
+    let result = match statement {
+        Statement::ExpressionStatement(stmt) => stmt.evaluate(env),
+        statement => statement.evaluate(env).map(|completion| match completion {
+            Completion::Return(binding) => binding,
+            _ => Binding::Void,
+        }),
+    };
+    if result.is_err() {
+        env.unwind();
+    }
+    result
 }
 
-pub(crate) fn print(binding_list: Vec<Binding>, env: &mut Environment) {
-    let mut string_list = vec![];
-    for binding in binding_list {
-        let string = match binding {
-            Binding::BooleanLiteral(val) => format!("{}", val),
-            Binding::NumberLiteral(val) => format!("{}", val),
-            Binding::Array(env::ArrayType::Boolean { array, .. }) => {
-                format!("{:?}", array.borrow())
-            }
-            Binding::Array(env::ArrayType::Number { array, .. }) => format!("{:?}", array.borrow()),
-            Binding::Variable(_) => {
-                unimplemented!() // TODO
-            }
-            Binding::Void => format!("void"),
-            _ => {
-                panic!("not support type");
+/// define the declarations entered in the REPL, a global with the same name is replaced, so a
+/// function can be fixed by entering it again
+pub(crate) fn evaluate_declarations(
+    program: &Program,
+    env: &mut Environment,
+) -> Result<(), RuntimeError> {
+    for decl in program.declarations.iter() {
+        match decl {
+            Declaration::FunctionDeclaration(func) => env.scope_stack[0].remove(&func.id.value),
+            Declaration::VarDeclaration(var) => env.scope_stack[0].remove(&var.id.value),
+            Declaration::StructDeclaration(decl) => {
+                env.structs.remove(&decl.id.value);
+                None
             }
+            Declaration::FunctionPrototype(_) | Declaration::Error(_) => None,
         };
-        string_list.push(string);
     }
-    if env.std_io {
-        print!("{}", string_list.join(""));
-    } else {
-        if let Some(last) = env.std_simulator.last_mut() {
-            *last += &string_list.join("");
-        } else {
-            env.std_simulator.push(string_list.join(""));
-        }
+    let result = program.evaluate(env).map(|_| ());
+    if result.is_err() {
+        env.unwind();
     }
+    result
+}
+
+pub(crate) fn print(binding_list: Vec<Binding>, env: &mut Environment) {
+    let output = binding_list.iter().map(to_print_string).collect::<String>();
+    env.host.write(&output);
 }
 
 pub(crate) fn println(binding_list: Vec<Binding>, env: &mut Environment) {
-    let mut string_list = vec![];
-    for binding in binding_list {
-        let arg_string = match binding {
-            Binding::NumberLiteral(val) => format!("{}", val),
-            Binding::BooleanLiteral(val) => format!("{}", val),
-            Binding::Array(env::ArrayType::Boolean { array, .. }) => {
-                format!("{:?}", array.borrow())
-            }
-            Binding::Array(env::ArrayType::Number { array, .. }) => format!("{:?}", array.borrow()),
-            Binding::Variable(_) => {
-                unimplemented!() // TODO
-            }
-            Binding::Void => format!("void"),
-            _ => {
-                panic!("not support type");
-            }
-        };
-        string_list.push(arg_string);
-    }
-    if env.std_io {
-        println!("{}", string_list.join(""));
-    } else {
-        env.std_simulator.push(string_list.join("") + "\n");
+    let output = binding_list.iter().map(to_print_string).collect::<String>();
+    env.host.write(&(output + "\n"));
+}
+
+pub(crate) fn to_print_string(binding: &Binding) -> String {
+    match binding {
+        Binding::BooleanLiteral(val) => format!("{}", val),
+        Binding::NumberLiteral(val) => format!("{}", val),
+        Binding::CharLiteral(val) => val.to_string(),
+        Binding::Array(env::ArrayType::Boolean { array, .. }) => format!("{:?}", array.borrow()),
+        Binding::Array(env::ArrayType::Number { array, .. }) => format!("{:?}", array.borrow()),
+        // a char array is printed as a string, which ends at the first '\0'
+        Binding::Array(env::ArrayType::Char { array, .. }) => array
+            .borrow()
+            .iter()
+            .take_while(|c| **c != '\0')
+            .collect(),
+        Binding::Array(env::ArrayType::Array { array, .. }) => format!(
+            "[{}]",
+            array
+                .borrow()
+                .iter()
+                .map(|row| to_print_string(&Binding::Array(row.clone())))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Binding::Array(env::ArrayType::Struct { array, .. }) => format!(
+            "[{}]",
+            array
+                .borrow()
+                .iter()
+                .map(|value| to_print_string(&Binding::Struct(value.clone())))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        // the fields are printed in declaration order
+        Binding::Struct(value) => format!(
+            "{{{}}}",
+            value
+                .fields
+                .borrow()
+                .iter()
+                .map(|(_, field)| to_print_string(field))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        // same as the vm, the address isn't meaningful
+        Binding::Pointer(_) => "pointer".to_string(),
+        Binding::FunctionDeclaration(func) => format!("function {}", func.id.value),
+        Binding::Variable(name) => name.clone(),
+        Binding::Void => "void".to_string(),
     }
 }
@@ -85,6 +85,19 @@ impl Lexer {
                             cur_token_type = TokenType::Lt;
                         }
                     }
+                    '+' | '-' | '*' | '%' => {
+                        let (next_state, token_type) = match cur_char {
+                            '+' => (State::InPlus, TokenType::Plus),
+                            '-' => (State::InMinus, TokenType::Minus),
+                            '*' => (State::InMultiply, TokenType::Multiply),
+                            _ => (State::InMod, TokenType::Mod),
+                        };
+                        state = next_state;
+                        if self.cursor == self.length {
+                            state = State::Done;
+                            cur_token_type = token_type;
+                        }
+                    }
                     '/' => {
                         state = State::InDivide;
                         if self.cursor == self.length {
@@ -103,7 +116,7 @@ impl Lexer {
                         state = State::InNotEqual;
                         if self.cursor == self.length {
                             state = State::Done;
-                            cur_token_type = TokenType::Error;
+                            cur_token_type = TokenType::Not;
                         }
                     }
                     '=' => {
@@ -117,11 +130,22 @@ impl Lexer {
                         state = State::InAnd;
                         if self.cursor == self.length {
                             state = State::Done;
-                            cur_token_type = TokenType::Error;
+                            cur_token_type = TokenType::BitAnd;
                         }
                     }
                     '|' => {
                         state = State::InOr;
+                        if self.cursor == self.length {
+                            state = State::Done;
+                            cur_token_type = TokenType::BitOr;
+                        }
+                    }
+                    '\'' | '"' => {
+                        state = if cur_char == '\'' {
+                            State::InChar
+                        } else {
+                            State::InString
+                        };
                         if self.cursor == self.length {
                             state = State::Done;
                             cur_token_type = TokenType::Error;
@@ -159,17 +183,19 @@ impl Lexer {
                     _ => {
                         state = State::Done;
                         match cur_char {
-                            '+' => cur_token_type = TokenType::Plus,
-                            '*' => cur_token_type = TokenType::Multiply,
-                            '-' => cur_token_type = TokenType::Minus,
                             '(' => cur_token_type = TokenType::Lparen,
                             ')' => cur_token_type = TokenType::Rparen,
                             ';' => cur_token_type = TokenType::Semi,
                             ',' => cur_token_type = TokenType::Comma,
+                            '.' => cur_token_type = TokenType::Dot,
+                            ':' => cur_token_type = TokenType::Colon,
+                            '?' => cur_token_type = TokenType::Question,
                             '[' => cur_token_type = TokenType::Lbrack,
                             ']' => cur_token_type = TokenType::Rbrack,
                             '{' => cur_token_type = TokenType::Lbrace,
                             '}' => cur_token_type = TokenType::Rbrace,
+                            '^' => cur_token_type = TokenType::BitXor,
+                            '~' => cur_token_type = TokenType::BitNot,
                             _ => cur_token_type = TokenType::Error,
                         }
                     }
@@ -183,7 +209,7 @@ impl Lexer {
                         state = State::Done;
                         self.unget_next_char();
                         save = false;
-                        cur_token_type = TokenType::Error;
+                        cur_token_type = TokenType::BitAnd;
                     }
                 },
                 State::InOr => match cur_char {
@@ -195,10 +221,35 @@ impl Lexer {
                         state = State::Done;
                         self.unget_next_char();
                         save = false;
-                        cur_token_type = TokenType::Error;
+                        cur_token_type = TokenType::BitOr;
                     }
                 },
+                State::InPlus | State::InMinus | State::InMultiply | State::InMod => {
+                    cur_token_type = match (&state, cur_char) {
+                        (State::InPlus, '=') => TokenType::PlusAssign,
+                        (State::InMinus, '=') => TokenType::MinusAssign,
+                        (State::InMultiply, '=') => TokenType::MultiplyAssign,
+                        (State::InMod, '=') => TokenType::ModAssign,
+                        (State::InPlus, '+') => TokenType::Increment,
+                        (State::InMinus, '-') => TokenType::Decrement,
+                        (state, _) => {
+                            self.unget_next_char();
+                            save = false;
+                            match state {
+                                State::InPlus => TokenType::Plus,
+                                State::InMinus => TokenType::Minus,
+                                State::InMultiply => TokenType::Multiply,
+                                _ => TokenType::Mod,
+                            }
+                        }
+                    };
+                    state = State::Done;
+                }
                 State::InDivide => match cur_char {
+                    '=' => {
+                        state = State::Done;
+                        cur_token_type = TokenType::DivideAssign;
+                    }
                     '*' => {
                         state = State::InComment;
                         if self.cursor == self.length {
@@ -236,11 +287,49 @@ impl Lexer {
                         cur_token_type = TokenType::Id;
                     }
                 }
+                State::InChar | State::InString => {
+                    let (quote, token_type, escape) = if state == State::InChar {
+                        ('\'', TokenType::CharLiteral, State::InCharEscape)
+                    } else {
+                        ('"', TokenType::StringLiteral, State::InStringEscape)
+                    };
+                    if cur_char == quote {
+                        state = State::Done;
+                        cur_token_type = token_type;
+                    } else if cur_char == '\n' {
+                        // a literal can't span lines, the newline is lexed again
+                        self.unget_next_char();
+                        save = false;
+                        state = State::Done;
+                        cur_token_type = TokenType::Error;
+                    } else if self.cursor == self.length {
+                        state = State::Done;
+                        cur_token_type = TokenType::Error;
+                    } else if cur_char == '\\' {
+                        state = escape;
+                    }
+                }
+                State::InCharEscape | State::InStringEscape => {
+                    if cur_char == '\n' || self.cursor == self.length {
+                        if cur_char == '\n' {
+                            self.unget_next_char();
+                            save = false;
+                        }
+                        state = State::Done;
+                        cur_token_type = TokenType::Error;
+                    } else if state == State::InCharEscape {
+                        state = State::InChar;
+                    } else {
+                        state = State::InString;
+                    }
+                }
                 State::Done => {} // do nothing
                 State::InLess => {
                     state = State::Done;
                     if cur_char == '=' {
                         cur_token_type = TokenType::Le;
+                    } else if cur_char == '<' {
+                        cur_token_type = TokenType::Shl;
                     } else {
                         self.unget_next_char();
                         save = false;
@@ -251,6 +340,8 @@ impl Lexer {
                     state = State::Done;
                     if cur_char == '=' {
                         cur_token_type = TokenType::Ge;
+                    } else if cur_char == '>' {
+                        cur_token_type = TokenType::Shr;
                     } else {
                         self.unget_next_char();
                         save = false;
@@ -274,7 +365,7 @@ impl Lexer {
                     } else {
                         self.unget_next_char();
                         save = false;
-                        cur_token_type = TokenType::Error;
+                        cur_token_type = TokenType::Not;
                     }
                 }
                 State::InComment => {
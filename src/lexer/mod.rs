@@ -1,4 +1,5 @@
 pub mod token;
 pub mod state;
 pub mod lex;
-mod util;
+pub mod preprocess;
+pub(crate) mod util;
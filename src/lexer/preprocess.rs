@@ -0,0 +1,473 @@
+use std::ops::Range;
+use std::path::Path;
+
+use fxhash::FxHashMap;
+
+use crate::error_emit::{ErrorReporter, FileId, FileSpan};
+
+/// where the preprocessor reads an included file from, `path` is already joined to the
+/// directory of the including file
+pub trait SourceLoader {
+    fn load(&mut self, path: &str) -> Option<String>;
+}
+
+/// read the included files from the file system
+#[derive(Debug, Default)]
+pub struct FileSystemLoader;
+
+impl SourceLoader for FileSystemLoader {
+    fn load(&mut self, path: &str) -> Option<String> {
+        std::fs::read_to_string(path).ok()
+    }
+}
+
+/// the included files are given up front, for the tests and the playground
+#[derive(Debug, Default)]
+pub struct MemoryLoader {
+    files: FxHashMap<String, String>,
+}
+
+impl MemoryLoader {
+    pub fn add_file(&mut self, path: &str, source: &str) {
+        self.files.insert(path.to_string(), source.to_string());
+    }
+}
+
+impl SourceLoader for MemoryLoader {
+    fn load(&mut self, path: &str) -> Option<String> {
+        self.files.get(path).cloned()
+    }
+}
+
+#[derive(Debug)]
+struct SourceFile {
+    name: String,
+    source: String,
+}
+
+/// the output in `output` is copied from `source` of the file `file`, or is the expansion of
+/// the macro used at `source`
+#[derive(Debug)]
+struct Segment {
+    output: Range<usize>,
+    file: usize,
+    source: Range<usize>,
+}
+
+impl Segment {
+    /// a copied segment maps every offset, an expansion maps to the whole macro use
+    fn is_copy(&self) -> bool {
+        self.output.len() == self.source.len()
+    }
+
+    fn source_start(&self, offset: usize) -> usize {
+        if self.is_copy() {
+            self.source.start + (offset - self.output.start).min(self.source.len())
+        } else {
+            self.source.start
+        }
+    }
+
+    fn source_end(&self, offset: usize) -> usize {
+        if self.is_copy() {
+            self.source.start + (offset - self.output.start).min(self.source.len())
+        } else {
+            self.source.end
+        }
+    }
+}
+
+/// map a range of the preprocessed source back to the file it comes from, the first file is
+/// the root file
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+    segments: Vec<Segment>,
+}
+
+impl SourceMap {
+    pub fn file_name(&self, file: usize) -> Option<&str> {
+        self.files.get(file).map(|file| file.name.as_str())
+    }
+
+    /// the index of the file and the range in it which produced `range` of the output
+    pub fn locate(&self, range: Range<usize>) -> Option<(usize, Range<usize>)> {
+        let first = self.segment(range.start)?;
+        let start = first.source_start(range.start);
+        let last = self.segment(range.end.saturating_sub(1).max(range.start))?;
+        let end = if last.file == first.file {
+            last.source_end(range.end)
+        } else {
+            first.source.end
+        };
+        Some((first.file, start..end.max(start)))
+    }
+
+    /// the name of the file, the line and the column, one based, of an offset of the output
+    pub fn position(&self, offset: usize) -> Option<(&str, usize, usize)> {
+        let (file, range) = self.locate(offset..offset)?;
+        let file = &self.files[file];
+        let before = &file.source[..range.start];
+        let line = before.matches('\n').count() + 1;
+        let column = before.len() - before.rfind('\n').map_or(0, |index| index + 1) + 1;
+        Some((&file.name, line, column))
+    }
+
+    fn segment(&self, offset: usize) -> Option<&Segment> {
+        let index = self
+            .segments
+            .partition_point(|segment| segment.output.start <= offset);
+        self.segments.get(index.checked_sub(1)?)
+    }
+
+    /// point the diagnostics of `reporter` at the original files, the reporter should have
+    /// the preprocessed source added under the name of the root file. Call it once, the
+    /// original files are added to the reporter under the same names
+    pub fn remap(&self, reporter: &mut ErrorReporter<'_>) {
+        let expanded = match self.files.first().and_then(|file| reporter.file_id(&file.name)) {
+            Some(id) => id,
+            None => return,
+        };
+        let ids: Vec<FileId> = self
+            .files
+            .iter()
+            .map(|file| reporter.add_file(file.name.clone(), file.source.clone()))
+            .collect();
+        reporter.remap_file(expanded, |range| {
+            self.locate(range)
+                .map(|(file, range)| FileSpan::new(ids[file], range))
+        });
+    }
+}
+
+/// the source after preprocessing, it is lexed instead of the root file
+#[derive(Debug)]
+pub struct Preprocessed {
+    pub source: String,
+    pub source_map: SourceMap,
+}
+
+/// an `#ifdef` or `#ifndef` and whether the lines in its current branch are kept
+struct Condition {
+    active: bool,
+    has_else: bool,
+    file: usize,
+    range: Range<usize>,
+}
+
+/// expand `#include "file"`, object-like `#define NAME value`, `#ifdef`, `#ifndef`, `#else` and
+/// `#endif` ahead of the lexer. A directive takes a whole line
+pub struct Preprocessor {
+    loader: Box<dyn SourceLoader>,
+    defines: FxHashMap<String, String>,
+    // the files being included, the innermost last, an include of any of them is a cycle
+    include_stack: Vec<usize>,
+    // the id in `error_reporter` of every file in the source map
+    file_ids: Vec<FileId>,
+    output: String,
+    source_map: SourceMap,
+    pub error_reporter: ErrorReporter<'static>,
+}
+
+impl Preprocessor {
+    pub fn new(loader: Box<dyn SourceLoader>) -> Self {
+        Self {
+            loader,
+            defines: FxHashMap::default(),
+            include_stack: vec![],
+            file_ids: vec![],
+            output: String::new(),
+            source_map: SourceMap::default(),
+            error_reporter: ErrorReporter::new(),
+        }
+    }
+
+    /// the diagnostics, including the warnings when it succeeds, are left in `error_reporter`
+    pub fn preprocess(&mut self, file_name: &str, source: &str) -> Result<Preprocessed, ()> {
+        let file = self.add_file(file_name.to_string(), source.to_string());
+        self.process_file(file);
+        let preprocessed = Preprocessed {
+            source: std::mem::take(&mut self.output),
+            source_map: std::mem::take(&mut self.source_map),
+        };
+        self.file_ids.clear();
+        if self.error_reporter.has_error() {
+            return Err(());
+        }
+        Ok(preprocessed)
+    }
+
+    fn add_file(&mut self, name: String, source: String) -> usize {
+        let id = self.error_reporter.add_file(name.clone(), source.clone());
+        self.file_ids.push(id);
+        self.source_map.files.push(SourceFile { name, source });
+        self.source_map.files.len() - 1
+    }
+
+    fn report(&mut self, file: usize, range: Range<usize>, message: String) {
+        self.error_reporter
+            .add_diagnostic(FileSpan::new(self.file_ids[file], range), message);
+    }
+
+    fn process_file(&mut self, file: usize) {
+        self.include_stack.push(file);
+        let source = self.source_map.files[file].source.clone();
+        let mut conditions: Vec<Condition> = vec![];
+        let mut in_comment = false;
+        let mut line_start = 0;
+        for line in source.split_inclusive('\n') {
+            let content = line.trim_end_matches('\n');
+            let directive = content.trim_start();
+            let active = conditions.iter().all(|condition| condition.active);
+            if !in_comment && directive.starts_with('#') {
+                let start = line_start + content.len() - directive.len();
+                let range = start..start + directive.trim_end().len();
+                self.process_directive(file, range, active, &mut conditions);
+            } else if active {
+                self.expand_line(file, line_start, content, &mut in_comment);
+            }
+            // the line break is kept, so the lines of the root file keep their numbers
+            if line.ends_with('\n') {
+                let end = line_start + line.len();
+                self.push(file, end - 1..end, "\n");
+            }
+            line_start += line.len();
+        }
+        for condition in conditions {
+            let range = condition.range.clone();
+            let directive = &self.source_map.files[condition.file].source[range.clone()];
+            let name = directive.split_whitespace().next().unwrap_or_default();
+            let message = format!("unterminated `{}`", name);
+            self.report(condition.file, range, message);
+        }
+        self.include_stack.pop();
+    }
+
+    fn process_directive(
+        &mut self,
+        file: usize,
+        range: Range<usize>,
+        active: bool,
+        conditions: &mut Vec<Condition>,
+    ) {
+        let text = self.source_map.files[file].source[range.clone()].to_string();
+        let text = text[1..].trim_start();
+        let (name, argument) = match text.find(char::is_whitespace) {
+            Some(index) => (&text[..index], text[index..].trim()),
+            None => (text, ""),
+        };
+        match name {
+            "ifdef" | "ifndef" => {
+                let defined = self
+                    .expect_name(file, range.clone(), name, argument)
+                    .map_or(false, |macro_name| self.defines.contains_key(macro_name));
+                conditions.push(Condition {
+                    active: active && defined == (name == "ifdef"),
+                    has_else: false,
+                    file,
+                    range,
+                });
+            }
+            "else" | "endif" => {
+                let enclosing = conditions.len() < 2
+                    || conditions[..conditions.len() - 1]
+                        .iter()
+                        .all(|condition| condition.active);
+                match conditions.last_mut() {
+                    Some(_) if name == "endif" => {
+                        conditions.pop();
+                    }
+                    Some(condition) if !condition.has_else => {
+                        condition.has_else = true;
+                        condition.active = enclosing && !condition.active;
+                    }
+                    Some(_) => self.report(file, range, "`#else` after `#else`".into()),
+                    None => self.report(file, range, format!("`#{}` without `#ifdef`", name)),
+                }
+            }
+            // the other directives in a skipped branch aren't looked at
+            _ if !active => {}
+            "define" => {
+                let (macro_name, value) = match argument.find(char::is_whitespace) {
+                    Some(index) => (&argument[..index], argument[index..].trim()),
+                    None => (argument, ""),
+                };
+                if let Some(macro_name) = self.expect_name(file, range.clone(), name, macro_name) {
+                    let previous = self.defines.insert(macro_name.to_string(), value.to_string());
+                    if previous.map_or(false, |previous| previous != value) {
+                        let message = format!("macro `{}` is redefined", macro_name);
+                        let span = FileSpan::new(self.file_ids[file], range);
+                        self.error_reporter.add_warning(span, message);
+                    }
+                }
+            }
+            "include" => self.include(file, range, argument),
+            _ => self.report(file, range, format!("unknown directive `#{}`", name)),
+        }
+    }
+
+    /// the macro name after `#define`, `#ifdef` or `#ifndef`
+    fn expect_name<'s>(
+        &mut self,
+        file: usize,
+        range: Range<usize>,
+        directive: &str,
+        name: &'s str,
+    ) -> Option<&'s str> {
+        let mut chars = name.chars();
+        let is_name = chars
+            .next()
+            .map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if is_name {
+            Some(name)
+        } else {
+            self.report(file, range, format!("expected a macro name after `#{}`", directive));
+            None
+        }
+    }
+
+    fn include(&mut self, file: usize, range: Range<usize>, argument: &str) {
+        let path = match argument.strip_prefix('"').and_then(|path| path.strip_suffix('"')) {
+            Some(path) if !path.is_empty() => path,
+            _ => {
+                self.report(file, range, "expected `\"file\"` after `#include`".into());
+                return;
+            }
+        };
+        // the path is relative to the file containing the `#include`
+        let path = match Path::new(&self.source_map.files[file].name).parent() {
+            Some(directory) => directory.join(path).to_string_lossy().to_string(),
+            None => path.to_string(),
+        };
+        let cycle = self
+            .include_stack
+            .iter()
+            .position(|&including| self.source_map.files[including].name == path);
+        if let Some(first) = cycle {
+            let mut names: Vec<&str> = self.include_stack[first..]
+                .iter()
+                .map(|&including| self.source_map.files[including].name.as_str())
+                .collect();
+            names.push(&path);
+            let message = format!("include cycle: {}", names.join(" -> "));
+            self.report(file, range, message);
+            return;
+        }
+        match self.loader.load(&path) {
+            Some(source) => {
+                let included = self.add_file(path, source);
+                self.process_file(included);
+            }
+            None => self.report(file, range, format!("can't read the included file `{}`", path)),
+        }
+    }
+
+    /// copy the line, the defined names outside of comments and literals are expanded
+    fn expand_line(&mut self, file: usize, line_start: usize, line: &str, in_comment: &mut bool) {
+        let bytes = line.as_bytes();
+        let mut copied = 0;
+        let mut index = 0;
+        while index < bytes.len() {
+            if *in_comment {
+                match line[index..].find("*/") {
+                    Some(end) => {
+                        *in_comment = false;
+                        index += end + 2;
+                    }
+                    None => index = bytes.len(),
+                }
+                continue;
+            }
+            match bytes[index] {
+                b'/' if bytes.get(index + 1) == Some(&b'*') => {
+                    *in_comment = true;
+                    index += 2;
+                }
+                quote @ (b'"' | b'\'') => index = skip_literal(bytes, index, quote),
+                b'0'..=b'9' => index = skip_word(bytes, index),
+                c if c.is_ascii_alphabetic() || c == b'_' => {
+                    let end = skip_word(bytes, index);
+                    let name = &line[index..end];
+                    if self.defines.contains_key(name) {
+                        let expansion = self.expand(name, &mut vec![]);
+                        let start = line_start + copied;
+                        self.push(file, start..line_start + index, &line[copied..index]);
+                        self.push(file, line_start + index..line_start + end, &expansion);
+                        copied = end;
+                    }
+                    index = end;
+                }
+                _ => index += 1,
+            }
+        }
+        self.push(file, line_start + copied..line_start + line.len(), &line[copied..]);
+    }
+
+    /// the value of the macro, the names defined in it are expanded except the ones being
+    /// expanded, so a macro can't expand to itself
+    fn expand(&self, name: &str, expanding: &mut Vec<String>) -> String {
+        expanding.push(name.to_string());
+        let value = &self.defines[name];
+        let bytes = value.as_bytes();
+        let mut expansion = String::new();
+        let mut copied = 0;
+        let mut index = 0;
+        while index < bytes.len() {
+            match bytes[index] {
+                quote @ (b'"' | b'\'') => index = skip_literal(bytes, index, quote),
+                b'0'..=b'9' => index = skip_word(bytes, index),
+                c if c.is_ascii_alphabetic() || c == b'_' => {
+                    let end = skip_word(bytes, index);
+                    let inner = &value[index..end];
+                    if self.defines.contains_key(inner) && !expanding.iter().any(|e| e == inner) {
+                        expansion.push_str(&value[copied..index]);
+                        expansion.push_str(&self.expand(inner, expanding));
+                        copied = end;
+                    }
+                    index = end;
+                }
+                _ => index += 1,
+            }
+        }
+        expansion.push_str(&value[copied..]);
+        expanding.pop();
+        expansion
+    }
+
+    fn push(&mut self, file: usize, source: Range<usize>, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        let start = self.output.len();
+        self.output.push_str(text);
+        self.source_map.segments.push(Segment {
+            output: start..self.output.len(),
+            file,
+            source,
+        });
+    }
+}
+
+/// the index after the identifier or number starting at `start`
+fn skip_word(bytes: &[u8], start: usize) -> usize {
+    start
+        + bytes[start..]
+            .iter()
+            .take_while(|c| c.is_ascii_alphanumeric() || **c == b'_')
+            .count()
+}
+
+/// the index after the char or string literal starting at `start`, or the end of the line if
+/// the literal isn't closed
+fn skip_literal(bytes: &[u8], start: usize, quote: u8) -> usize {
+    let mut index = start + 1;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'\\' => index += 2,
+            c if c == quote => return index + 1,
+            _ => index += 1,
+        }
+    }
+    bytes.len()
+}
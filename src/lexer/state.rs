@@ -3,6 +3,11 @@ pub enum State {
     START,
     InDivide,
     // INMULTPLY,
+    // the operators which may be followed by `=`, or doubled like `++`
+    InPlus,
+    InMinus,
+    InMultiply,
+    InMod,
     InNum,
     InId,
     Done,
@@ -13,5 +18,10 @@ pub enum State {
     InComment,
     InEndComment,
     InAnd,
-    InOr
+    InOr,
+    // inside the quotes of a char or string literal, and right after a `\` in them
+    InChar,
+    InCharEscape,
+    InString,
+    InStringEscape,
 }
@@ -9,6 +9,16 @@ pub enum KeywordType {
     VOID,
     WHILE,
     BOOL,
+    FOR,
+    DO,
+    BREAK,
+    CONTINUE,
+    CHAR,
+    STRUCT,
+    SWITCH,
+    CASE,
+    DEFAULT,
+    CONST,
 }
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 pub enum TokenType {
@@ -19,6 +29,9 @@ pub enum TokenType {
     Id,
     NumberLiteral,
     BooleanLiteral,
+    // the content keeps the quotes and the escape sequences
+    CharLiteral,
+    StringLiteral,
     Keyword(KeywordType),
     /* special symbols */
     Plus,
@@ -33,8 +46,26 @@ pub enum TokenType {
     And,
     Or,
     Ne,
+    Not,
+    Mod,
+    BitAnd,
+    BitOr,
+    BitXor,
+    BitNot,
+    Shl,
+    Shr,
+    PlusAssign,
+    MinusAssign,
+    MultiplyAssign,
+    DivideAssign,
+    ModAssign,
+    Increment,
+    Decrement,
     Semi,
     Comma,
+    Dot,
+    Colon,
+    Question,
     Lparen, // (
     Rparen, // )
     Lbrack, // [
@@ -51,6 +82,8 @@ impl fmt::Display for TokenType {
             TokenType::Id => "id",
             TokenType::NumberLiteral => "numberLiteral",
             TokenType::BooleanLiteral => "booleanLiteral",
+            TokenType::CharLiteral => "charLiteral",
+            TokenType::StringLiteral => "stringLiteral",
             TokenType::Keyword(k) => match k {
                 KeywordType::IF => "if",
                 KeywordType::ELSE => "else",
@@ -59,6 +92,16 @@ impl fmt::Display for TokenType {
                 KeywordType::VOID => "void",
                 KeywordType::WHILE => "while",
                 KeywordType::BOOL => "bool",
+                KeywordType::FOR => "for",
+                KeywordType::DO => "do",
+                KeywordType::BREAK => "break",
+                KeywordType::CONTINUE => "continue",
+                KeywordType::CHAR => "char",
+                KeywordType::STRUCT => "struct",
+                KeywordType::SWITCH => "switch",
+                KeywordType::CASE => "case",
+                KeywordType::DEFAULT => "default",
+                KeywordType::CONST => "const",
             },
             TokenType::Plus => "`+`",
             TokenType::Minus => "`-`",
@@ -72,6 +115,9 @@ impl fmt::Display for TokenType {
             TokenType::Ne => "`!=`",
             TokenType::Semi => "`;`",
             TokenType::Comma => "`,`",
+            TokenType::Dot => "`.`",
+            TokenType::Colon => "`:`",
+            TokenType::Question => "`?`",
             TokenType::Lparen => "`(`",
             TokenType::Rparen => "`)`",
             TokenType::Lbrack => "`[`",
@@ -81,7 +127,22 @@ impl fmt::Display for TokenType {
             TokenType::Comment => "comment",
             TokenType::Assign => "`=`",
             TokenType::And => "`&&`",
-            TokenType::Or => "`||`"
+            TokenType::Or => "`||`",
+            TokenType::Not => "`!`",
+            TokenType::Mod => "`%`",
+            TokenType::BitAnd => "`&`",
+            TokenType::BitOr => "`|`",
+            TokenType::BitXor => "`^`",
+            TokenType::BitNot => "`~`",
+            TokenType::Shl => "`<<`",
+            TokenType::Shr => "`>>`",
+            TokenType::PlusAssign => "`+=`",
+            TokenType::MinusAssign => "`-=`",
+            TokenType::MultiplyAssign => "`*=`",
+            TokenType::DivideAssign => "`/=`",
+            TokenType::ModAssign => "`%=`",
+            TokenType::Increment => "`++`",
+            TokenType::Decrement => "`--`",
         };
         f.write_str(string)
     }
@@ -24,7 +24,42 @@ pub fn keyword_or_id(s: &str) -> TokenType {
         "void" => TokenType::Keyword(KeywordType::VOID),
         "while" => TokenType::Keyword(KeywordType::WHILE),
         "bool" => TokenType::Keyword(KeywordType::BOOL),
+        "for" => TokenType::Keyword(KeywordType::FOR),
+        "do" => TokenType::Keyword(KeywordType::DO),
+        "break" => TokenType::Keyword(KeywordType::BREAK),
+        "continue" => TokenType::Keyword(KeywordType::CONTINUE),
+        "char" => TokenType::Keyword(KeywordType::CHAR),
+        "struct" => TokenType::Keyword(KeywordType::STRUCT),
+        "switch" => TokenType::Keyword(KeywordType::SWITCH),
+        "case" => TokenType::Keyword(KeywordType::CASE),
+        "default" => TokenType::Keyword(KeywordType::DEFAULT),
+        "const" => TokenType::Keyword(KeywordType::CONST),
         "true" | "false" => TokenType::BooleanLiteral,
         _ => TokenType::Id,
     }
 }
+
+/// decode the escape sequences between the quotes of a char or string literal, the unknown
+/// escape sequence is returned as the error
+pub fn unescape(s: &str) -> Result<String, String> {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some('0') => result.push('\0'),
+            Some('\\') => result.push('\\'),
+            Some('\'') => result.push('\''),
+            Some('"') => result.push('"'),
+            Some(c) => return Err(format!("\\{}", c)),
+            None => return Err("\\".to_string()),
+        }
+    }
+    Ok(result)
+}
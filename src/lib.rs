@@ -1,9 +1,19 @@
 pub mod error_emit;
+pub mod fmt;
+pub mod host;
 pub mod interpreter;
 pub mod lexer;
+pub mod native;
 mod macros;
 pub mod parser;
+pub mod repl;
+pub mod semantic;
 #[cfg(target_arch = "x86_64")]
 pub mod vm;
 pub use macros::*;
 pub mod util;
+
+/// the number of nested calls after which a call is a stack overflow, in both backends. An
+/// interpreted call takes some kilobytes of native stack, the `cminus` binary runs on a thread
+/// with a large stack
+pub const MAX_CALL_DEPTH: usize = 10_000;
@@ -1,42 +1,410 @@
-mod error_emit;
-mod interpreter;
-mod lexer;
-mod parser;
-use lexer::lex::Lexer;
-mod util;
-use parser::{parse::Parser, visitor::AstPrinter};
-use std::fs::read_to_string;
-use std::path;
-use std::time::Instant;
+use std::{
+    env,
+    fs::read_to_string,
+    io::{self, BufRead, Write},
+    panic, process, thread,
+};
+
+use serde_json::Value;
+use tinylang_rs::{
+    error_emit::ErrorReporter,
+    fmt::Formatter,
+    host::StdioHost,
+    interpreter,
+    lexer::{
+        lex::Lexer,
+        preprocess::{FileSystemLoader, Preprocessor, SourceMap},
+        token::Token,
+    },
+    parser::{parse::Parser, visitor::AstPrinter, Program},
+    repl::Repl,
+    semantic,
+};
+#[cfg(target_arch = "x86_64")]
+use tinylang_rs::vm::{op_code, EmitOperationCode, Vm};
 #[cfg(target_arch = "x86_64")]
 #[global_allocator]
 static GLOBAL: mimallocator::Mimalloc = mimallocator::Mimalloc;
 
-fn main() -> Result<(), std::io::Error> {
-    let path = path::Path::new("tests/fixtures/interpreter/test.txt");
-    let source_code = read_to_string(path)?;
-    let mut lex = Lexer::new(&source_code);
-    let list = lex.lex();
-    // println!("{:?}", list);
-    let mut parser = Parser::new(list, &source_code);
-    let mut res = match parser.parse_program() {
-        Ok(prog) => prog,
-        Err(_) => {
-            parser.error_reporter.emit_std()?;
-            return Ok(());
-        }
-    };
-    let start = Instant::now();
-    match interpreter::interpret(&mut res, false) {
-        Ok(env) => {
-            println!("{}", env.get_std_simulator_string());
-        }
-        Err(_) => {
-            println!("interpreter error",);
-        }
-    };
-    println!("total: {:?}", start.elapsed());
-    // let mut walker = AstPrinter::default();
-    // println!("{}", walker.print_ast(&res));
-    Ok(())
+// the exit status when the program can't be run, a program run to the end exits with the int
+// its `main` returns, or 0
+const EXIT_USAGE: i32 = 64;
+const EXIT_SYNTAX: i32 = 65;
+const EXIT_NO_INPUT: i32 = 66;
+const EXIT_SEMANTIC: i32 = 67;
+const EXIT_RUNTIME: i32 = 70;
+// `fmt --check` exits with it when the file isn't formatted
+const EXIT_UNFORMATTED: i32 = 1;
+// the interpreter evaluates a call by recursing natively, the commands run on a thread with
+// room for `MAX_CALL_DEPTH` nested calls
+const STACK_SIZE: usize = 256 << 20;
+
+const USAGE: &str = "usage: cminus <command> [options] <file>
+
+commands:
+    run [--backend interp|vm]         check and run the program
+    check                             report the syntax, name and type errors
+    tokens                            print the tokens of the preprocessed source
+    ast [--format text|json|dot]      print the syntax tree
+    disasm                            print the instructions of the vm
+    fmt [--check]                     print the formatted source, or check that it's formatted
+    repl                              evaluate the input line by line";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Backend {
+    Interpreter,
+    Vm,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AstFormat {
+    Text,
+    Json,
+    Dot,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Command {
+    Run(Backend),
+    Check,
+    Tokens,
+    Ast(AstFormat),
+    Disasm,
+    Fmt { check: bool },
+    Repl,
+}
+
+impl Command {
+    /// the command and the file to work on, only `repl` has no file. The error is the message to
+    /// print above the usage
+    fn parse(args: &[String]) -> Result<(Command, Option<&str>), String> {
+        let (name, args) = args.split_first().ok_or("a command is expected")?;
+        let mut command = match name.as_str() {
+            "run" => Command::Run(Backend::Interpreter),
+            "check" => Command::Check,
+            "tokens" => Command::Tokens,
+            "ast" => Command::Ast(AstFormat::Text),
+            "disasm" => Command::Disasm,
+            "fmt" => Command::Fmt { check: false },
+            "repl" => Command::Repl,
+            _ => return Err(format!("unknown command `{}`", name)),
+        };
+        let mut file = None;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                if file.replace(arg.as_str()).is_some() {
+                    return Err("only one file is expected".into());
+                }
+                continue;
+            }
+            if let (Command::Fmt { .. }, "--check") = (command, arg.as_str()) {
+                command = Command::Fmt { check: true };
+                continue;
+            }
+            // both `--format json` and `--format=json` are accepted
+            let (option, value) = match arg.find('=') {
+                Some(index) => (&arg[..index], Some(arg[index + 1..].to_string())),
+                None => (arg.as_str(), args.next().cloned()),
+            };
+            let value = value.ok_or_else(|| format!("`{}` expects a value", option))?;
+            command = match (command, option, value.as_str()) {
+                (Command::Run(_), "--backend", "interp") => Command::Run(Backend::Interpreter),
+                (Command::Run(_), "--backend", "vm") => Command::Run(Backend::Vm),
+                (Command::Ast(_), "--format", "text") => Command::Ast(AstFormat::Text),
+                (Command::Ast(_), "--format", "json") => Command::Ast(AstFormat::Json),
+                (Command::Ast(_), "--format", "dot") => Command::Ast(AstFormat::Dot),
+                (Command::Run(_), "--backend", _) | (Command::Ast(_), "--format", _) => {
+                    return Err(format!("unknown value `{}` for `{}`", value, option));
+                }
+                _ => return Err(format!("unknown option `{}` for `{}`", option, name)),
+            };
+        }
+        match (command, file) {
+            (Command::Repl, Some(_)) => Err("`repl` doesn't take a file".into()),
+            (Command::Repl, None) => Ok((command, None)),
+            (_, None) => Err("a file is expected".into()),
+            (_, file) => Ok((command, file)),
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+    let code = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || match Command::parse(&args) {
+            Ok((Command::Repl, _)) => repl(),
+            Ok((command, file_name)) => {
+                drive(command, file_name.unwrap_or_default()).unwrap_or_else(|code| code)
+            }
+            Err(message) => {
+                eprintln!("cminus: {}\n\n{}", message, USAGE);
+                EXIT_USAGE
+            }
+        })
+        .expect("failed to spawn the thread running the command")
+        .join()
+        .unwrap_or_else(|payload| panic::resume_unwind(payload));
+    // `process::exit` doesn't flush the output of a program which stopped in the middle of a line
+    let _ = io::stdout().flush();
+    process::exit(code);
+}
+
+/// run the stages the command needs, the error is the exit status once the diagnostics are
+/// printed
+fn drive(command: Command, file_name: &str) -> Result<i32, i32> {
+    let source = read_to_string(file_name).map_err(|error| {
+        eprintln!("cminus: can't read `{}`: {}", file_name, error);
+        EXIT_NO_INPUT
+    })?;
+    if let Command::Fmt { check } = command {
+        return format(file_name, &source, check);
+    }
+    let mut preprocessor = Preprocessor::new(Box::new(FileSystemLoader));
+    let preprocessed = preprocessor.preprocess(file_name, &source);
+    emit(&mut preprocessor.error_reporter, None);
+    let preprocessed = preprocessed.map_err(|_| EXIT_SYNTAX)?;
+    let source = preprocessed.source;
+    let source_map = preprocessed.source_map;
+
+    let tokens = Lexer::new(&source).lex();
+    if command == Command::Tokens {
+        print_tokens(&tokens, &source_map);
+        return Ok(0);
+    }
+    let mut parser = Parser::new(tokens, file_name, &source);
+    let mut program = parser.parse_program().map_err(|_| {
+        emit(&mut parser.error_reporter, Some(&source_map));
+        EXIT_SYNTAX
+    })?;
+    if let Command::Ast(format) = command {
+        print_ast(&program, format);
+        return Ok(0);
+    }
+
+    let (mut warning_reporter, checked) = semantic::check(file_name, &source, &program);
+    emit(&mut warning_reporter, Some(&source_map));
+    checked.map_err(|mut error_reporter| {
+        emit(&mut error_reporter, Some(&source_map));
+        EXIT_SEMANTIC
+    })?;
+
+    match command {
+        Command::Run(Backend::Interpreter) => {
+            match interpreter::interpret(&mut program, Box::new(StdioHost)) {
+                Ok(env) => Ok(env.main_result().unwrap_or(0)),
+                Err(error) => {
+                    let _ = io::stdout().flush();
+                    emit(&mut error.report(file_name, &source), Some(&source_map));
+                    Err(EXIT_RUNTIME)
+                }
+            }
+        }
+        Command::Run(Backend::Vm) => run_vm(&mut program, file_name, &source, &source_map),
+        Command::Disasm => disassemble(&mut program, &source_map),
+        _ => Ok(0),
+    }
+}
+
+/// read the input from stdin until it ends or `:quit` is entered, the input continues on the
+/// next line while a brace or a parenthesis is open
+fn repl() -> i32 {
+    println!("cminus repl, enter `:help` for the commands and `:quit` to leave");
+    let mut repl = Repl::new(Box::new(StdioHost));
+    let mut input = String::new();
+    loop {
+        print(if input.is_empty() { "> " } else { "... " });
+        let _ = io::stdout().flush();
+        let mut line = String::new();
+        match io::stdin().lock().read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => input += &line,
+        }
+        if input.trim() == ":quit" {
+            break;
+        }
+        // an empty line ends the input even if a brace is left open, to show what is wrong
+        if Repl::is_complete(&input) || line.trim().is_empty() {
+            print(&repl.eval(&input));
+            input.clear();
+        }
+    }
+    0
+}
+
+/// the directives are formatted with the file, not expanded
+fn format(file_name: &str, source: &str, check: bool) -> Result<i32, i32> {
+    let mut formatter = Formatter::new(file_name, source);
+    let formatted = formatter.format().map_err(|_| {
+        emit(&mut formatter.error_reporter, None);
+        EXIT_SYNTAX
+    })?;
+    if !check {
+        print(&formatted);
+        Ok(0)
+    } else if formatted == source {
+        Ok(0)
+    } else {
+        eprintln!("cminus: `{}` isn't formatted", file_name);
+        Err(EXIT_UNFORMATTED)
+    }
+}
+
+/// print the diagnostics, pointed at the original files if the source was preprocessed
+fn emit(reporter: &mut ErrorReporter, source_map: Option<&SourceMap>) {
+    if reporter.is_empty() {
+        return;
+    }
+    if let Some(source_map) = source_map {
+        source_map.remap(reporter);
+    }
+    // nothing more can be reported if stderr is closed
+    let _ = reporter.emit_std();
+}
+
+fn location(source_map: &SourceMap, offset: usize) -> String {
+    match source_map.position(offset) {
+        Some((file_name, line, column)) => format!("{}:{}:{}", file_name, line, column),
+        None => "?".into(),
+    }
+}
+
+/// write the whole output of a command, a closed pipe like `cminus ast main.cm | head` isn't an
+/// error
+fn print(output: &str) {
+    let _ = io::stdout().write_all(output.as_bytes());
+}
+
+fn print_tokens(tokens: &[Token], source_map: &SourceMap) {
+    let mut output = String::new();
+    for token in tokens {
+        let location = location(source_map, token.start_index);
+        let token_type = format!("{:?}", token.token_type);
+        output += &format!("{:<16} {:<24} {:?}\n", location, token_type, token.content);
+    }
+    print(&output);
+}
+
+fn print_ast(program: &Program, format: AstFormat) {
+    match format {
+        AstFormat::Text => print(&AstPrinter::default().print_ast(program)),
+        AstFormat::Json => match serde_json::to_string_pretty(program) {
+            Ok(json) => print(&(json + "\n")),
+            Err(error) => eprintln!("cminus: {}", error),
+        },
+        AstFormat::Dot => match serde_json::to_value(program) {
+            Ok(json) => print(&dot(&json)),
+            Err(error) => eprintln!("cminus: {}", error),
+        },
+    }
+}
+
+/// a graphviz digraph of the json form of the tree, objects and arrays are nodes and scalars
+/// are listed in the label of their node. An enum variant is labeled by its name instead of
+/// being a node of its own
+fn dot(json: &Value) -> String {
+    fn node(label: String, value: &Value, graph: &mut Vec<String>, next: &mut usize) -> usize {
+        // `{ "Variant": { .. } }` is how serde writes an enum variant
+        if let Value::Object(map) = value {
+            if let Some((variant, inner)) = map.iter().next().filter(|_| map.len() == 1) {
+                if inner.is_object() || inner.is_array() {
+                    let label = if label.parse::<usize>().is_ok() {
+                        variant.clone()
+                    } else {
+                        format!("{}: {}", label, variant)
+                    };
+                    return node(label, inner, graph, next);
+                }
+            }
+        }
+        let id = *next;
+        *next += 1;
+        // the label is known once the scalar fields are collected
+        let line = graph.len();
+        graph.push(String::new());
+        let children: Vec<(String, &Value)> = match value {
+            Value::Object(map) => map.iter().map(|(key, value)| (key.clone(), value)).collect(),
+            Value::Array(values) => values
+                .iter()
+                .enumerate()
+                .map(|(index, value)| (index.to_string(), value))
+                .collect(),
+            _ => vec![],
+        };
+        let mut fields = vec![escape(&label)];
+        for (key, child) in children {
+            match child {
+                Value::Null => {}
+                Value::Object(_) | Value::Array(_) => {
+                    let child_id = node(key, child, graph, next);
+                    graph.push(format!("    n{} -> n{};", id, child_id));
+                }
+                scalar => fields.push(escape(&format!("{}: {}", key, scalar))),
+            }
+        }
+        graph[line] = format!("    n{} [label=\"{}\"];", id, fields.join("\\n"));
+        id
+    }
+    fn escape(field: &str) -> String {
+        field.replace('\\', "\\\\").replace('"', "\\\"")
+    }
+    let mut graph = vec![];
+    node("Program".into(), json, &mut graph, &mut 0);
+    format!("digraph ast {{\n    node [shape=box];\n{}\n}}\n", graph.join("\n"))
+}
+
+#[cfg(target_arch = "x86_64")]
+fn compile(program: &mut Program) -> Result<Vm, i32> {
+    let mut vm = Vm::with_host(Box::new(StdioHost));
+    program.emit(&mut vm).map_err(|error| {
+        eprintln!("cminus: {}", error);
+        EXIT_SEMANTIC
+    })?;
+    Ok(vm)
+}
+
+#[cfg(target_arch = "x86_64")]
+fn run_vm(
+    program: &mut Program,
+    file_name: &str,
+    source: &str,
+    source_map: &SourceMap,
+) -> Result<i32, i32> {
+    let mut vm = compile(program)?;
+    match vm.exec() {
+        Ok(()) => Ok(vm.main_result().unwrap_or(0)),
+        Err(error) => {
+            let _ = io::stdout().flush();
+            emit(&mut vm.report(&error, file_name, source), Some(source_map));
+            Err(EXIT_RUNTIME)
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn disassemble(program: &mut Program, source_map: &SourceMap) -> Result<i32, i32> {
+    let vm = compile(program)?;
+    let output: String = vm
+        .functions()
+        .iter()
+        .map(|function| op_code::disassemble(function, |range| location(source_map, range.start)))
+        .collect();
+    print(&output);
+    Ok(0)
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn run_vm(_: &mut Program, _: &str, _: &str, _: &SourceMap) -> Result<i32, i32> {
+    eprintln!("cminus: the vm isn't available on this target");
+    Err(EXIT_USAGE)
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn disassemble(program: &mut Program, source_map: &SourceMap) -> Result<i32, i32> {
+    run_vm(program, "", "", source_map)
 }
@@ -0,0 +1,153 @@
+use std::{fmt::Debug, rc::Rc};
+
+use fxhash::FxHashMap;
+use smol_str::SmolStr;
+
+use crate::semantic::{FunctionSignature, Type};
+
+/// the values which can cross the boundary between a script and a native function
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NativeValue {
+    Int(i32),
+    Boolean(bool),
+    Void,
+}
+
+impl NativeValue {
+    pub fn ty(&self) -> Type {
+        match self {
+            NativeValue::Int(_) => Type::Int,
+            NativeValue::Boolean(_) => Type::Boolean,
+            NativeValue::Void => Type::Void,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NativeError {
+    // the arguments don't match the signature, reported before the closure is called
+    ArgumentMismatch(String),
+    // returned by the closure
+    Failed(String),
+}
+
+type NativeClosure = dyn Fn(&[NativeValue]) -> Result<NativeValue, String>;
+
+pub struct NativeFunction {
+    name: SmolStr,
+    signature: FunctionSignature,
+    closure: Box<NativeClosure>,
+}
+
+impl NativeFunction {
+    pub fn name(&self) -> &SmolStr {
+        &self.name
+    }
+
+    pub fn signature(&self) -> &FunctionSignature {
+        &self.signature
+    }
+
+    /// check the arguments against the signature, then call the closure
+    pub fn call(&self, arguments: &[NativeValue]) -> Result<NativeValue, NativeError> {
+        let params = &self.signature.params;
+        if params.len() != arguments.len() {
+            return Err(NativeError::ArgumentMismatch(format!(
+                "function `{}` takes {} arguments but {} were supplied",
+                self.name,
+                params.len(),
+                arguments.len()
+            )));
+        }
+        for (param, argument) in params.iter().zip(arguments.iter()) {
+            if *param != argument.ty() {
+                return Err(NativeError::ArgumentMismatch(format!(
+                    "mismatched types, expected `{}`, found `{}`",
+                    param,
+                    argument.ty()
+                )));
+            }
+        }
+        let value = (self.closure)(arguments).map_err(NativeError::Failed)?;
+        if value.ty() != self.signature.return_type {
+            return Err(NativeError::Failed(format!(
+                "function `{}` should return `{}`, found `{}`",
+                self.name,
+                self.signature.return_type,
+                value.ty()
+            )));
+        }
+        Ok(value)
+    }
+}
+
+impl Debug for NativeFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NativeFunction")
+            .field("name", &self.name)
+            .field("signature", &self.signature)
+            .finish()
+    }
+}
+
+/// the native functions registered by the embedder, shared by the semantic passes and both
+/// backends. A function is called through its index, like `Vm`'s function table
+#[derive(Debug, Clone, Default)]
+pub struct Natives {
+    functions: Vec<Rc<NativeFunction>>,
+    function_table: FxHashMap<SmolStr, usize>,
+}
+
+impl Natives {
+    /// only `int` and `bool` params and `int`, `bool` or `void` return type are supported.
+    /// Registering a name twice replaces the previous function
+    pub fn register(
+        &mut self,
+        name: &str,
+        signature: FunctionSignature,
+        closure: impl Fn(&[NativeValue]) -> Result<NativeValue, String> + 'static,
+    ) {
+        assert!(
+            signature
+                .params
+                .iter()
+                .all(|param| matches!(param, Type::Int | Type::Boolean)),
+            "params of native function `{}` should be `int` or `bool`",
+            name
+        );
+        assert!(
+            matches!(signature.return_type, Type::Int | Type::Boolean | Type::Void),
+            "native function `{}` should return `int`, `bool` or `void`",
+            name
+        );
+        let name = SmolStr::from(name);
+        let function = Rc::new(NativeFunction {
+            name: name.clone(),
+            signature,
+            closure: Box::new(closure),
+        });
+        match self.function_table.get(&name) {
+            Some(&index) => self.functions[index] = function,
+            None => {
+                self.function_table.insert(name, self.functions.len());
+                self.functions.push(function);
+            }
+        }
+    }
+
+    pub fn resolve(&self, name: &str) -> Option<usize> {
+        self.function_table.get(name).copied()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Rc<NativeFunction>> {
+        self.resolve(name).map(|index| &self.functions[index])
+    }
+
+    pub fn function(&self, index: usize) -> &Rc<NativeFunction> {
+        &self.functions[index]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Rc<NativeFunction>> {
+        self.functions.iter()
+    }
+}
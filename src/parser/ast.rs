@@ -1,17 +1,18 @@
 use std::fmt::Display;
 
 use codespan_drive::CodeSpan;
+use serde::Serialize;
 use smol_str::SmolStr;
 use super::span::Codespan;
 
-#[derive(Debug, Clone, CodeSpan)]
+#[derive(Debug, Clone, CodeSpan, Serialize)]
 pub struct Program {
     pub(crate) declarations: Vec<Declaration>,
     pub start: usize,
     pub end: usize,
 }
 
-#[derive(Debug, Clone, CodeSpan)]
+#[derive(Debug, Clone, CodeSpan, Serialize)]
 pub struct FunctionDeclaration {
     pub(crate) type_specifier: TypeSpecifier,
     pub(crate) id: Identifier,
@@ -20,94 +21,251 @@ pub struct FunctionDeclaration {
     pub start: usize,
     pub end: usize,
 }
-#[derive(Debug, Clone, CodeSpan)]
+/// `int f(int x);`, declares a function which is defined elsewhere in the program
+#[derive(Debug, Clone, CodeSpan, Serialize)]
+pub struct FunctionPrototype {
+    pub(crate) type_specifier: TypeSpecifier,
+    pub(crate) id: Identifier,
+    pub(crate) params: Params,
+    pub start: usize,
+    pub end: usize,
+}
+#[derive(Debug, Clone, CodeSpan, Serialize)]
 pub struct VarDeclaration {
     pub(crate) type_specifier: TypeSpecifier,
     pub(crate) id: Identifier,
-    pub(crate) num: Option<NumberLiteral>,
+    /// a `const` variable is initialized and never assigned
+    pub(crate) is_const: bool,
+    /// the size of each dimension of an array, `int m[3][4]` has two, empty for a scalar
+    pub(crate) dimensions: Vec<ArraySize>,
     pub(crate) initializer: Option<Expression>,
-    pub(crate) array_initializer: Option<Vec<Expression>>,
+    pub(crate) array_initializer: Option<Vec<Initializer>>,
     pub start: usize,
     pub end: usize,
 }
 
-#[derive(Debug, Clone)]
+/// the size of a dimension of an array, a number or the name of a global `const int`, which is
+/// checked by the resolver
+#[derive(Debug, Clone, Serialize)]
+pub enum ArraySize {
+    Number(NumberLiteral),
+    Constant(Identifier),
+}
+
+impl ArraySize {
+    /// the size, a constant is looked up by `constant`
+    pub(crate) fn value(&self, constant: &dyn Fn(&SmolStr) -> Option<i32>) -> Option<i32> {
+        match self {
+            ArraySize::Number(num) => Some(num.value),
+            ArraySize::Constant(id) => constant(&id.value),
+        }
+    }
+}
+
+impl Codespan for ArraySize {
+    fn start(&self) -> usize {
+        match self {
+            ArraySize::Number(num) => num.start,
+            ArraySize::Constant(id) => id.start,
+        }
+    }
+
+    fn end(&self) -> usize {
+        match self {
+            ArraySize::Number(num) => num.end,
+            ArraySize::Constant(id) => id.end,
+        }
+    }
+
+    fn set_start(&mut self, start: usize) {
+        match self {
+            ArraySize::Number(num) => num.start = start,
+            ArraySize::Constant(id) => id.start = start,
+        }
+    }
+
+    fn set_end(&mut self, end: usize) {
+        match self {
+            ArraySize::Number(num) => num.end = end,
+            ArraySize::Constant(id) => id.end = end,
+        }
+    }
+}
+
+/// an element of an array initializer, the parser checks that the rows of a multi-dimensional
+/// array are initialized by lists and the elements of the last dimension by expressions
+#[derive(Debug, Clone, Serialize)]
+pub enum Initializer {
+    Expression(Expression),
+    List(InitializerList),
+}
+
+impl Codespan for Initializer {
+    fn start(&self) -> usize {
+        match self {
+            Initializer::Expression(expr) => expr.start(),
+            Initializer::List(list) => list.start,
+        }
+    }
+
+    fn end(&self) -> usize {
+        match self {
+            Initializer::Expression(expr) => expr.end(),
+            Initializer::List(list) => list.end,
+        }
+    }
+
+    fn set_start(&mut self, start: usize) {
+        match self {
+            Initializer::Expression(expr) => expr.set_start(start),
+            Initializer::List(list) => list.start = start,
+        };
+    }
+
+    fn set_end(&mut self, end: usize) {
+        match self {
+            Initializer::Expression(expr) => expr.set_end(end),
+            Initializer::List(list) => list.end = end,
+        };
+    }
+}
+
+/// `{1, 2}` in `int m[2][2] = {{1, 2}, {3}};`
+#[derive(Debug, Clone, CodeSpan, Serialize)]
+pub struct InitializerList {
+    pub(crate) elements: Vec<Initializer>,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub enum Declaration {
     FunctionDeclaration(FunctionDeclaration),
+    FunctionPrototype(FunctionPrototype),
     VarDeclaration(VarDeclaration),
+    StructDeclaration(StructDeclaration),
+    Error(ErrorNode),
 }
 
 impl Codespan for Declaration {
     fn start(&self) -> usize {
         match self {
             Declaration::FunctionDeclaration(decl) => decl.start,
+            Declaration::FunctionPrototype(decl) => decl.start,
             Declaration::VarDeclaration(decl) => decl.start,
+            Declaration::StructDeclaration(decl) => decl.start,
+            Declaration::Error(decl) => decl.start,
         }
     }
 
     fn end(&self) -> usize {
         match self {
             Declaration::FunctionDeclaration(decl) => decl.end,
+            Declaration::FunctionPrototype(decl) => decl.end,
             Declaration::VarDeclaration(decl) => decl.end,
+            Declaration::StructDeclaration(decl) => decl.end,
+            Declaration::Error(decl) => decl.end,
         }
     }
 
     fn set_start(&mut self, start: usize) {
         match self {
             Declaration::FunctionDeclaration(decl) => decl.start = start,
+            Declaration::FunctionPrototype(decl) => decl.start = start,
             Declaration::VarDeclaration(decl) => decl.start = start,
+            Declaration::StructDeclaration(decl) => decl.start = start,
+            Declaration::Error(decl) => decl.start = start,
         };
     }
 
     fn set_end(&mut self, end: usize) {
         match self {
             Declaration::FunctionDeclaration(decl) => decl.end = end,
+            Declaration::FunctionPrototype(decl) => decl.end = end,
             Declaration::VarDeclaration(decl) => decl.end = end,
+            Declaration::StructDeclaration(decl) => decl.end = end,
+            Declaration::Error(decl) => decl.end = end,
         };
     }
 }
-#[derive(Debug, Clone, CodeSpan)]
+/// `struct Point { int x; int y; };`, the fields never have initializers
+#[derive(Debug, Clone, CodeSpan, Serialize)]
+pub struct StructDeclaration {
+    pub(crate) id: Identifier,
+    pub(crate) fields: Vec<VarDeclaration>,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// the tokens skipped by the parser to recover from a syntax error, a program containing it
+/// is never passed to the backends
+#[derive(Debug, Clone, CodeSpan, Serialize)]
+pub struct ErrorNode {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, CodeSpan, Serialize)]
 pub struct Identifier {
     pub(crate) value: SmolStr,
     pub start: usize,
     pub end: usize,
 }
-#[derive(Debug, Clone, CodeSpan)]
+#[derive(Debug, Clone, CodeSpan, Serialize)]
 pub struct NumberLiteral {
     pub(crate) value: i32,
     pub start: usize,
     pub end: usize,
 }
-#[derive(Debug, Clone, CodeSpan)]
+#[derive(Debug, Clone, CodeSpan, Serialize)]
 pub struct BooleanLiteral {
     pub(crate) value: bool,
     pub start: usize,
     pub end: usize,
 }
-#[derive(Debug, Clone, CodeSpan)]
+#[derive(Debug, Clone, CodeSpan, Serialize)]
+pub struct CharLiteral {
+    pub(crate) value: char,
+    pub start: usize,
+    pub end: usize,
+}
+/// the escape sequences are decoded, evaluated to a new `char` array ended with `'\0'`
+#[derive(Debug, Clone, CodeSpan, Serialize)]
+pub struct StringLiteral {
+    pub(crate) value: SmolStr,
+    pub start: usize,
+    pub end: usize,
+}
+#[derive(Debug, Clone, CodeSpan, Serialize)]
 pub struct TypeSpecifier {
     pub(crate) kind: TypeSpecifierKind,
     pub start: usize,
     pub end: usize,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub(crate) enum TypeSpecifierKind {
     Int,
     Void,
     Boolean,
+    Char,
+    /// the name after `struct`
+    Struct(SmolStr),
+    /// `int *`, the kind of the value pointed to
+    Pointer(Box<TypeSpecifierKind>),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum Params {
-    Void,
+    /// `(void)`, the span is the `void` keyword's
+    Void { start: usize, end: usize },
     ParamsList { params: Vec<Parameter> },
 }
 
 impl Codespan for Params {
     fn start(&self) -> usize {
         match self {
-            Params::Void => self.start(),
+            Params::Void { start, .. } => *start,
             Params::ParamsList { .. } => {
                 unreachable!()
             }
@@ -116,7 +274,7 @@ impl Codespan for Params {
 
     fn end(&self) -> usize {
         match self {
-            Params::Void => self.end(),
+            Params::Void { end, .. } => *end,
             Params::ParamsList { .. } => {
                 unreachable!()
             }
@@ -132,17 +290,19 @@ impl Codespan for Params {
     }
 }
 
-#[derive(Debug, Clone, CodeSpan)]
+#[derive(Debug, Clone, CodeSpan, Serialize)]
 pub struct Parameter {
     pub(crate) type_specifier: TypeSpecifier,
     pub(crate) id: Identifier,
     pub(crate) is_array: bool,
+    /// the sizes after the first `[]` of an array parameter, `int m[][4]` has one
+    pub(crate) dimensions: Vec<ArraySize>,
     pub start: usize,
     pub end: usize,
 }
 
 
-#[derive(Debug, Clone, CodeSpan)]
+#[derive(Debug, Clone, CodeSpan, Serialize)]
 pub struct CompoundStatement {
     pub(crate) local_declaration: Vec<VarDeclaration>,
     pub(crate) statement_list: Vec<Statement>,
@@ -150,13 +310,19 @@ pub struct CompoundStatement {
     pub end: usize,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum Statement {
     CompoundStatement(CompoundStatement),
     ExpressionStatement(ExpressionStatement),
     SelectionStatement(SelectionStatement),
     IterationStatement(IterationStatement),
+    ForStatement(ForStatement),
+    DoWhileStatement(DoWhileStatement),
+    SwitchStatement(SwitchStatement),
+    BreakStatement(BreakStatement),
+    ContinueStatement(ContinueStatement),
     ReturnStatement(ReturnStatement),
+    Error(ErrorNode),
 }
 
 impl Codespan for Statement {
@@ -166,7 +332,13 @@ impl Codespan for Statement {
             Statement::ExpressionStatement(stmt) => stmt.start,
             Statement::SelectionStatement(stmt) => stmt.start,
             Statement::IterationStatement(stmt) => stmt.start,
+            Statement::ForStatement(stmt) => stmt.start,
+            Statement::DoWhileStatement(stmt) => stmt.start,
+            Statement::SwitchStatement(stmt) => stmt.start,
+            Statement::BreakStatement(stmt) => stmt.start,
+            Statement::ContinueStatement(stmt) => stmt.start,
             Statement::ReturnStatement(stmt) => stmt.start,
+            Statement::Error(stmt) => stmt.start,
         }
     }
 
@@ -176,7 +348,13 @@ impl Codespan for Statement {
             Statement::ExpressionStatement(stmt) => stmt.end,
             Statement::SelectionStatement(stmt) => stmt.end,
             Statement::IterationStatement(stmt) => stmt.end,
+            Statement::ForStatement(stmt) => stmt.end,
+            Statement::DoWhileStatement(stmt) => stmt.end,
+            Statement::SwitchStatement(stmt) => stmt.end,
+            Statement::BreakStatement(stmt) => stmt.end,
+            Statement::ContinueStatement(stmt) => stmt.end,
             Statement::ReturnStatement(stmt) => stmt.end,
+            Statement::Error(stmt) => stmt.end,
         }
     }
 
@@ -184,7 +362,7 @@ impl Codespan for Statement {
 
     fn set_end(&mut self, _end: usize) {}
 }
-#[derive(Debug, Clone, CodeSpan)]
+#[derive(Debug, Clone, CodeSpan, Serialize)]
 pub struct SelectionStatement {
     pub(crate) test: Expression,
     pub(crate) consequent: Box<Statement>,
@@ -193,7 +371,7 @@ pub struct SelectionStatement {
     pub end: usize,
 }
 
-#[derive(Debug, Clone, CodeSpan)]
+#[derive(Debug, Clone, CodeSpan, Serialize)]
 pub struct IterationStatement {
     pub(crate) test: Expression,
     pub(crate) body: Box<Statement>,
@@ -201,14 +379,64 @@ pub struct IterationStatement {
     pub end: usize,
 }
 
-#[derive(Debug, Clone, CodeSpan)]
+/// `for (init; test; update) body`, a missing test is always true
+#[derive(Debug, Clone, CodeSpan, Serialize)]
+pub struct ForStatement {
+    pub(crate) init: Option<Expression>,
+    pub(crate) test: Option<Expression>,
+    pub(crate) update: Option<Expression>,
+    pub(crate) body: Box<Statement>,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// `switch (test) { case 1: ... default: ... }`, the statements of a case fall through to the
+/// next case until `break`
+#[derive(Debug, Clone, CodeSpan, Serialize)]
+pub struct SwitchStatement {
+    pub(crate) test: Expression,
+    pub(crate) cases: Vec<SwitchCase>,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// the label is `None` for `default`
+#[derive(Debug, Clone, CodeSpan, Serialize)]
+pub struct SwitchCase {
+    pub(crate) label: Option<NumberLiteral>,
+    pub(crate) statements: Vec<Statement>,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, CodeSpan, Serialize)]
+pub struct DoWhileStatement {
+    pub(crate) body: Box<Statement>,
+    pub(crate) test: Expression,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, CodeSpan, Serialize)]
+pub struct BreakStatement {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, CodeSpan, Serialize)]
+pub struct ContinueStatement {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, CodeSpan, Serialize)]
 pub struct ReturnStatement {
     pub(crate) expression: Option<Expression>,
     pub start: usize,
     pub end: usize,
 }
 
-#[derive(Debug, Clone, CodeSpan)]
+#[derive(Debug, Clone, CodeSpan, Serialize)]
 pub struct ExpressionStatement {
     pub(crate) expression: Option<Expression>,
     pub start: usize,
@@ -216,12 +444,16 @@ pub struct ExpressionStatement {
 }
 
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum Expression {
     Assignment(AssignmentExpression),
     BinaryExpression(BinaryExpression),
     LogicExpression(LogicExpression),
+    Conditional(ConditionalExpression),
     UnaryExpression(UnaryExpression),
+    UpdateExpression(UpdateExpression),
+    Address(AddressExpression),
+    Dereference(DereferenceExpression),
     Factor(Factor),
 }
 
@@ -233,7 +465,11 @@ impl Codespan for Expression {
             Expression::BinaryExpression(expr) => expr.start,
             Expression::Factor(expr) => expr.start(),
             Expression::LogicExpression(expr) => expr.start(),
+            Expression::Conditional(expr) => expr.start,
             Expression::UnaryExpression(expr) => expr.start(),
+            Expression::UpdateExpression(expr) => expr.start,
+            Expression::Address(expr) => expr.start,
+            Expression::Dereference(expr) => expr.start,
         }
     }
 
@@ -243,7 +479,11 @@ impl Codespan for Expression {
             Expression::BinaryExpression(expr) => expr.end,
             Expression::Factor(expr) => expr.end(),
             Expression::LogicExpression(expr) => expr.end(),
+            Expression::Conditional(expr) => expr.end,
             Expression::UnaryExpression(expr) => expr.end(),
+            Expression::UpdateExpression(expr) => expr.end,
+            Expression::Address(expr) => expr.end,
+            Expression::Dereference(expr) => expr.end,
         }
     }
 
@@ -251,9 +491,13 @@ impl Codespan for Expression {
         match self {
             Expression::Assignment(expr) => expr.start = start,
             Expression::BinaryExpression(expr) => expr.start = start,
-            Expression::Factor(_) => unimplemented!(),
+            Expression::Factor(expr) => expr.set_start(start),
             Expression::LogicExpression(expr) => expr.start = start,
-            Expression::UnaryExpression(_) => unimplemented!(),
+            Expression::Conditional(expr) => expr.start = start,
+            Expression::UnaryExpression(expr) => expr.start = start,
+            Expression::UpdateExpression(expr) => expr.start = start,
+            Expression::Address(expr) => expr.start = start,
+            Expression::Dereference(expr) => expr.start = start,
         };
     }
 
@@ -261,31 +505,180 @@ impl Codespan for Expression {
         match self {
             Expression::Assignment(expr) => expr.end = end,
             Expression::BinaryExpression(expr) => expr.end = end,
-            Expression::Factor(_) => unimplemented!(),
+            Expression::Factor(expr) => expr.set_end(end),
             Expression::LogicExpression(expr) => expr.end = end,
-            Expression::UnaryExpression(_) => unimplemented!(),
+            Expression::Conditional(expr) => expr.end = end,
+            Expression::UnaryExpression(expr) => expr.end = end,
+            Expression::UpdateExpression(expr) => expr.end = end,
+            Expression::Address(expr) => expr.end = end,
+            Expression::Dereference(expr) => expr.end = end,
         };
     }
 }
-#[derive(Debug, Clone, CodeSpan)]
+impl Expression {
+    /// the value of an int expression made of literals, the arithmetic and bitwise operators,
+    /// parentheses and the constants known by `constant`, `None` for any other expression. The
+    /// operations wrap like at runtime, a division by zero is left to the runtime error
+    pub(crate) fn constant_value(&self, constant: &dyn Fn(&SmolStr) -> Option<i32>) -> Option<i32> {
+        match self {
+            Expression::Factor(Factor::NumberLiteral(num)) => Some(num.value),
+            Expression::Factor(Factor::Expression(expr)) => expr.constant_value(constant),
+            Expression::Factor(Factor::Var(var)) if var.accessors.is_empty() => {
+                constant(&var.id.value)
+            }
+            Expression::UnaryExpression(expr) => {
+                let value = expr.expression.constant_value(constant)?;
+                match expr.operation {
+                    Operation::NEG(..) => Some(value.wrapping_neg()),
+                    Operation::POS(..) => Some(value),
+                    Operation::BITNOT(..) => Some(!value),
+                    _ => None,
+                }
+            }
+            Expression::BinaryExpression(expr) => {
+                let left = expr.left.constant_value(constant)?;
+                let right = expr.right.constant_value(constant)?;
+                match expr.operation {
+                    Operation::PLUS(..) => Some(left.wrapping_add(right)),
+                    Operation::MINUS(..) => Some(left.wrapping_sub(right)),
+                    Operation::MULTIPLY(..) => Some(left.wrapping_mul(right)),
+                    Operation::DIVIDE(..) if right != 0 => Some(left.wrapping_div(right)),
+                    Operation::MOD(..) if right != 0 => Some(left.wrapping_rem(right)),
+                    Operation::BITAND(..) => Some(left & right),
+                    Operation::BITOR(..) => Some(left | right),
+                    Operation::BITXOR(..) => Some(left ^ right),
+                    Operation::SHL(..) => Some(left.wrapping_shl(right as u32)),
+                    Operation::SHR(..) => Some(left.wrapping_shr(right as u32)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+#[derive(Debug, Clone, CodeSpan, Serialize)]
 pub struct AssignmentExpression {
-    pub(crate) lhs: Var,
+    pub(crate) lhs: Target,
     pub(crate) rhs: Box<Expression>,
+    /// the arithmetic operation of a compound assignment like `+=`, `None` for `=`
+    pub(crate) operation: Option<Operation>,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// `++` and `--`, the operation is `PLUS` or `MINUS` with the range of the operator
+#[derive(Debug, Clone, CodeSpan, Serialize)]
+pub struct UpdateExpression {
+    pub(crate) target: Target,
+    pub(crate) operation: Operation,
+    /// `++a` is evaluated to the updated value, `a++` to the previous one
+    pub(crate) prefix: bool,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// what an assignment or `++`/`--` writes to
+#[derive(Debug, Clone, Serialize)]
+pub enum Target {
+    Var(Var),
+    Dereference(DereferenceExpression),
+}
+
+impl Codespan for Target {
+    fn start(&self) -> usize {
+        match self {
+            Target::Var(var) => var.start,
+            Target::Dereference(expr) => expr.start,
+        }
+    }
+
+    fn end(&self) -> usize {
+        match self {
+            Target::Var(var) => var.end,
+            Target::Dereference(expr) => expr.end,
+        }
+    }
+
+    fn set_start(&mut self, start: usize) {
+        match self {
+            Target::Var(var) => var.start = start,
+            Target::Dereference(expr) => expr.start = start,
+        };
+    }
+
+    fn set_end(&mut self, end: usize) {
+        match self {
+            Target::Var(var) => var.end = end,
+            Target::Dereference(expr) => expr.end = end,
+        };
+    }
+}
+
+/// `&x` or `&a[i]`, only a variable or an element of an array has an address
+#[derive(Debug, Clone, CodeSpan, Serialize)]
+pub struct AddressExpression {
+    pub(crate) var: Var,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// `*p` or `*(p + 1)`, the value pointed to by `pointer`
+#[derive(Debug, Clone, CodeSpan, Serialize)]
+pub struct DereferenceExpression {
+    pub(crate) pointer: Box<Expression>,
     pub start: usize,
     pub end: usize,
 }
 
 
-#[derive(Debug, Clone, CodeSpan)]
+#[derive(Debug, Clone, CodeSpan, Serialize)]
 pub struct Var {
     pub(crate) id: Identifier,
-    pub(crate) expression: Option<Box<Expression>>,
+    /// `m[i].x` has an index and a member, a variable without them has none
+    pub(crate) accessors: Vec<Accessor>,
     pub start: usize,
     pub end: usize,
 }
 
+/// an element of an array or a member of a struct, selected from the value on its left
+#[derive(Debug, Clone, Serialize)]
+pub enum Accessor {
+    Index(Expression),
+    Member(Identifier),
+}
+
+impl Codespan for Accessor {
+    fn start(&self) -> usize {
+        match self {
+            Accessor::Index(expr) => expr.start(),
+            Accessor::Member(id) => id.start,
+        }
+    }
+
+    fn end(&self) -> usize {
+        match self {
+            Accessor::Index(expr) => expr.end(),
+            Accessor::Member(id) => id.end,
+        }
+    }
+
+    fn set_start(&mut self, start: usize) {
+        match self {
+            Accessor::Index(expr) => expr.set_start(start),
+            Accessor::Member(id) => id.start = start,
+        };
+    }
+
+    fn set_end(&mut self, end: usize) {
+        match self {
+            Accessor::Index(expr) => expr.set_end(end),
+            Accessor::Member(id) => id.end = end,
+        };
+    }
+}
+
 
-#[derive(Debug, Clone, CodeSpan)]
+#[derive(Debug, Clone, CodeSpan, Serialize)]
 pub struct LogicExpression {
     pub(crate) left: Box<Expression>,
     pub(crate) right: Box<Expression>,
@@ -294,7 +687,17 @@ pub struct LogicExpression {
     pub end: usize,
 }
 
-#[derive(Debug, Clone, CodeSpan)]
+/// `test ? consequent : alternative`, only one of the arms is evaluated
+#[derive(Debug, Clone, CodeSpan, Serialize)]
+pub struct ConditionalExpression {
+    pub(crate) test: Box<Expression>,
+    pub(crate) consequent: Box<Expression>,
+    pub(crate) alternative: Box<Expression>,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, CodeSpan, Serialize)]
 pub struct UnaryExpression {
     pub(crate) expression: Box<Expression>,
     pub(crate) operation: Operation,
@@ -302,7 +705,7 @@ pub struct UnaryExpression {
     pub end: usize,
 }
 
-#[derive(Debug, Clone, CodeSpan)]
+#[derive(Debug, Clone, CodeSpan, Serialize)]
 pub struct BinaryExpression {
     pub(crate) left: Box<Expression>,
     pub(crate) right: Box<Expression>,
@@ -310,7 +713,7 @@ pub struct BinaryExpression {
     pub start: usize,
     pub end: usize,
 }
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum Operation {
     GT(usize, usize),
     LT(usize, usize),
@@ -326,6 +729,14 @@ pub enum Operation {
     OR(usize, usize),
     NEG(usize, usize),
     POS(usize, usize),
+    MOD(usize, usize),
+    BITAND(usize, usize),
+    BITOR(usize, usize),
+    BITXOR(usize, usize),
+    SHL(usize, usize),
+    SHR(usize, usize),
+    BITNOT(usize, usize),
+    NOT(usize, usize),
 }
 impl Display for Operation {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -370,6 +781,14 @@ impl Display for Operation {
                 write!(f, "NEG")
             }
             Operation::POS(_, _) => write!(f, "POS"),
+            Operation::MOD(_, _) => write!(f, "MOD"),
+            Operation::BITAND(_, _) => write!(f, "BITAND"),
+            Operation::BITOR(_, _) => write!(f, "BITOR"),
+            Operation::BITXOR(_, _) => write!(f, "BITXOR"),
+            Operation::SHL(_, _) => write!(f, "SHL"),
+            Operation::SHR(_, _) => write!(f, "SHR"),
+            Operation::BITNOT(_, _) => write!(f, "BITNOT"),
+            Operation::NOT(_, _) => write!(f, "NOT"),
         }
     }
 }
@@ -390,6 +809,14 @@ impl Codespan for Operation {
             Operation::OR(start, _) => *start,
             Operation::NEG(start, _) => *start,
             Operation::POS(start, _) => *start,
+            Operation::MOD(start, _) => *start,
+            Operation::BITAND(start, _) => *start,
+            Operation::BITOR(start, _) => *start,
+            Operation::BITXOR(start, _) => *start,
+            Operation::SHL(start, _) => *start,
+            Operation::SHR(start, _) => *start,
+            Operation::BITNOT(start, _) => *start,
+            Operation::NOT(start, _) => *start,
         }
     }
 
@@ -409,6 +836,14 @@ impl Codespan for Operation {
             Operation::OR(_, end) => *end,
             Operation::NEG(_, end) => *end,
             Operation::POS(_, end) => *end,
+            Operation::MOD(_, end) => *end,
+            Operation::BITAND(_, end) => *end,
+            Operation::BITOR(_, end) => *end,
+            Operation::BITXOR(_, end) => *end,
+            Operation::SHL(_, end) => *end,
+            Operation::SHR(_, end) => *end,
+            Operation::BITNOT(_, end) => *end,
+            Operation::NOT(_, end) => *end,
         }
     }
 
@@ -419,13 +854,15 @@ impl Codespan for Operation {
         unimplemented!() // TODO
     }
 }
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum Factor {
     Expression(Box<Expression>),
     Var(Var),
     CallExpression(CallExpression),
     NumberLiteral(NumberLiteral),
     BooleanLiteral(BooleanLiteral),
+    CharLiteral(CharLiteral),
+    StringLiteral(StringLiteral),
 }
 
 
@@ -437,6 +874,8 @@ impl Codespan for Factor {
             Factor::CallExpression(call_expression) => call_expression.start,
             Factor::NumberLiteral(num) => num.start,
             Factor::BooleanLiteral(boolean) => boolean.start,
+            Factor::CharLiteral(literal) => literal.start,
+            Factor::StringLiteral(literal) => literal.start,
         }
     }
 
@@ -447,30 +886,36 @@ impl Codespan for Factor {
             Factor::CallExpression(call_expression) => call_expression.end,
             Factor::NumberLiteral(num) => num.end,
             Factor::BooleanLiteral(boolean) => boolean.end,
+            Factor::CharLiteral(literal) => literal.end,
+            Factor::StringLiteral(literal) => literal.end,
         }
     }
 
     fn set_start(&mut self, start: usize) {
         match self {
-            Factor::Expression(expr) => unimplemented!(),
+            Factor::Expression(expr) => expr.set_start(start),
             Factor::Var(var) => var.start = start,
             Factor::CallExpression(call_expression) => call_expression.start = start,
             Factor::NumberLiteral(num) => num.start = start,
             Factor::BooleanLiteral(boolean) => boolean.start = start,
+            Factor::CharLiteral(literal) => literal.start = start,
+            Factor::StringLiteral(literal) => literal.start = start,
         };
     }
 
     fn set_end(&mut self, end: usize) {
         match self {
-            Factor::Expression(expr) => unimplemented!(),
+            Factor::Expression(expr) => expr.set_end(end),
             Factor::Var(var) => var.end = end,
             Factor::CallExpression(call_expression) => call_expression.end = end,
             Factor::NumberLiteral(num) => num.end = end,
             Factor::BooleanLiteral(boolean) => boolean.end = end,
+            Factor::CharLiteral(literal) => literal.end = end,
+            Factor::StringLiteral(literal) => literal.end = end,
         };
     }
 }
-#[derive(Debug, Clone, CodeSpan)]
+#[derive(Debug, Clone, CodeSpan, Serialize)]
 pub struct CallExpression {
     pub(crate) id: Identifier,
     pub(crate) arguments: Vec<Expression>,
@@ -5,4 +5,5 @@ pub(crate) mod span;
 pub mod visitor;
 pub(crate) mod walk;
 
+pub use ast::Program;
 pub use span::Codespan;
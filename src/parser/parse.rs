@@ -1,32 +1,51 @@
 use crate::{
-    error_emit::ErrorReporter,
-    lexer::token::{KeywordType, Token, TokenType},
+    error_emit::{ErrorReporter, FileId, FileSpan},
+    lexer::{
+        token::{KeywordType, Token, TokenType},
+        util,
+    },
     parser::{ast::*, Codespan},
 };
 
+/// a binary operator token and the operation built from its range
+type BinaryOperator = (TokenType, fn(usize, usize) -> Operation);
+
 pub struct Parser<'a> {
     token_list: Vec<Token>,
     cursor: usize,
     source_file: &'a str,
+    file_id: FileId,
+    // the count of loops enclosing the statement being parsed
+    loop_depth: usize,
+    // the count of switches enclosing the statement being parsed, `break` leaves a switch too
+    switch_depth: usize,
     pub error_reporter: ErrorReporter<'a>,
 }
 
 impl<'a> Parser<'a> {
-    pub fn new(token_list: Vec<Token>, source_file: &'a str) -> Parser<'a> {
+    /// `file_name` is the path printed in diagnostics
+    pub fn new(token_list: Vec<Token>, file_name: &'a str, source_file: &'a str) -> Parser<'a> {
         let token_list = token_list
             .into_iter()
             .filter(|token| token.token_type != TokenType::Comment)
             .collect();
         let mut error_reporter = ErrorReporter::new();
-        error_reporter.add_file("main.cm", source_file.to_string());
+        let file_id = error_reporter.add_file(file_name, source_file.to_string());
         Self {
             token_list,
             cursor: 0,
             source_file,
+            file_id,
+            loop_depth: 0,
+            switch_depth: 0,
             error_reporter,
         }
     }
 
+    fn span(&self, range: impl Into<std::ops::Range<usize>>) -> FileSpan {
+        FileSpan::new(self.file_id, range)
+    }
+
     fn get_source_file_end_range(&self) -> impl Into<std::ops::Range<usize>> {
         self.source_file.len() - 1..self.source_file.len()
     }
@@ -40,11 +59,15 @@ impl<'a> Parser<'a> {
         }
         false
     }
+    /// `const` starts a variable declaration as well
     fn match_type_specifier(&mut self) -> bool {
         if let Some(token) = self.next_token() {
-            token.token_type == TokenType::Keyword(KeywordType::VOID)
+            token.token_type == TokenType::Keyword(KeywordType::CONST)
+                || token.token_type == TokenType::Keyword(KeywordType::VOID)
                 || token.token_type == TokenType::Keyword(KeywordType::INT)
                 || token.token_type == TokenType::Keyword(KeywordType::BOOL)
+                || token.token_type == TokenType::Keyword(KeywordType::CHAR)
+                || token.token_type == TokenType::Keyword(KeywordType::STRUCT)
         } else {
             false
         }
@@ -84,11 +107,35 @@ impl<'a> Parser<'a> {
             match token.token_type {
                 TokenType::Multiply => return Some(Operation::MULTIPLY(start, end)),
                 TokenType::Times => return Some(Operation::DIVIDE(start, end)),
+                TokenType::Mod => return Some(Operation::MOD(start, end)),
                 _ => return None,
             }
         }
         None
     }
+    /// the arithmetic operation of `+=`, `-=`, `*=`, `/=` and `%=`
+    fn match_compound_assign_op(&mut self) -> Option<Operation> {
+        let token = self.next_token()?;
+        let (start, end) = (token.start_index, token.end_index);
+        match token.token_type {
+            TokenType::PlusAssign => Some(Operation::PLUS(start, end)),
+            TokenType::MinusAssign => Some(Operation::MINUS(start, end)),
+            TokenType::MultiplyAssign => Some(Operation::MULTIPLY(start, end)),
+            TokenType::DivideAssign => Some(Operation::DIVIDE(start, end)),
+            TokenType::ModAssign => Some(Operation::MOD(start, end)),
+            _ => None,
+        }
+    }
+    /// the operation of `++` and `--`
+    fn match_update_op(&mut self) -> Option<Operation> {
+        let token = self.next_token()?;
+        let (start, end) = (token.start_index, token.end_index);
+        match token.token_type {
+            TokenType::Increment => Some(Operation::PLUS(start, end)),
+            TokenType::Decrement => Some(Operation::MINUS(start, end)),
+            _ => None,
+        }
+    }
     fn consume(&mut self, step: usize) {
         self.cursor += step;
     }
@@ -98,8 +145,7 @@ impl<'a> Parser<'a> {
             let range = self.get_source_file_end_range();
             if need_report {
                 self.error_reporter.add_diagnostic(
-                    "main.cm",
-                    range,
+                    self.span(range),
                     format!("expected {}, found none", token_type),
                 );
             }
@@ -112,8 +158,7 @@ impl<'a> Parser<'a> {
         } else {
             if need_report {
                 self.error_reporter.add_diagnostic(
-                    "main.cm",
-                    token.range(),
+                    self.span(token.range()),
                     format!("expected {}, found {}", token_type, token.token_type),
                 );
             }
@@ -124,111 +169,356 @@ impl<'a> Parser<'a> {
     fn backtrack(&mut self, step: usize) {
         self.cursor = self.cursor.wrapping_sub(step);
     }
+    /// parse the whole token list, every syntax error is collected in `error_reporter`
     pub fn parse_program(&mut self) -> Result<Program, ()> {
+        let program = self.parse_partial_program();
+        if self.error_reporter.is_empty() {
+            Ok(program)
+        } else {
+            Err(())
+        }
+    }
+    /// parse the whole token list, recovering from syntax errors, a declaration which can't be
+    /// parsed is replaced by an `ErrorNode` covering the skipped tokens
+    pub fn parse_partial_program(&mut self) -> Program {
         let mut declarations = vec![];
         let mut end = 0;
         while self.cursor < self.token_list.len() {
-            let declaration = self.parse_declaration()?;
+            let cursor = self.cursor;
+            let declaration = match self.parse_declaration() {
+                Ok(declaration) => declaration,
+                Err(_) => Declaration::Error(self.synchronize_declaration(cursor)),
+            };
             end = declaration.end();
             declarations.push(declaration);
         }
-        Ok(Program {
+        Program {
             declarations,
             start: 0,
             end,
-        })
+        }
+    }
+    /// parse the whole token list as statements, like the statement list of a function body.
+    /// The REPL runs them in the global scope
+    pub fn parse_statements(&mut self) -> Result<Vec<Statement>, ()> {
+        let mut statements = vec![];
+        while self.cursor < self.token_list.len() {
+            let cursor = self.cursor;
+            let statement = match self.parse_statement() {
+                Ok(statement) => statement,
+                Err(_) => Statement::Error(self.synchronize_statement(cursor)),
+            };
+            statements.push(statement);
+        }
+        if self.error_reporter.is_empty() {
+            Ok(statements)
+        } else {
+            Err(())
+        }
+    }
+    /// whether the next token starts a declaration rather than a statement
+    pub fn at_declaration(&mut self) -> bool {
+        self.match_type_specifier()
+    }
+    /// skip to the start of the next declaration: a type specifier outside of any braces or
+    /// parentheses, or the token after a top level `;` or a `}` closing the outermost brace
+    fn synchronize_declaration(&mut self, cursor: usize) -> ErrorNode {
+        self.cursor = cursor;
+        let mut depth = 0usize;
+        let mut paren_depth = 0usize;
+        while let Some(token_type) = self.next_token().map(|token| token.token_type) {
+            if depth == 0
+                && paren_depth == 0
+                && self.cursor > cursor
+                && self.match_type_specifier()
+            {
+                break;
+            }
+            self.consume(1);
+            match token_type {
+                TokenType::Semi if depth == 0 => break,
+                TokenType::Lparen => paren_depth += 1,
+                TokenType::Rparen => paren_depth = paren_depth.saturating_sub(1),
+                TokenType::Lbrace => {
+                    depth += 1;
+                    paren_depth = 0;
+                }
+                TokenType::Rbrace if depth == 1 => break,
+                TokenType::Rbrace => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        self.error_node(cursor)
+    }
+    /// skip to the start of the next statement: the token after a top level `;` or a block
+    /// which isn't followed by `else`, a `}` closing the enclosing block, a type specifier
+    /// outside of any parentheses or the end of the file. At least one token is skipped
+    fn synchronize_statement(&mut self, cursor: usize) -> ErrorNode {
+        self.cursor = cursor;
+        let mut depth = 0usize;
+        let mut paren_depth = 0usize;
+        while let Some(token_type) = self.next_token().map(|token| token.token_type) {
+            if depth == 0 && self.cursor > cursor {
+                if token_type == TokenType::Rbrace {
+                    break;
+                }
+                if paren_depth == 0 && self.match_type_specifier() {
+                    break;
+                }
+            }
+            self.consume(1);
+            match token_type {
+                TokenType::Semi if depth == 0 && paren_depth == 0 => break,
+                TokenType::Lparen => paren_depth += 1,
+                TokenType::Rparen => paren_depth = paren_depth.saturating_sub(1),
+                TokenType::Lbrace => {
+                    depth += 1;
+                    paren_depth = 0;
+                }
+                TokenType::Rbrace if depth == 1 => {
+                    depth = 0;
+                    if !self.match_token(TokenType::Keyword(KeywordType::ELSE)) {
+                        break;
+                    }
+                }
+                TokenType::Rbrace => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        self.error_node(cursor)
+    }
+    fn error_node(&self, cursor: usize) -> ErrorNode {
+        let last = self.cursor.min(self.token_list.len()).max(cursor + 1) - 1;
+        match (self.token_list.get(cursor), self.token_list.get(last)) {
+            (Some(first), Some(last)) => ErrorNode {
+                start: first.start_index,
+                end: last.end_index,
+            },
+            _ => ErrorNode {
+                start: self.source_file.len(),
+                end: self.source_file.len(),
+            },
+        }
     }
 
     fn parse_declaration(&mut self) -> Result<Declaration, ()> {
+        let cursor = self.cursor;
+        if self.match_token(TokenType::Keyword(KeywordType::CONST)) {
+            return self.parse_variable_declaration();
+        }
         if self.match_type_specifier() {
-            self.consume(1);
+            self.parse_type_specifier()?;
         } else {
             let range = self.next_token().ok_or_else(|| ())?.range();
             self.error_reporter.add_diagnostic(
-                "main.cm",
-                range,
+                self.span(range),
                 format!(
-                    "expected `int` , `bool` or `void` , found {}",
+                    "expected `int` , `bool` , `char` , `void` or `struct` , found {}",
                     self.next_token().unwrap().token_type
                 ),
             );
             return Err(());
         }
+        // `struct Name {` declares the struct, otherwise `struct Name` is a type specifier
+        if self.token_list[cursor].token_type == TokenType::Keyword(KeywordType::STRUCT)
+            && self.match_token(TokenType::Lbrace)
+        {
+            self.backtrack(self.cursor - cursor);
+            return self.parse_struct_declaration();
+        }
         self.match_and_consume(TokenType::Id, true)?;
-        if self.match_token(TokenType::Lparen) {
-            self.backtrack(2);
-            return self.parse_function_declaration();
+        let is_function = self.match_token(TokenType::Lparen);
+        self.backtrack(self.cursor - cursor);
+        if is_function {
+            self.parse_function_declaration()
         } else {
-            self.backtrack(2);
-            return self.parse_variable_declaration();
+            self.parse_variable_declaration()
         }
     }
 
+    fn parse_struct_declaration(&mut self) -> Result<Declaration, ()> {
+        let start = (self.match_and_consume(TokenType::Keyword(KeywordType::STRUCT), true)?)
+            .start_index;
+        let id_token = self.match_and_consume(TokenType::Id, true)?;
+        self.match_and_consume(TokenType::Lbrace, true)?;
+        let mut fields = vec![];
+        while !self.match_token(TokenType::Rbrace) {
+            let field = match self.parse_variable_declaration()? {
+                Declaration::VarDeclaration(field) => field,
+                _ => unreachable!(),
+            };
+            if field.initializer.is_some() || field.array_initializer.is_some() {
+                self.error_reporter.add_diagnostic(
+                    self.span(field.start..field.end),
+                    format!("field `{}` can't have an initializer", field.id.value),
+                );
+                return Err(());
+            }
+            fields.push(field);
+        }
+        self.match_and_consume(TokenType::Rbrace, true)?;
+        let end = (self.match_and_consume(TokenType::Semi, true)?).end_index;
+        Ok(Declaration::StructDeclaration(StructDeclaration {
+            id: Identifier {
+                value: id_token.content,
+                start: id_token.start_index,
+                end: id_token.end_index,
+            },
+            fields,
+            start,
+            end,
+        }))
+    }
+
     fn parse_variable_declaration(&mut self) -> Result<Declaration, ()> {
+        let const_token = if self.match_token(TokenType::Keyword(KeywordType::CONST)) {
+            Some(self.match_and_consume(TokenType::Keyword(KeywordType::CONST), true)?)
+        } else {
+            None
+        };
         let type_specifier = self.parse_type_specifier()?;
+        if let (Some(token), TypeSpecifierKind::Pointer(_)) = (&const_token, &type_specifier.kind)
+        {
+            self.error_reporter.add_diagnostic(
+                self.span(token.start_index..type_specifier.end),
+                "`const` pointers aren't supported".into(),
+            );
+            return Err(());
+        }
         let id_token = self.match_and_consume(TokenType::Id, true)?;
         let identifier = Identifier {
             value: id_token.content,
             start: id_token.start_index,
             end: id_token.end_index,
         };
-        let mut num = None;
-        let mut array_initializer = None;
-        if self.match_token(TokenType::Lbrack) {
+        let mut dimensions = vec![];
+        while self.match_token(TokenType::Lbrack) {
             self.consume(1);
-            let num_token = self.match_and_consume(TokenType::NumberLiteral, true)?;
-            let value = if let Ok(value) = num_token.content.parse::<i32>() {
-                value
-            } else {
-                self.error_reporter.add_diagnostic(
-                    "main.cm",
-                    num_token.range(),
-                    "can't parse token to integer".into(),
-                );
-                return Err(());
-            };
-            num = Some(NumberLiteral {
-                value,
-                start: num_token.start_index,
-                end: num_token.end_index,
-            });
+            dimensions.push(self.parse_array_size()?);
             self.match_and_consume(TokenType::Rbrack, true)?;
         }
         let mut initializer = None;
+        let mut array_initializer = None;
         if self.match_token(TokenType::Assign) {
             self.consume(1);
-            if num.is_some() {
-                array_initializer = Some(self.parse_array_initialization()?);
-            } else {
+            if dimensions.is_empty() {
                 initializer = Some(self.parse_expression()?);
+            } else {
+                array_initializer = Some(self.parse_array_initialization(&dimensions)?.elements);
             }
         }
         let end = (self.match_and_consume(TokenType::Semi, true)?).end_index;
+        if const_token.is_some() && initializer.is_none() && array_initializer.is_none() {
+            self.error_reporter.add_diagnostic(
+                self.span(identifier.start..identifier.end),
+                format!("const `{}` should be initialized", identifier.value),
+            );
+            return Err(());
+        }
         Ok(Declaration::VarDeclaration(VarDeclaration {
-            start: type_specifier.start,
+            start: const_token.as_ref().map_or(type_specifier.start, |token| token.start_index),
             end,
             type_specifier,
             id: identifier,
+            is_const: const_token.is_some(),
             initializer,
             array_initializer,
-            num,
+            dimensions,
         }))
     }
-    fn parse_array_initialization(&mut self) -> Result<Vec<Expression>, ()> {
-        let mut expressions = vec![];
-        self.match_and_consume(TokenType::Lbrace, true)?;
-        expressions.push(self.parse_expression()?);
-        while self.match_token(TokenType::Comma) {
+    /// a number or the name of a global `const int`, the name is resolved by the resolver
+    fn parse_array_size(&mut self) -> Result<ArraySize, ()> {
+        if self.match_token(TokenType::Id) {
+            let token = self.match_and_consume(TokenType::Id, true)?;
+            return Ok(ArraySize::Constant(Identifier {
+                value: token.content,
+                start: token.start_index,
+                end: token.end_index,
+            }));
+        }
+        let num_token = self.match_and_consume(TokenType::NumberLiteral, true)?;
+        let value = if let Ok(value) = num_token.content.parse::<i32>() {
+            value
+        } else {
+            self.error_reporter.add_diagnostic(
+                self.span(num_token.range()),
+                "can't parse token to integer".into(),
+            );
+            return Err(());
+        };
+        Ok(ArraySize::Number(NumberLiteral {
+            value,
+            start: num_token.start_index,
+            end: num_token.end_index,
+        }))
+    }
+    /// the initializer of an array with `dimensions`, every row of a multi-dimensional array is
+    /// a nested list, and a string can initialize the last dimension of a `char` array
+    fn parse_array_initialization(
+        &mut self,
+        dimensions: &[ArraySize],
+    ) -> Result<InitializerList, ()> {
+        if dimensions.len() == 1 && self.match_token(TokenType::StringLiteral) {
+            let elements = self.parse_string_initialization()?;
+            // every char shares the span of the string literal
+            let (start, end) = (elements[0].start(), elements[0].end());
+            return Ok(InitializerList {
+                elements: elements.into_iter().map(Initializer::Expression).collect(),
+                start,
+                end,
+            });
+        }
+        let start = (self.match_and_consume(TokenType::Lbrace, true)?).start_index;
+        let mut elements = vec![];
+        loop {
+            elements.push(if dimensions.len() > 1 {
+                Initializer::List(self.parse_array_initialization(&dimensions[1..])?)
+            } else {
+                Initializer::Expression(self.parse_expression()?)
+            });
+            if !self.match_token(TokenType::Comma) {
+                break;
+            }
             self.consume(1);
-            expressions.push(self.parse_expression()?);
         }
-        self.match_and_consume(TokenType::Rbrace, true)?;
-        Ok(expressions)
+        let end = (self.match_and_consume(TokenType::Rbrace, true)?).end_index;
+        Ok(InitializerList {
+            elements,
+            start,
+            end,
+        })
+    }
+    /// `char s[6] = "hello";` is lowered to the initializer list of the chars and the `'\0'`
+    fn parse_string_initialization(&mut self) -> Result<Vec<Expression>, ()> {
+        let token = self.match_and_consume(TokenType::StringLiteral, true)?;
+        let value = self.unescape_literal(&token)?;
+        Ok(value
+            .chars()
+            .chain(std::iter::once('\0'))
+            .map(|value| {
+                Expression::Factor(Factor::CharLiteral(CharLiteral {
+                    value,
+                    start: token.start_index,
+                    end: token.end_index,
+                }))
+            })
+            .collect())
+    }
+    /// the content between the quotes of a char or string literal token, escape sequences
+    /// decoded
+    fn unescape_literal(&mut self, token: &Token) -> Result<String, ()> {
+        let content = &token.content[1..token.content.len() - 1];
+        util::unescape(content).map_err(|escape| {
+            self.error_reporter.add_diagnostic(
+                self.span(token.range()),
+                format!("unknown character escape `{}`", escape),
+            );
+        })
     }
     fn parse_function_declaration(&mut self) -> Result<Declaration, ()> {
         let type_specifier = self.parse_type_specifier()?;
         let id_token = self.match_and_consume(TokenType::Id, true)?;
-        let mut params: Params = Params::Void;
+        let mut params = Params::ParamsList { params: vec![] };
         let identifier = Identifier {
             value: id_token.content,
             start: id_token.start_index,
@@ -238,8 +528,9 @@ impl<'a> Parser<'a> {
         match self.next_token() {
             Some(token) => match token.token_type {
                 TokenType::Keyword(KeywordType::VOID) => {
+                    let (start, end) = (token.start_index, token.end_index);
                     self.consume(1);
-                    params = Params::Void;
+                    params = Params::Void { start, end };
                 }
                 // TokenType::LPAREN => {}
                 _ => {
@@ -259,6 +550,17 @@ impl<'a> Parser<'a> {
             None => {}
         }
         self.match_and_consume(TokenType::Rparen, true)?;
+        // a prototype ends with `;` instead of the body
+        if self.match_token(TokenType::Semi) {
+            let end = (self.match_and_consume(TokenType::Semi, true)?).end_index;
+            return Ok(Declaration::FunctionPrototype(FunctionPrototype {
+                start: type_specifier.start,
+                end,
+                id: identifier,
+                type_specifier,
+                params,
+            }));
+        }
         let body = self.parse_compound_statement()?;
         Ok(Declaration::FunctionDeclaration(FunctionDeclaration {
             start: type_specifier.start,
@@ -275,12 +577,12 @@ impl<'a> Parser<'a> {
         let mut local_declaration = vec![];
         let mut statement_list = vec![];
         while self.match_type_specifier() {
+            let cursor = self.cursor;
             match self.parse_variable_declaration() {
                 Ok(decl) => match decl {
-                    Declaration::FunctionDeclaration(_) => {
+                    Declaration::FunctionDeclaration(_) | Declaration::FunctionPrototype(_) => {
                         self.error_reporter.add_diagnostic(
-                            "main.cm",
-                            self.token_list[self.cursor].range(),
+                            self.span(self.token_list[self.cursor].range()),
                             "Unexpected function declaration".into(),
                         );
                         return Err(());
@@ -288,14 +590,21 @@ impl<'a> Parser<'a> {
                     Declaration::VarDeclaration(var_decl) => {
                         local_declaration.push(var_decl);
                     }
+                    Declaration::StructDeclaration(_) | Declaration::Error(_) => unreachable!(),
                 },
                 Err(_) => {
-                    return Err(());
+                    let node = self.synchronize_statement(cursor);
+                    statement_list.push(Statement::Error(node));
                 }
             }
         }
-        while !self.match_token(TokenType::Rbrace) {
-            statement_list.push(self.parse_statement()?);
+        while !self.match_token(TokenType::Rbrace) && self.cursor < self.token_list.len() {
+            let cursor = self.cursor;
+            let statement = match self.parse_statement() {
+                Ok(statement) => statement,
+                Err(_) => Statement::Error(self.synchronize_statement(cursor)),
+            };
+            statement_list.push(statement);
         }
         let end = (self.match_and_consume(TokenType::Rbrace, true)?).end_index;
         Ok(CompoundStatement {
@@ -317,6 +626,23 @@ impl<'a> Parser<'a> {
                 TokenType::Keyword(KeywordType::WHILE) => Ok(Statement::IterationStatement(
                     self.parse_iteration_statement()?,
                 )),
+                TokenType::Keyword(KeywordType::FOR) => {
+                    Ok(Statement::ForStatement(self.parse_for_statement()?))
+                }
+                TokenType::Keyword(KeywordType::DO) => {
+                    Ok(Statement::DoWhileStatement(self.parse_do_while_statement()?))
+                }
+                TokenType::Keyword(KeywordType::SWITCH) => {
+                    Ok(Statement::SwitchStatement(self.parse_switch_statement()?))
+                }
+                TokenType::Keyword(KeywordType::BREAK) => {
+                    let (start, end) = self.parse_jump_statement(KeywordType::BREAK)?;
+                    Ok(Statement::BreakStatement(BreakStatement { start, end }))
+                }
+                TokenType::Keyword(KeywordType::CONTINUE) => {
+                    let (start, end) = self.parse_jump_statement(KeywordType::CONTINUE)?;
+                    Ok(Statement::ContinueStatement(ContinueStatement { start, end }))
+                }
                 TokenType::Keyword(KeywordType::RETURN) => {
                     Ok(Statement::ReturnStatement(self.parse_return_statement()?))
                 }
@@ -332,7 +658,7 @@ impl<'a> Parser<'a> {
         let start =
             (self.match_and_consume(TokenType::Keyword(KeywordType::WHILE), true)?).start_index;
         let expression = self.parse_expression()?;
-        let body = Box::new(self.parse_statement()?);
+        let body = Box::new(self.parse_loop_body()?);
         Ok(IterationStatement {
             start,
             end: body.end(),
@@ -340,6 +666,181 @@ impl<'a> Parser<'a> {
             body,
         })
     }
+    fn parse_for_statement(&mut self) -> Result<ForStatement, ()> {
+        let start =
+            (self.match_and_consume(TokenType::Keyword(KeywordType::FOR), true)?).start_index;
+        self.match_and_consume(TokenType::Lparen, true)?;
+        let init = self.parse_optional_expression(TokenType::Semi)?;
+        self.match_and_consume(TokenType::Semi, true)?;
+        let test = self.parse_optional_expression(TokenType::Semi)?;
+        self.match_and_consume(TokenType::Semi, true)?;
+        let update = self.parse_optional_expression(TokenType::Rparen)?;
+        self.match_and_consume(TokenType::Rparen, true)?;
+        let body = Box::new(self.parse_loop_body()?);
+        Ok(ForStatement {
+            start,
+            end: body.end(),
+            init,
+            test,
+            update,
+            body,
+        })
+    }
+    fn parse_do_while_statement(&mut self) -> Result<DoWhileStatement, ()> {
+        let start =
+            (self.match_and_consume(TokenType::Keyword(KeywordType::DO), true)?).start_index;
+        let body = Box::new(self.parse_loop_body()?);
+        self.match_and_consume(TokenType::Keyword(KeywordType::WHILE), true)?;
+        self.match_and_consume(TokenType::Lparen, true)?;
+        let test = self.parse_expression()?;
+        self.match_and_consume(TokenType::Rparen, true)?;
+        let end = (self.match_and_consume(TokenType::Semi, true)?).end_index;
+        Ok(DoWhileStatement {
+            start,
+            end,
+            body,
+            test,
+        })
+    }
+    fn parse_loop_body(&mut self) -> Result<Statement, ()> {
+        self.loop_depth += 1;
+        let body = self.parse_statement();
+        self.loop_depth -= 1;
+        body
+    }
+    /// an expression unless the next token is `terminator`
+    fn parse_optional_expression(
+        &mut self,
+        terminator: TokenType,
+    ) -> Result<Option<Expression>, ()> {
+        if self.match_token(terminator) {
+            Ok(None)
+        } else {
+            Ok(Some(self.parse_expression()?))
+        }
+    }
+    fn parse_switch_statement(&mut self) -> Result<SwitchStatement, ()> {
+        let start =
+            (self.match_and_consume(TokenType::Keyword(KeywordType::SWITCH), true)?).start_index;
+        self.match_and_consume(TokenType::Lparen, true)?;
+        let test = self.parse_expression()?;
+        self.match_and_consume(TokenType::Rparen, true)?;
+        self.match_and_consume(TokenType::Lbrace, true)?;
+        self.switch_depth += 1;
+        let cases = self.parse_switch_cases();
+        self.switch_depth -= 1;
+        let cases = cases?;
+        let end = (self.match_and_consume(TokenType::Rbrace, true)?).end_index;
+        Ok(SwitchStatement {
+            test,
+            cases,
+            start,
+            end,
+        })
+    }
+    /// a duplicate label or a second `default` is reported but still parsed
+    fn parse_switch_cases(&mut self) -> Result<Vec<SwitchCase>, ()> {
+        let mut cases: Vec<SwitchCase> = vec![];
+        while !self.match_token(TokenType::Rbrace) && self.cursor < self.token_list.len() {
+            let case = self.parse_switch_case()?;
+            let duplicate = cases.iter().any(|other| match (&other.label, &case.label) {
+                (Some(other), Some(label)) => other.value == label.value,
+                (None, None) => true,
+                _ => false,
+            });
+            if duplicate {
+                let (range, message) = match case.label {
+                    Some(ref label) => (
+                        label.start..label.end,
+                        format!("duplicate case value `{}`", label.value),
+                    ),
+                    None => (case.start..case.start + 7, "duplicate `default` label".into()),
+                };
+                self.error_reporter.add_diagnostic(self.span(range), message);
+            }
+            cases.push(case);
+        }
+        Ok(cases)
+    }
+    /// `case 1:` or `default:` and the statements before the next label
+    fn parse_switch_case(&mut self) -> Result<SwitchCase, ()> {
+        let start = self.token_list[self.cursor].start_index;
+        let label = if self.match_token(TokenType::Keyword(KeywordType::DEFAULT)) {
+            self.consume(1);
+            None
+        } else {
+            self.match_and_consume(TokenType::Keyword(KeywordType::CASE), true)?;
+            Some(self.parse_case_label()?)
+        };
+        let mut end = (self.match_and_consume(TokenType::Colon, true)?).end_index;
+        let mut statements = vec![];
+        while let Some(token_type) = self.next_token().map(|token| token.token_type) {
+            if let TokenType::Rbrace
+            | TokenType::Keyword(KeywordType::CASE)
+            | TokenType::Keyword(KeywordType::DEFAULT) = token_type
+            {
+                break;
+            }
+            let cursor = self.cursor;
+            let statement = match self.parse_statement() {
+                Ok(statement) => statement,
+                Err(_) => Statement::Error(self.synchronize_statement(cursor)),
+            };
+            end = statement.end();
+            statements.push(statement);
+        }
+        Ok(SwitchCase {
+            label,
+            statements,
+            start,
+            end,
+        })
+    }
+    /// the integer constant of `case`, which may be negative
+    fn parse_case_label(&mut self) -> Result<NumberLiteral, ()> {
+        let minus = if self.match_token(TokenType::Minus) {
+            Some(self.match_and_consume(TokenType::Minus, true)?)
+        } else {
+            None
+        };
+        let num_token = self.match_and_consume(TokenType::NumberLiteral, true)?;
+        let content = match minus {
+            Some(_) => format!("-{}", num_token.content),
+            None => num_token.content.to_string(),
+        };
+        let value = if let Ok(value) = content.parse::<i32>() {
+            value
+        } else {
+            self.error_reporter.add_diagnostic(
+                self.span(num_token.range()),
+                "can't parse token to integer".into(),
+            );
+            return Err(());
+        };
+        Ok(NumberLiteral {
+            value,
+            start: minus.map_or(num_token.start_index, |minus| minus.start_index),
+            end: num_token.end_index,
+        })
+    }
+    /// `break ;` or `continue ;`, a jump outside of any loop is reported but still parsed,
+    /// `break` may leave a switch as well
+    fn parse_jump_statement(&mut self, keyword: KeywordType) -> Result<(usize, usize), ()> {
+        let token = self.match_and_consume(TokenType::Keyword(keyword), true)?;
+        if keyword == KeywordType::BREAK && self.loop_depth == 0 && self.switch_depth == 0 {
+            self.error_reporter.add_diagnostic(
+                self.span(token.range()),
+                format!("`{}` outside of a loop or switch", token.token_type),
+            );
+        } else if keyword == KeywordType::CONTINUE && self.loop_depth == 0 {
+            self.error_reporter.add_diagnostic(
+                self.span(token.range()),
+                format!("`{}` outside of a loop", token.token_type),
+            );
+        }
+        let end = (self.match_and_consume(TokenType::Semi, true)?).end_index;
+        Ok((token.start_index, end))
+    }
     fn parse_selection_statement(&mut self) -> Result<SelectionStatement, ()> {
         let start =
             (self.match_and_consume(TokenType::Keyword(KeywordType::IF), true)?).start_index;
@@ -401,16 +902,9 @@ impl<'a> Parser<'a> {
 
     fn parse_var(&mut self) -> Result<Var, ()> {
         let id = self.match_and_consume(TokenType::Id, true)?;
-        let mut expression = None;
-        let end = if self.match_token(TokenType::Lbrack) {
-            self.consume(1);
-            expression = Some(Box::new(self.parse_expression()?));
-            (self.match_and_consume(TokenType::Rbrack, true)?).end_index
-        } else {
-            id.end_index
-        };
+        let (accessors, end) = self.parse_accessors(id.end_index)?;
         Ok(Var {
-            expression,
+            accessors,
             id: Identifier {
                 value: id.content,
                 start: id.start_index,
@@ -420,33 +914,88 @@ impl<'a> Parser<'a> {
             end,
         })
     }
+    /// the `[expression]`s and `.member`s after the identifier of a variable, and the end of
+    /// the last one
+    fn parse_accessors(&mut self, mut end: usize) -> Result<(Vec<Accessor>, usize), ()> {
+        let mut accessors = vec![];
+        loop {
+            if self.match_token(TokenType::Lbrack) {
+                self.consume(1);
+                accessors.push(Accessor::Index(self.parse_expression()?));
+                end = (self.match_and_consume(TokenType::Rbrack, true)?).end_index;
+            } else if self.match_token(TokenType::Dot) {
+                self.consume(1);
+                let id = self.match_and_consume(TokenType::Id, true)?;
+                end = id.end_index;
+                accessors.push(Accessor::Member(Identifier {
+                    value: id.content,
+                    start: id.start_index,
+                    end: id.end_index,
+                }));
+            } else {
+                return Ok((accessors, end));
+            }
+        }
+    }
+    /// a variable or `*` followed by the pointer
+    fn parse_target(&mut self) -> Result<Target, ()> {
+        if self.match_token(TokenType::Multiply) {
+            Ok(Target::Dereference(self.parse_dereference()?))
+        } else {
+            Ok(Target::Var(self.parse_var()?))
+        }
+    }
+
+    /// `*p++` dereferences `p++` like C
+    fn parse_dereference(&mut self) -> Result<DereferenceExpression, ()> {
+        let start = (self.match_and_consume(TokenType::Multiply, true)?).start_index;
+        let pointer = self.parse_factor()?;
+        Ok(DereferenceExpression {
+            start,
+            end: pointer.end(),
+            pointer: Box::new(pointer),
+        })
+    }
+
     fn parse_assignment_expression(&mut self) -> Result<Expression, ()> {
-        let var = self.parse_var()?;
-        self.match_and_consume(TokenType::Assign, true)?;
+        let target = self.parse_target()?;
+        let operation = match self.match_compound_assign_op() {
+            Some(operation) => {
+                self.consume(1);
+                Some(operation)
+            }
+            None => {
+                self.match_and_consume(TokenType::Assign, true)?;
+                None
+            }
+        };
         let expression = self.parse_expression()?;
         Ok(Expression::Assignment(AssignmentExpression {
-            start: var.start,
+            start: target.start(),
             end: expression.end(),
-            lhs: var,
+            lhs: target,
             rhs: Box::new(expression),
+            operation,
         }))
     }
     fn parse_expression(&mut self) -> Result<Expression, ()> {
         let cursor = self.cursor;
+        let diagnostic_count = self.error_reporter.len();
         if let Ok(expr) = self.parse_assignment_expression() {
             return Ok(expr);
         }
-        self.error_reporter.pop_diagnostic("main.cm");
+        self.error_reporter.truncate(diagnostic_count);
         // println!("parse_expression: {}", self.error_reporter.emit_string());
         self.cursor = cursor;
-        if let Ok(expr) = self.parse_or_expression() {
+        if let Ok(expr) = self.parse_conditional_expression() {
             Ok(expr)
         } else {
             // println!("parse_expression: {}", self.error_reporter.emit_string());
-            if cursor < self.token_list.len() {
+            if self.error_reporter.len() > diagnostic_count {
+                // the operand which can't be parsed is already reported
+            } else if cursor < self.token_list.len() {
                 self.error_reporter.add_diagnostic(
-                    "main.cm",
-                    self.token_list[cursor].range(),
+                    self.span(self.token_list[cursor].range()),
                     format!(
                         "expected `(`, `identifier`, `number`, found {:?}",
                         self.token_list[cursor].token_type
@@ -454,14 +1003,31 @@ impl<'a> Parser<'a> {
                 );
             } else {
                 self.error_reporter.add_diagnostic(
-                    "main.cm",
-                    self.get_source_file_end_range(),
+                    self.span(self.get_source_file_end_range()),
                     "".into(),
                 );
             }
             Err(())
         }
     }
+    /// `a ? b : c ? d : e` is `a ? b : (c ? d : e)`
+    fn parse_conditional_expression(&mut self) -> Result<Expression, ()> {
+        let test = self.parse_or_expression()?;
+        if !self.match_token(TokenType::Question) {
+            return Ok(test);
+        }
+        self.consume(1);
+        let consequent = self.parse_expression()?;
+        self.match_and_consume(TokenType::Colon, true)?;
+        let alternative = self.parse_conditional_expression()?;
+        Ok(Expression::Conditional(ConditionalExpression {
+            start: test.start(),
+            end: alternative.end(),
+            test: Box::new(test),
+            consequent: Box::new(consequent),
+            alternative: Box::new(alternative),
+        }))
+    }
     fn parse_or_expression(&mut self) -> Result<Expression, ()> {
         let mut left_expr = self.parse_and_expression()?;
         while self.match_token(TokenType::Or) {
@@ -483,7 +1049,7 @@ impl<'a> Parser<'a> {
         Ok(left_expr)
     }
     fn parse_and_expression(&mut self) -> Result<Expression, ()> {
-        let mut left_expr = self.parse_simple_expression()?;
+        let mut left_expr = self.parse_bit_or_expression()?;
         while self.match_token(TokenType::And) {
             let Token {
                 start_index,
@@ -491,7 +1057,7 @@ impl<'a> Parser<'a> {
                 ..
             } = self.next_token().unwrap().clone();
             self.consume(1);
-            let right_expr = self.parse_simple_expression()?;
+            let right_expr = self.parse_bit_or_expression()?;
             left_expr = Expression::LogicExpression(LogicExpression {
                 start: left_expr.start(),
                 end: right_expr.end(),
@@ -502,11 +1068,57 @@ impl<'a> Parser<'a> {
         }
         Ok(left_expr)
     }
+    fn parse_bit_or_expression(&mut self) -> Result<Expression, ()> {
+        self.parse_left_associative_expression(
+            Self::parse_bit_xor_expression,
+            &[(TokenType::BitOr, Operation::BITOR)],
+        )
+    }
+    fn parse_bit_xor_expression(&mut self) -> Result<Expression, ()> {
+        self.parse_left_associative_expression(
+            Self::parse_bit_and_expression,
+            &[(TokenType::BitXor, Operation::BITXOR)],
+        )
+    }
+    fn parse_bit_and_expression(&mut self) -> Result<Expression, ()> {
+        self.parse_left_associative_expression(
+            Self::parse_simple_expression,
+            &[(TokenType::BitAnd, Operation::BITAND)],
+        )
+    }
+    /// the binary operators of one precedence level, `operand` parses the level above
+    fn parse_left_associative_expression(
+        &mut self,
+        operand: fn(&mut Self) -> Result<Expression, ()>,
+        operators: &[BinaryOperator],
+    ) -> Result<Expression, ()> {
+        let mut left_expr = operand(self)?;
+        while let Some(token) = self.next_token() {
+            let (start, end) = (token.start_index, token.end_index);
+            let operation = match operators
+                .iter()
+                .find(|(token_type, _)| *token_type == token.token_type)
+            {
+                Some((_, operation)) => operation(start, end),
+                None => break,
+            };
+            self.consume(1);
+            let right_expr = operand(self)?;
+            left_expr = Expression::BinaryExpression(BinaryExpression {
+                start: left_expr.start(),
+                end: right_expr.end(),
+                left: Box::new(left_expr),
+                right: Box::new(right_expr),
+                operation,
+            });
+        }
+        Ok(left_expr)
+    }
     fn parse_simple_expression(&mut self) -> Result<Expression, ()> {
-        let left_expr = self.parse_additive_expression()?;
+        let left_expr = self.parse_shift_expression()?;
         if let Some(op) = self.match_rel_op() {
             self.consume(1);
-            let right_expr = self.parse_additive_expression()?;
+            let right_expr = self.parse_shift_expression()?;
             return Ok(Expression::BinaryExpression(BinaryExpression {
                 start: left_expr.start(),
                 end: right_expr.end(),
@@ -518,6 +1130,16 @@ impl<'a> Parser<'a> {
         Ok(left_expr)
     }
 
+    fn parse_shift_expression(&mut self) -> Result<Expression, ()> {
+        self.parse_left_associative_expression(
+            Self::parse_additive_expression,
+            &[
+                (TokenType::Shl, Operation::SHL),
+                (TokenType::Shr, Operation::SHR),
+            ],
+        )
+    }
+
     fn parse_additive_expression(&mut self) -> Result<Expression, ()> {
         let mut left_term = self.parse_term()?;
         // println!("{:?}", left_term);
@@ -557,7 +1179,7 @@ impl<'a> Parser<'a> {
     fn parse_unary_operator(&mut self) -> Option<Token> {
         if let Some(token) = self.next_token() {
             match token.token_type {
-                TokenType::Plus | TokenType::Minus => {
+                TokenType::Plus | TokenType::Minus | TokenType::BitNot | TokenType::Not => {
                     let ret = Some(token.clone());
                     self.consume(1);
                     ret
@@ -584,14 +1206,36 @@ impl<'a> Parser<'a> {
                         value
                     } else {
                         self.error_reporter.add_diagnostic(
-                            "main.cm",
-                            range,
+                            self.span(range),
                             "can't parse token to integer".into(),
                         );
                         return Err(());
                     };
                     Expression::Factor(Factor::NumberLiteral(NumberLiteral { value, start, end }))
                 }
+                TokenType::CharLiteral => {
+                    let token = token.clone();
+                    self.consume(1);
+                    let value = self.unescape_literal(&token)?;
+                    let mut chars = value.chars();
+                    let value = match (chars.next(), chars.next()) {
+                        (Some(value), None) => value,
+                        _ => {
+                            self.error_reporter.add_diagnostic(
+                                self.span(range),
+                                "character literal should contain one character".into(),
+                            );
+                            return Err(());
+                        }
+                    };
+                    Expression::Factor(Factor::CharLiteral(CharLiteral { value, start, end }))
+                }
+                TokenType::StringLiteral => {
+                    let token = token.clone();
+                    self.consume(1);
+                    let value = self.unescape_literal(&token)?.into();
+                    Expression::Factor(Factor::StringLiteral(StringLiteral { value, start, end }))
+                }
                 TokenType::BooleanLiteral => {
                     self.consume(1);
                     Expression::Factor(Factor::BooleanLiteral(BooleanLiteral {
@@ -611,64 +1255,81 @@ impl<'a> Parser<'a> {
                 TokenType::Id => {
                     let value = token.content.clone();
                     self.consume(1);
-                    if let Some(token) = self.next_token() {
-                        match token.token_type {
-                            TokenType::Lparen => {
-                                self.consume(1);
-                                let arguments = self.parse_args()?;
-                                let call_expression_end =
-                                    (self.match_and_consume(TokenType::Rparen, true)?).end_index;
-                                Expression::Factor(Factor::CallExpression(CallExpression {
-                                    arguments,
-                                    id: Identifier { value, start, end },
-                                    start,
-                                    end: call_expression_end,
-                                }))
-                            }
-                            TokenType::Lbrack => {
-                                self.consume(1);
-                                let local_expression = self.parse_expression()?;
-                                let var_end =
-                                    (self.match_and_consume(TokenType::Rbrack, true)?).end_index;
-                                let var = Var {
-                                    id: Identifier { value, start, end },
-                                    expression: Some(Box::new(local_expression)),
-                                    start,
-                                    end: var_end,
-                                };
-                                Expression::Factor(Factor::Var(var))
-                            }
-                            _ => Expression::Factor(Factor::Var(Var {
-                                expression: None,
-                                id: Identifier { value, start, end },
-                                start,
-                                end,
-                            })),
-                        }
+                    if self.match_token(TokenType::Lparen) {
+                        self.consume(1);
+                        let arguments = self.parse_args()?;
+                        let call_expression_end =
+                            (self.match_and_consume(TokenType::Rparen, true)?).end_index;
+                        Expression::Factor(Factor::CallExpression(CallExpression {
+                            arguments,
+                            id: Identifier { value, start, end },
+                            start,
+                            end: call_expression_end,
+                        }))
                     } else {
+                        let (accessors, var_end) = self.parse_accessors(end)?;
                         Expression::Factor(Factor::Var(Var {
-                            expression: None,
                             id: Identifier { value, start, end },
+                            accessors,
                             start,
-                            end,
+                            end: var_end,
                         }))
                     }
                 }
+                TokenType::Increment | TokenType::Decrement => {
+                    let operation = self.match_update_op().unwrap();
+                    self.consume(1);
+                    let target = self.parse_target()?;
+                    Expression::UpdateExpression(UpdateExpression {
+                        start,
+                        end: target.end(),
+                        target,
+                        operation,
+                        prefix: true,
+                    })
+                }
+                TokenType::Multiply => Expression::Dereference(self.parse_dereference()?),
+                TokenType::BitAnd => {
+                    self.consume(1);
+                    let var = self.parse_var()?;
+                    Expression::Address(AddressExpression {
+                        start,
+                        end: var.end,
+                        var,
+                    })
+                }
                 _ => {
                     let token = token.clone();
                     self.error_reporter.add_diagnostic(
-                        "main.cm",
-                        token.range(),
+                        self.span(token.range()),
                         "expected `Identifier`, `NumberLiteral`, `(`".to_string(),
                     );
                     return Err(());
                 }
             };
+            let factor = match factor {
+                Expression::Factor(Factor::Var(var)) => match self.match_update_op() {
+                    Some(operation) => {
+                        self.consume(1);
+                        Expression::UpdateExpression(UpdateExpression {
+                            start: var.start,
+                            end: operation.end(),
+                            target: Target::Var(var),
+                            operation,
+                            prefix: false,
+                        })
+                    }
+                    None => Expression::Factor(Factor::Var(var)),
+                },
+                factor => factor,
+            };
             if let Some(token) = unary_token {
                 let (unary_start, unary_end) = (token.start_index, token.end_index);
                 let operation = match token.token_type {
                     TokenType::Plus => Operation::POS(unary_start, unary_end),
                     TokenType::Minus => Operation::NEG(unary_start, unary_end),
+                    TokenType::BitNot => Operation::BITNOT(unary_start, unary_end),
+                    TokenType::Not => Operation::NOT(unary_start, unary_end),
                     _ => {
                         unreachable!()
                     }
@@ -684,8 +1345,7 @@ impl<'a> Parser<'a> {
         }
 
         self.error_reporter.add_diagnostic(
-            "main.cm",
-            self.get_source_file_end_range(),
+            self.span(self.get_source_file_end_range()),
             "expected Token found None".into(),
         );
         return Err(());
@@ -711,7 +1371,8 @@ impl<'a> Parser<'a> {
             end: id_token.end_index,
         };
         let mut is_array = false;
-        let end = if self.match_token(TokenType::Lbrack) {
+        let mut dimensions = vec![];
+        let mut end = if self.match_token(TokenType::Lbrack) {
             let end: usize;
             self.match_and_consume(TokenType::Lbrack, true)?;
             end = (self.match_and_consume(TokenType::Rbrack, true)?).end_index;
@@ -720,15 +1381,31 @@ impl<'a> Parser<'a> {
         } else {
             id_token.end_index
         };
+        while is_array && self.match_token(TokenType::Lbrack) {
+            self.consume(1);
+            dimensions.push(self.parse_array_size()?);
+            end = (self.match_and_consume(TokenType::Rbrack, true)?).end_index;
+        }
         Ok(Parameter {
             start: type_specifier.start,
             end,
             type_specifier,
             id: identifier,
             is_array,
+            dimensions,
         })
     }
+    /// every `*` after the type makes a pointer to it
     fn parse_type_specifier(&mut self) -> Result<TypeSpecifier, ()> {
+        let mut type_specifier = self.parse_base_type_specifier()?;
+        while self.match_token(TokenType::Multiply) {
+            type_specifier.end = (self.match_and_consume(TokenType::Multiply, true)?).end_index;
+            type_specifier.kind = TypeSpecifierKind::Pointer(Box::new(type_specifier.kind));
+        }
+        Ok(type_specifier)
+    }
+
+    fn parse_base_type_specifier(&mut self) -> Result<TypeSpecifier, ()> {
         if let Some(token) = self.next_token() {
             let start = token.start_index;
             let end = token.end_index;
@@ -757,11 +1434,27 @@ impl<'a> Parser<'a> {
                         end,
                     });
                 }
+                TokenType::Keyword(KeywordType::CHAR) => {
+                    self.consume(1);
+                    return Ok(TypeSpecifier {
+                        kind: TypeSpecifierKind::Char,
+                        start,
+                        end,
+                    });
+                }
+                TokenType::Keyword(KeywordType::STRUCT) => {
+                    self.consume(1);
+                    let id_token = self.match_and_consume(TokenType::Id, true)?;
+                    return Ok(TypeSpecifier {
+                        kind: TypeSpecifierKind::Struct(id_token.content),
+                        start,
+                        end: id_token.end_index,
+                    });
+                }
                 _ => {
                     let token = token.clone();
                     self.error_reporter.add_diagnostic(
-                        "main.cm",
-                        token.range(),
+                        self.span(token.range()),
                         format!(
                             "expected `int` or `void` or `bool`, found {}",
                             token.token_type
@@ -772,8 +1465,7 @@ impl<'a> Parser<'a> {
             }
         }
         self.error_reporter.add_diagnostic(
-            "main.cm",
-            self.get_source_file_end_range(),
+            self.span(self.get_source_file_end_range()),
             "expected `int` , `void` or `bool` ".into(),
         );
         return Err(());
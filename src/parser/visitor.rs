@@ -3,11 +3,16 @@ use super::{ast::*, Codespan};
 pub trait Visitor<T> {
     fn visit_program(&mut self, node: &Program) -> T;
     fn visit_function_declaration(&mut self, node: &FunctionDeclaration) -> T;
+    fn visit_function_prototype(&mut self, node: &FunctionPrototype) -> T;
     fn visit_var_declaration(&mut self, node: &VarDeclaration) -> T;
+    fn visit_initializer(&mut self, node: &Initializer) -> T;
+    fn visit_struct_declaration(&mut self, node: &StructDeclaration) -> T;
     fn visit_declaration(&mut self, node: &Declaration) -> T;
     fn visit_identifier(&mut self, node: &Identifier) -> T;
     fn visit_number_literal(&mut self, node: &NumberLiteral) -> T;
     fn visit_boolean_literal(&mut self, node: &BooleanLiteral) -> T;
+    fn visit_char_literal(&mut self, node: &CharLiteral) -> T;
+    fn visit_string_literal(&mut self, node: &StringLiteral) -> T;
     fn visit_type_specifier(&mut self, node: &TypeSpecifier) -> T;
     fn visit_params(&mut self, node: &Params) -> T;
     fn visit_parameter(&mut self, node: &Parameter) -> T;
@@ -15,17 +20,28 @@ pub trait Visitor<T> {
     fn visit_statement(&mut self, node: &Statement) -> T;
     fn visit_selection_statement(&mut self, node: &SelectionStatement) -> T;
     fn visit_iteration_statement(&mut self, node: &IterationStatement) -> T;
+    fn visit_for_statement(&mut self, node: &ForStatement) -> T;
+    fn visit_do_while_statement(&mut self, node: &DoWhileStatement) -> T;
+    fn visit_switch_statement(&mut self, node: &SwitchStatement) -> T;
+    fn visit_break_statement(&mut self, node: &BreakStatement) -> T;
+    fn visit_continue_statement(&mut self, node: &ContinueStatement) -> T;
     fn visit_return_statement(&mut self, node: &ReturnStatement) -> T;
     fn visit_expression_statement(&mut self, node: &ExpressionStatement) -> T;
     fn visit_expression(&mut self, node: &Expression) -> T;
     fn visit_assignment_expression(&mut self, node: &AssignmentExpression) -> T;
     fn visit_var(&mut self, node: &Var) -> T;
+    fn visit_target(&mut self, node: &Target) -> T;
+    fn visit_address_expression(&mut self, node: &AddressExpression) -> T;
+    fn visit_dereference_expression(&mut self, node: &DereferenceExpression) -> T;
     fn visit_logic_expression(&mut self, node: &LogicExpression) -> T;
+    fn visit_conditional_expression(&mut self, node: &ConditionalExpression) -> T;
     fn visit_unary_expression(&mut self, node: &UnaryExpression) -> T;
+    fn visit_update_expression(&mut self, node: &UpdateExpression) -> T;
     fn visit_binary_expression(&mut self, node: &BinaryExpression) -> T;
     fn visit_operation(&mut self, node: &Operation) -> T;
     fn visit_factor(&mut self, node: &Factor) -> T;
     fn visit_call_expression(&mut self, node: &CallExpression) -> T;
+    fn visit_error_node(&mut self, node: &ErrorNode) -> T;
 }
 
 #[derive(Default)]
@@ -74,6 +90,22 @@ impl Visitor<String> for AstPrinter {
         ast
     }
 
+    fn visit_function_prototype(&mut self, node: &FunctionPrototype) -> String {
+        let ast = format!(
+            "{}FunctionPrototype {}\n",
+            " ".repeat(2 * self.level),
+            generate_codespan_postfix(node)
+        );
+        self.level += 1;
+        let children = [
+            self.visit_type_specifier(&node.type_specifier),
+            self.visit_identifier(&node.id),
+            self.visit_params(&node.params),
+        ];
+        self.level -= 1;
+        ast + &children.join("\n")
+    }
+
     fn visit_var_declaration(&mut self, node: &VarDeclaration) -> String {
         let ast = format!(
             "{}VarDeclaration {}\n",
@@ -85,8 +117,11 @@ impl Visitor<String> for AstPrinter {
             self.visit_type_specifier(&node.type_specifier),
             self.visit_identifier(&node.id),
         ];
-        if let Some(ref num) = node.num {
-            children.push(self.visit_number_literal(num));
+        for dimension in node.dimensions.iter() {
+            children.push(match dimension {
+                ArraySize::Number(num) => self.visit_number_literal(num),
+                ArraySize::Constant(id) => self.visit_identifier(id),
+            });
         }
         if let Some(ref initializer) = node.initializer {
             children.push(
@@ -98,7 +133,7 @@ impl Visitor<String> for AstPrinter {
             children.push(format!("{}<Initializer>", " ".repeat(2 * (self.level + 1)),));
             self.level += 1;
             for init in initializer {
-                children.push(self.visit_expression(init));
+                children.push(self.visit_initializer(init));
             }
             self.level -= 1;
         }
@@ -106,12 +141,53 @@ impl Visitor<String> for AstPrinter {
         ast + &children.join("\n")
     }
 
+    fn visit_struct_declaration(&mut self, node: &StructDeclaration) -> String {
+        let ast = format!(
+            "{}StructDeclaration {}\n",
+            " ".repeat(2 * self.level),
+            generate_codespan_postfix(node)
+        );
+        self.level += 1;
+        let mut children = vec![self.visit_identifier(&node.id)];
+        for field in node.fields.iter() {
+            children.push(self.visit_var_declaration(field));
+        }
+        self.level -= 1;
+        ast + &children.join("\n")
+    }
+
+    fn visit_initializer(&mut self, node: &Initializer) -> String {
+        match node {
+            Initializer::Expression(expr) => self.visit_expression(expr),
+            Initializer::List(list) => {
+                let ast = format!(
+                    "{}InitializerList {}\n",
+                    " ".repeat(2 * self.level),
+                    generate_codespan_postfix(list)
+                );
+                self.level += 1;
+                let children = list
+                    .elements
+                    .iter()
+                    .map(|init| self.visit_initializer(init))
+                    .collect::<Vec<_>>();
+                self.level -= 1;
+                ast + &children.join("\n")
+            }
+        }
+    }
+
     fn visit_declaration(&mut self, node: &Declaration) -> String {
         match node {
             Declaration::VarDeclaration(var_decl) => self.visit_var_declaration(var_decl),
+            Declaration::StructDeclaration(struct_decl) => {
+                self.visit_struct_declaration(struct_decl)
+            }
             Declaration::FunctionDeclaration(func_decl) => {
                 self.visit_function_declaration(func_decl)
             }
+            Declaration::FunctionPrototype(prototype) => self.visit_function_prototype(prototype),
+            Declaration::Error(error) => self.visit_error_node(error),
         }
     }
 
@@ -142,6 +218,24 @@ impl Visitor<String> for AstPrinter {
         )
     }
 
+    fn visit_char_literal(&mut self, node: &CharLiteral) -> String {
+        format!(
+            "{}CharLiteral({:?}) {}",
+            " ".repeat(2 * self.level),
+            node.value,
+            generate_codespan_postfix(node)
+        )
+    }
+
+    fn visit_string_literal(&mut self, node: &StringLiteral) -> String {
+        format!(
+            "{}StringLiteral({:?}) {}",
+            " ".repeat(2 * self.level),
+            node.value,
+            generate_codespan_postfix(node)
+        )
+    }
+
     fn visit_type_specifier(&mut self, node: &TypeSpecifier) -> String {
         format!(
             "{}TypeSpecifier({:?}) {}",
@@ -153,7 +247,7 @@ impl Visitor<String> for AstPrinter {
 
     fn visit_params(&mut self, node: &Params) -> String {
         match node {
-            Params::Void => format!(
+            Params::Void { .. } => format!(
                 "{}Void {}",
                 " ".repeat(2 * self.level),
                 generate_codespan_postfix(node)
@@ -192,11 +286,18 @@ impl Visitor<String> for AstPrinter {
 
     fn visit_parameter(&mut self, node: &Parameter) -> String {
         format!(
-            "{}Parameter({:?} {}{}) {}",
+            "{}Parameter({:?} {}{}{}) {}",
             " ".repeat(2 * self.level),
             node.type_specifier.kind,
             node.id.value,
             if node.is_array { "[]" } else { "" },
+            node.dimensions.iter().fold(String::new(), |dimensions, dimension| {
+                let size = match dimension {
+                    ArraySize::Number(num) => num.value.to_string(),
+                    ArraySize::Constant(id) => id.value.to_string(),
+                };
+                dimensions + "[" + &size + "]"
+            }),
             generate_codespan_postfix(node)
         )
     }
@@ -242,7 +343,13 @@ impl Visitor<String> for AstPrinter {
             Statement::ExpressionStatement(stmt) => self.visit_expression_statement(stmt),
             Statement::SelectionStatement(stmt) => self.visit_selection_statement(stmt),
             Statement::IterationStatement(stmt) => self.visit_iteration_statement(stmt),
+            Statement::ForStatement(stmt) => self.visit_for_statement(stmt),
+            Statement::DoWhileStatement(stmt) => self.visit_do_while_statement(stmt),
+            Statement::SwitchStatement(stmt) => self.visit_switch_statement(stmt),
+            Statement::BreakStatement(stmt) => self.visit_break_statement(stmt),
+            Statement::ContinueStatement(stmt) => self.visit_continue_statement(stmt),
             Statement::ReturnStatement(stmt) => self.visit_return_statement(stmt),
+            Statement::Error(error) => self.visit_error_node(error),
         }
     }
 
@@ -284,6 +391,105 @@ impl Visitor<String> for AstPrinter {
         ast + &children.join("\n")
     }
 
+    fn visit_for_statement(&mut self, node: &ForStatement) -> String {
+        let ast = format!(
+            "{}ForStatement {}\n",
+            " ".repeat(2 * self.level),
+            generate_codespan_postfix(node)
+        );
+        self.level += 1;
+        let mut children = vec![];
+        for expr in [&node.init, &node.test, &node.update].iter().copied().flatten() {
+            children.push(self.visit_expression(expr));
+        }
+        children.push(self.visit_statement(&node.body));
+        self.level -= 1;
+        ast + &children
+            .into_iter()
+            .filter(|child| !child.is_empty())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    fn visit_do_while_statement(&mut self, node: &DoWhileStatement) -> String {
+        let ast = format!(
+            "{}DoWhileStatement {}\n",
+            " ".repeat(2 * self.level),
+            generate_codespan_postfix(node)
+        );
+        self.level += 1;
+        let children = vec![
+            self.visit_statement(&node.body),
+            self.visit_expression(&node.test),
+        ];
+        self.level -= 1;
+        ast + &children
+            .into_iter()
+            .filter(|child| !child.is_empty())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    fn visit_switch_statement(&mut self, node: &SwitchStatement) -> String {
+        let ast = format!(
+            "{}SwitchStatement {}\n",
+            " ".repeat(2 * self.level),
+            generate_codespan_postfix(node)
+        );
+        self.level += 1;
+        let mut children = vec![self.visit_expression(&node.test)];
+        for case in node.cases.iter() {
+            let name = if case.label.is_some() { "SwitchCase" } else { "DefaultCase" };
+            let mut case_ast = format!(
+                "{}{} {}",
+                " ".repeat(2 * self.level),
+                name,
+                generate_codespan_postfix(case)
+            );
+            self.level += 1;
+            if let Some(ref label) = case.label {
+                case_ast += "\n";
+                case_ast += &format!(
+                    "{}NumberLiteral({}) {}",
+                    " ".repeat(2 * self.level),
+                    label.value,
+                    generate_codespan_postfix(label)
+                );
+            }
+            for stmt in case.statements.iter() {
+                let stmt_ast = self.visit_statement(stmt);
+                if !stmt_ast.is_empty() {
+                    case_ast += "\n";
+                    case_ast += &stmt_ast;
+                }
+            }
+            self.level -= 1;
+            children.push(case_ast);
+        }
+        self.level -= 1;
+        ast + &children
+            .into_iter()
+            .filter(|child| !child.is_empty())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    fn visit_break_statement(&mut self, node: &BreakStatement) -> String {
+        format!(
+            "{}BreakStatement {}",
+            " ".repeat(2 * self.level),
+            generate_codespan_postfix(node)
+        )
+    }
+
+    fn visit_continue_statement(&mut self, node: &ContinueStatement) -> String {
+        format!(
+            "{}ContinueStatement {}",
+            " ".repeat(2 * self.level),
+            generate_codespan_postfix(node)
+        )
+    }
+
     fn visit_return_statement(&mut self, node: &ReturnStatement) -> String {
         let mut ast = format!(
             "{}ReturnStatement {}\n",
@@ -347,6 +553,10 @@ impl Visitor<String> for AstPrinter {
                 self.level -= 1;
                 ast + &children.join("\n")
             }
+            Expression::Conditional(expr) => self.visit_conditional_expression(expr),
+            Expression::UpdateExpression(expr) => self.visit_update_expression(expr),
+            Expression::Address(expr) => self.visit_address_expression(expr),
+            Expression::Dereference(expr) => self.visit_dereference_expression(expr),
             Expression::UnaryExpression(expr) => {
                 let ast = format!(
                     "{}UnaryExpression {}\n",
@@ -366,13 +576,13 @@ impl Visitor<String> for AstPrinter {
 
     fn visit_assignment_expression(&mut self, node: &AssignmentExpression) -> String {
         self.level += 1;
-        let ret = format!(
-            "{}\n{}",
-            self.visit_var(&node.lhs),
-            self.visit_expression(&node.rhs)
-        );
+        let mut children = vec![self.visit_target(&node.lhs)];
+        if let Some(ref operation) = node.operation {
+            children.push(self.visit_operation(operation));
+        }
+        children.push(self.visit_expression(&node.rhs));
         self.level -= 1;
-        ret
+        children.join("\n")
     }
 
     fn visit_var(&mut self, node: &Var) -> String {
@@ -387,22 +597,88 @@ impl Visitor<String> for AstPrinter {
             ),
             id,
         ];
-        if let Some(ref expr) = node.expression {
-            self.level += 1;
-            result.push(self.visit_expression(expr));
-            self.level -= 1;
+        self.level += 1;
+        for accessor in node.accessors.iter() {
+            result.push(match accessor {
+                Accessor::Index(expr) => self.visit_expression(expr),
+                Accessor::Member(id) => self.visit_identifier(id),
+            });
         }
+        self.level -= 1;
         result.join("\n")
     }
 
+    fn visit_target(&mut self, node: &Target) -> String {
+        match node {
+            Target::Var(var) => self.visit_var(var),
+            Target::Dereference(expr) => self.visit_dereference_expression(expr),
+        }
+    }
+
+    fn visit_address_expression(&mut self, node: &AddressExpression) -> String {
+        let ast = format!(
+            "{}AddressExpression {}\n",
+            " ".repeat(2 * self.level),
+            generate_codespan_postfix(node)
+        );
+        self.level += 1;
+        let var = self.visit_var(&node.var);
+        self.level -= 1;
+        ast + &var
+    }
+
+    fn visit_dereference_expression(&mut self, node: &DereferenceExpression) -> String {
+        let ast = format!(
+            "{}DereferenceExpression {}\n",
+            " ".repeat(2 * self.level),
+            generate_codespan_postfix(node)
+        );
+        self.level += 1;
+        let pointer = self.visit_expression(&node.pointer);
+        self.level -= 1;
+        ast + &pointer
+    }
+
     fn visit_logic_expression(&mut self, node: &LogicExpression) -> String {
         todo!()
     }
 
+    fn visit_conditional_expression(&mut self, node: &ConditionalExpression) -> String {
+        let ast = format!(
+            "{}ConditionalExpression {}\n",
+            " ".repeat(2 * self.level),
+            generate_codespan_postfix(node)
+        );
+        self.level += 1;
+        let children = [
+            self.visit_expression(&node.test),
+            self.visit_expression(&node.consequent),
+            self.visit_expression(&node.alternative),
+        ];
+        self.level -= 1;
+        ast + &children.join("\n")
+    }
+
     fn visit_unary_expression(&mut self, node: &UnaryExpression) -> String {
         todo!()
     }
 
+    fn visit_update_expression(&mut self, node: &UpdateExpression) -> String {
+        let ast = format!(
+            "{}UpdateExpression({}) {}\n",
+            " ".repeat(2 * self.level),
+            if node.prefix { "prefix" } else { "postfix" },
+            generate_codespan_postfix(node)
+        );
+        self.level += 1;
+        let children = [
+            self.visit_operation(&node.operation),
+            self.visit_target(&node.target),
+        ];
+        self.level -= 1;
+        ast + &children.join("\n")
+    }
+
     fn visit_binary_expression(&mut self, node: &BinaryExpression) -> String {
         todo!()
     }
@@ -423,6 +699,8 @@ impl Visitor<String> for AstPrinter {
             Factor::CallExpression(call) => self.visit_call_expression(call),
             Factor::NumberLiteral(num) => self.visit_number_literal(num),
             Factor::BooleanLiteral(boolean) => self.visit_boolean_literal(boolean),
+            Factor::CharLiteral(char) => self.visit_char_literal(char),
+            Factor::StringLiteral(string) => self.visit_string_literal(string),
         }
     }
 
@@ -462,6 +740,14 @@ impl Visitor<String> for AstPrinter {
             .collect::<Vec<String>>()
             .join("\n")
     }
+
+    fn visit_error_node(&mut self, node: &ErrorNode) -> String {
+        format!(
+            "{}ErrorNode {}",
+            " ".repeat(2 * self.level),
+            generate_codespan_postfix(node)
+        )
+    }
 }
 
 fn generate_codespan_postfix<T>(node: &T) -> String
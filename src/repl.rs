@@ -0,0 +1,366 @@
+use std::ops::Range;
+
+use smol_str::SmolStr;
+
+use crate::{
+    error_emit::{ErrorReporter, FileId, FileSpan},
+    host::Host,
+    interpreter::{
+        self,
+        env::{Binding, Environment},
+        RuntimeError,
+    },
+    lexer::{lex::Lexer, token::TokenType},
+    native::Natives,
+    parser::{
+        ast::{
+            CompoundStatement, Declaration, FunctionDeclaration, Identifier, Params, Program,
+            Statement, TypeSpecifier, TypeSpecifierKind,
+        },
+        parse::Parser,
+        visitor::{AstPrinter, Visitor},
+        Codespan,
+    },
+    semantic,
+};
+
+// diagnostics refer to the input being evaluated by this name
+const FILE_NAME: &str = "<repl>";
+
+// the function wrapping statements while they are checked, it can't be called from the input
+const STATEMENTS: &str = "<statements>";
+
+const HELP: &str = "enter declarations, statements or expressions, the value of an expression is \
+printed. The `;` ending the input may be left out
+    :ast <input>       print the syntax tree of the input
+    :tokens <input>    print the tokens of the input
+    :disasm [input]    print the vm instructions of the input, or of every function entered
+    :help              print this message
+";
+
+/// the input of the REPL, parsed
+enum Input {
+    Declarations(Program),
+    Statements(Vec<Statement>),
+}
+
+/// a declaration entered in the REPL, a declaration with the same name replaces it
+struct Entered {
+    name: SmolStr,
+    // the input is checked with the declarations entered before it
+    declaration: Declaration,
+    // `:disasm` compiles the source
+    source: String,
+}
+
+/// evaluate the input line by line, the globals and functions are kept in the environment of the
+/// interpreter between inputs
+pub struct Repl {
+    env: Environment,
+    declarations: Vec<Entered>,
+    // every input one after another, the spans of an input are offsets of `text`, so a function
+    // entered before is reported in its own input
+    text: String,
+    // the range of every input in `text`
+    inputs: Vec<Range<usize>>,
+}
+
+impl Repl {
+    /// the output of the program is written to `host`, the output of the REPL is returned by
+    /// `eval`
+    pub fn new(host: Box<dyn Host>) -> Self {
+        Self {
+            env: Environment::new(host, Natives::default()),
+            declarations: vec![],
+            text: String::new(),
+            inputs: vec![],
+        }
+    }
+
+    /// whether the input can be evaluated, while a brace or a parenthesis is open the input
+    /// continues on the next line
+    pub fn is_complete(input: &str) -> bool {
+        if input.trim_start().starts_with(':') {
+            return true;
+        }
+        let mut depth = 0i32;
+        for token in Lexer::new(input).lex() {
+            match token.token_type {
+                TokenType::Lbrace | TokenType::Lparen => depth += 1,
+                TokenType::Rbrace | TokenType::Rparen => depth -= 1,
+                _ => {}
+            }
+        }
+        depth <= 0
+    }
+
+    /// evaluate a declaration, a statement or a meta command, the result is what the REPL
+    /// prints: the value of an expression, the output of the command or the diagnostics
+    pub fn eval(&mut self, input: &str) -> String {
+        let input = input.trim();
+        if input.is_empty() {
+            return String::new();
+        }
+        if let Some(command) = input.strip_prefix(':') {
+            let (name, argument) = match command.find(char::is_whitespace) {
+                Some(index) => (&command[..index], command[index..].trim()),
+                None => (command, ""),
+            };
+            return match name {
+                "ast" => self.ast(argument),
+                "tokens" => self.tokens(argument),
+                "disasm" => self.disassemble(argument),
+                "help" => HELP.into(),
+                _ => format!("unknown command `:{}`, try `:help`\n", name),
+            };
+        }
+        let start = self.text.len();
+        self.text += &terminated(input);
+        self.inputs.push(start..self.text.len());
+        let input = match parse_at(&self.text, start) {
+            Ok(input) => input,
+            Err(error_reporter) => return self.emit(error_reporter),
+        };
+        if let Err(diagnostics) = self.check(&input) {
+            return diagnostics;
+        }
+        let result = match input {
+            Input::Declarations(program) => self.define(&program),
+            Input::Statements(statements) => {
+                statements.iter().try_fold(String::new(), |output, statement| {
+                    let binding = interpreter::evaluate_statement(statement, &mut self.env)?;
+                    Ok(match binding {
+                        Binding::Void => output,
+                        binding => output + &interpreter::to_print_string(&binding) + "\n",
+                    })
+                })
+            }
+        };
+        // the output of the program isn't buffered behind the output of the REPL
+        self.env.host.flush();
+        result.unwrap_or_else(|error| self.emit(error.report(FILE_NAME, &self.text)))
+    }
+
+    /// the diagnostics of `error_reporter`, which has `text` as `FILE_NAME`, pointed at the
+    /// inputs they are in, like the `SourceMap` of the preprocessor
+    fn emit(&self, mut error_reporter: ErrorReporter) -> String {
+        if let Some(text) = error_reporter.file_id(FILE_NAME) {
+            let ids: Vec<FileId> = self
+                .inputs
+                .iter()
+                .map(|range| error_reporter.add_file(FILE_NAME, self.text[range.clone()].into()))
+                .collect();
+            error_reporter.remap_file(text, |range| {
+                let index = self
+                    .inputs
+                    .partition_point(|input| input.start <= range.start)
+                    .checked_sub(1)?;
+                let input = &self.inputs[index];
+                let end = range.end.clamp(range.start, input.end);
+                Some(FileSpan::new(ids[index], range.start - input.start..end - input.start))
+            });
+        }
+        error_reporter.emit_string()
+    }
+
+    /// resolve the names and check the types of the input with the declarations entered before,
+    /// statements are checked as the body of a `void` function
+    fn check(&self, input: &Input) -> Result<(), String> {
+        let declarations = match input {
+            Input::Declarations(program) => program.declarations.clone(),
+            Input::Statements(statements) => {
+                let Range { start, end } = self.inputs.last().cloned().unwrap_or_default();
+                vec![Declaration::FunctionDeclaration(FunctionDeclaration {
+                    type_specifier: TypeSpecifier {
+                        kind: TypeSpecifierKind::Void,
+                        start,
+                        end: start,
+                    },
+                    id: Identifier {
+                        value: STATEMENTS.into(),
+                        start,
+                        end: start,
+                    },
+                    params: Params::ParamsList { params: vec![] },
+                    body: CompoundStatement {
+                        local_declaration: vec![],
+                        statement_list: statements.clone(),
+                        start,
+                        end,
+                    },
+                    start,
+                    end,
+                })]
+            }
+        };
+        let mut program = Program {
+            declarations: self
+                .declarations
+                .iter()
+                .map(|entered| entered.declaration.clone())
+                .collect(),
+            start: 0,
+            end: self.text.len(),
+        };
+        for decl in declarations {
+            match declared_name(&decl).and_then(|name| self.position(&name)) {
+                Some(index) => program.declarations[index] = decl,
+                None => program.declarations.push(decl),
+            }
+        }
+        let (_, checked) = semantic::check(FILE_NAME, &self.text, &program);
+        checked.map_err(|error_reporter| self.emit(error_reporter))
+    }
+
+    fn define(&mut self, program: &Program) -> Result<String, RuntimeError> {
+        interpreter::evaluate_declarations(program, &mut self.env)?;
+        for decl in program.declarations.iter() {
+            let name = match declared_name(decl) {
+                Some(name) => name,
+                None => continue,
+            };
+            let entered = Entered {
+                declaration: decl.clone(),
+                source: self.text[decl.start()..decl.end()].to_string(),
+                name,
+            };
+            match self.position(&entered.name) {
+                Some(index) => self.declarations[index] = entered,
+                None => self.declarations.push(entered),
+            }
+        }
+        Ok(String::new())
+    }
+
+    /// the index of the declaration of `name`, it's replaced in place so the declarations after
+    /// it still see the name
+    fn position(&self, name: &SmolStr) -> Option<usize> {
+        self.declarations
+            .iter()
+            .position(|entered| entered.name == *name)
+    }
+
+    fn ast(&self, argument: &str) -> String {
+        let mut printer = AstPrinter::default();
+        match parse(&terminated(argument)) {
+            Ok(Input::Declarations(program)) => printer.print_ast(&program) + "\n",
+            Ok(Input::Statements(statements)) => statements
+                .iter()
+                .map(|statement| printer.visit_statement(statement) + "\n")
+                .collect(),
+            Err(diagnostics) => diagnostics,
+        }
+    }
+
+    fn tokens(&self, argument: &str) -> String {
+        let mut output = String::new();
+        for token in Lexer::new(argument).lex() {
+            let position = &token.start_position;
+            let location = format!("{}:{}", position.line + 1, position.column + 1);
+            let token_type = format!("{:?}", token.token_type);
+            output += &format!("{:<8} {:<24} {:?}\n", location, token_type, token.content);
+        }
+        output
+    }
+
+    /// compile the declarations entered so far with the input, declarations are followed by an
+    /// empty `main` and statements become the body of `main`. Only the functions of the input
+    /// are printed, or every function entered if there is no input
+    #[cfg(target_arch = "x86_64")]
+    fn disassemble(&self, argument: &str) -> String {
+        use crate::vm::{op_code, EmitOperationCode, Vm};
+
+        let argument = terminated(argument);
+        let mut source: String = self
+            .declarations
+            .iter()
+            .filter(|entered| entered.name != "main")
+            .map(|entered| entered.source.clone() + "\n")
+            .collect();
+        let names: Vec<SmolStr> = match parse(&argument) {
+            Ok(_) if argument.is_empty() => {
+                let main = self.declarations.iter().find(|entered| entered.name == "main");
+                source += main.map_or("void main() {}", |main| main.source.as_str());
+                self.declarations.iter().map(|entered| entered.name.clone()).collect()
+            }
+            Ok(Input::Declarations(program)) => {
+                source += &argument;
+                source += "\nvoid main() {}";
+                program
+                    .declarations
+                    .iter()
+                    .filter_map(|decl| match decl {
+                        Declaration::FunctionDeclaration(func) => Some(func.id.value.clone()),
+                        _ => None,
+                    })
+                    .collect()
+            }
+            Ok(Input::Statements(_)) => {
+                source += &format!("void main() {{\n{}\n}}", argument);
+                vec!["main".into()]
+            }
+            Err(diagnostics) => return diagnostics,
+        };
+        let mut program = match parse(&source) {
+            Ok(Input::Declarations(program)) => program,
+            Ok(Input::Statements(_)) => unreachable!("the source ends with `main`"),
+            Err(diagnostics) => return diagnostics,
+        };
+        let mut vm = Vm::new();
+        if let Err(error) = program.emit(&mut vm) {
+            return format!("{}\n", error);
+        }
+        vm.functions()
+            .iter()
+            .filter(|function| names.contains(function.name()))
+            .map(|function| op_code::disassemble(function, |_| String::new()))
+            .collect()
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    fn disassemble(&self, _: &str) -> String {
+        "the vm isn't available on this target\n".into()
+    }
+}
+
+/// the name a declaration defines, a prototype defines nothing
+fn declared_name(decl: &Declaration) -> Option<SmolStr> {
+    match decl {
+        Declaration::FunctionDeclaration(func) => Some(func.id.value.clone()),
+        Declaration::VarDeclaration(var) => Some(var.id.value.clone()),
+        Declaration::StructDeclaration(decl) => Some(decl.id.value.clone()),
+        Declaration::FunctionPrototype(_) | Declaration::Error(_) => None,
+    }
+}
+
+/// the input with the `;` which may be left out after an expression or a declaration
+fn terminated(input: &str) -> String {
+    let input = input.trim();
+    let terminated = input.is_empty() || input.ends_with(';') || input.ends_with('}');
+    if terminated || !Repl::is_complete(input) {
+        input.to_string()
+    } else {
+        format!("{};", input)
+    }
+}
+
+/// declarations if the input starts with a type specifier, statements otherwise
+fn parse(source: &str) -> Result<Input, String> {
+    parse_at(source, 0).map_err(|error_reporter| error_reporter.emit_string())
+}
+
+/// parse the input starting at `start` of `text`, the spans are offsets of `text`
+fn parse_at(text: &str, start: usize) -> Result<Input, ErrorReporter<'_>> {
+    let mut tokens = Lexer::new(&text[start..]).lex();
+    for token in tokens.iter_mut() {
+        token.start_index += start;
+        token.end_index += start;
+    }
+    let mut parser = Parser::new(tokens, FILE_NAME, text);
+    let input = if parser.at_declaration() {
+        parser.parse_program().map(Input::Declarations)
+    } else {
+        parser.parse_statements().map(Input::Statements)
+    };
+    input.map_err(|_| parser.error_reporter)
+}
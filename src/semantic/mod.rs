@@ -0,0 +1,24 @@
+mod resolver;
+mod typeck;
+
+pub use resolver::{DeclarationId, Resolver, Symbol, SymbolKind, SymbolTable};
+pub use typeck::{FunctionSignature, Type, TypeChecker};
+
+use crate::{error_emit::ErrorReporter, parser::Program};
+
+/// resolve the names then check the types of `program`, the types are only checked once the
+/// names are resolved. The warnings of the resolver come first, the error is the reporter of
+/// the failed pass
+pub fn check<'a>(
+    file_name: &'a str,
+    source_file: &'a str,
+    program: &Program,
+) -> (ErrorReporter<'a>, Result<(), ErrorReporter<'a>>) {
+    let mut resolver = Resolver::new(file_name, source_file);
+    if resolver.resolve_program(program).is_err() {
+        return (resolver.warning_reporter, Err(resolver.error_reporter));
+    }
+    let mut checker = TypeChecker::new(file_name, source_file);
+    let checked = checker.check_program(program).map_err(|_| checker.error_reporter);
+    (resolver.warning_reporter, checked)
+}
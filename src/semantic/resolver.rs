@@ -0,0 +1,632 @@
+use std::ops::Range;
+
+use fxhash::{FxHashMap, FxHashSet};
+use smol_str::SmolStr;
+
+use crate::{
+    error_emit::{ErrorReporter, FileId, FileSpan},
+    native::Natives,
+    parser::{ast::*, visitor::Visitor, Codespan},
+};
+
+pub type DeclarationId = usize;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SymbolKind {
+    Global,
+    Function,
+    // registered by the embedder, has no span in the source
+    Native,
+    // slots are counted from the start of the call frame, params take the first slots
+    Parameter { slot: usize },
+    Local { slot: usize },
+}
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub name: SmolStr,
+    pub kind: SymbolKind,
+    // a `const` variable can't be assigned
+    pub is_const: bool,
+    // span of the identifier in the declaration
+    pub start: usize,
+    pub end: usize,
+}
+
+/// the side table produced by `Resolver`, every declared or referenced identifier is keyed by
+/// the start of its span
+#[derive(Debug, Default)]
+pub struct SymbolTable {
+    symbols: Vec<Symbol>,
+    references: FxHashMap<usize, DeclarationId>,
+}
+
+impl SymbolTable {
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    pub fn symbol(&self, id: DeclarationId) -> &Symbol {
+        &self.symbols[id]
+    }
+
+    /// the declaration of the identifier starting at `start`
+    pub fn lookup(&self, start: usize) -> Option<DeclarationId> {
+        self.references.get(&start).copied()
+    }
+
+    pub fn resolve(&self, identifier: &Identifier) -> Option<&Symbol> {
+        self.lookup(identifier.start).map(|id| self.symbol(id))
+    }
+
+    fn declare_native(&mut self, name: SmolStr) -> DeclarationId {
+        let id = self.symbols.len();
+        self.symbols.push(Symbol {
+            name,
+            kind: SymbolKind::Native,
+            is_const: false,
+            start: 0,
+            end: 0,
+        });
+        id
+    }
+
+    fn declare(&mut self, identifier: &Identifier, kind: SymbolKind) -> DeclarationId {
+        let id = self.symbols.len();
+        self.symbols.push(Symbol {
+            name: identifier.value.clone(),
+            kind,
+            is_const: false,
+            start: identifier.start,
+            end: identifier.end,
+        });
+        self.references.insert(identifier.start, id);
+        id
+    }
+}
+
+pub struct Resolver<'a> {
+    scope_stack: Vec<FxHashMap<SmolStr, DeclarationId>>,
+    // the next free slot of the function being resolved
+    next_slot: usize,
+    // the dimension count of every const array, an array converted to a pointer or passed to a
+    // function could be modified through it
+    const_arrays: FxHashMap<DeclarationId, usize>,
+    // the value of every global `const int` initialized by a constant expression, they can be
+    // used as array sizes
+    constants: FxHashMap<DeclarationId, i32>,
+    has_error: bool,
+    // the same id in both reporters, the file is added first to each of them
+    file_id: FileId,
+    pub symbol_table: SymbolTable,
+    pub error_reporter: ErrorReporter<'a>,
+    pub warning_reporter: ErrorReporter<'a>,
+}
+
+impl<'a> Resolver<'a> {
+    /// `file_name` is the path printed in diagnostics
+    pub fn new(file_name: &'a str, source_file: &'a str) -> Resolver<'a> {
+        let mut error_reporter = ErrorReporter::new();
+        let file_id = error_reporter.add_file(file_name, source_file.to_string());
+        let mut warning_reporter = ErrorReporter::warning();
+        warning_reporter.add_file(file_name, source_file.to_string());
+        Self {
+            scope_stack: vec![FxHashMap::default()],
+            next_slot: 0,
+            const_arrays: FxHashMap::default(),
+            constants: FxHashMap::default(),
+            has_error: false,
+            file_id,
+            symbol_table: SymbolTable::default(),
+            error_reporter,
+            warning_reporter,
+        }
+    }
+
+    pub fn declare_natives(&mut self, natives: &Natives) {
+        for native in natives.iter() {
+            let id = self.symbol_table.declare_native(native.name().clone());
+            self.scope_stack[0].insert(native.name().clone(), id);
+        }
+    }
+
+    pub fn resolve_program(&mut self, program: &Program) -> Result<(), ()> {
+        self.visit_program(program);
+        if self.has_error {
+            Err(())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn report(&mut self, range: Range<usize>, message: String) {
+        self.has_error = true;
+        self.error_reporter
+            .add_diagnostic(FileSpan::new(self.file_id, range), message);
+    }
+
+    fn declare(&mut self, identifier: &Identifier, kind: SymbolKind) {
+        let range = identifier.start..identifier.end;
+        if self
+            .scope_stack
+            .last()
+            .unwrap()
+            .contains_key(&identifier.value)
+        {
+            self.report(
+                range,
+                format!("`{}` has already defined in this scope", identifier.value),
+            );
+            return;
+        }
+        let shadowed = self.scope_stack[1..]
+            .iter()
+            .any(|scope| scope.contains_key(&identifier.value));
+        if shadowed {
+            self.warning_reporter.add_diagnostic(
+                FileSpan::new(self.file_id, range),
+                format!("`{}` shadows a declaration in outer scope", identifier.value),
+            );
+        }
+        let id = self.symbol_table.declare(identifier, kind);
+        self.scope_stack
+            .last_mut()
+            .unwrap()
+            .insert(identifier.value.clone(), id);
+    }
+
+    fn reference(&mut self, identifier: &Identifier) {
+        let id = self
+            .scope_stack
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&identifier.value).copied());
+        match id {
+            Some(id) => {
+                self.symbol_table.references.insert(identifier.start, id);
+            }
+            None => self.report(
+                identifier.start..identifier.end,
+                format!("cannot find `{}` in this scope", identifier.value),
+            ),
+        }
+    }
+
+    fn declare_const(&mut self, node: &VarDeclaration) {
+        // nothing is declared when the name is already defined in this scope
+        if let Some(id) = self.symbol_table.lookup(node.id.start) {
+            self.symbol_table.symbols[id].is_const = true;
+            if !node.dimensions.is_empty() {
+                self.const_arrays.insert(id, node.dimensions.len());
+            }
+            let is_constant = self.scope_stack.len() == 1
+                && node.dimensions.is_empty()
+                && matches!(node.type_specifier.kind, TypeSpecifierKind::Int);
+            let value = node
+                .initializer
+                .as_ref()
+                .filter(|_| is_constant)
+                .and_then(|init| init.constant_value(&|name| self.constant(name)));
+            if let Some(value) = value {
+                self.constants.insert(id, value);
+            }
+        }
+    }
+
+    /// the value of the global `const int` named `name` in the current scope
+    fn constant(&self, name: &SmolStr) -> Option<i32> {
+        self.scope_stack
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .and_then(|id| self.constants.get(id).copied())
+    }
+
+    /// an array has at least one element
+    fn resolve_array_size(&mut self, size: &ArraySize) {
+        if let ArraySize::Constant(id) = size {
+            if self.constant(&id.value).is_none() {
+                self.report(
+                    id.start..id.end,
+                    format!(
+                        "`{}` isn't a global `const int`, array size should be a constant",
+                        id.value
+                    ),
+                );
+                return;
+            }
+            self.reference(id);
+        }
+        match size.value(&|name| self.constant(name)) {
+            Some(value) if value <= 0 => self.report(
+                size.start()..size.end(),
+                format!("array size should be positive, found `{}`", value),
+            ),
+            _ => {}
+        }
+    }
+
+    /// whether `var` is a const array or a row of one, which is converted to a pointer when it's
+    /// used as a value
+    fn is_const_array(&self, var: &Var) -> bool {
+        let dimensions = self
+            .symbol_table
+            .lookup(var.id.start)
+            .and_then(|id| self.const_arrays.get(&id).copied());
+        matches!(dimensions, Some(dimensions) if var.accessors.len() < dimensions)
+    }
+
+    fn is_const(&self, identifier: &Identifier) -> bool {
+        self.symbol_table
+            .resolve(identifier)
+            .map_or(false, |symbol| symbol.is_const)
+    }
+
+    /// an element or a field of a const variable can't be assigned either
+    fn check_assignable(&mut self, target: &Target) {
+        if let Target::Var(var) = target {
+            if self.is_const(&var.id) {
+                self.report(
+                    var.start..var.end,
+                    format!("can't assign to const `{}`", var.id.value),
+                );
+            }
+        }
+    }
+
+    fn begin_scope(&mut self) -> usize {
+        self.scope_stack.push(FxHashMap::default());
+        self.next_slot
+    }
+
+    /// slots of the locals in this scope are reused by the following scopes, like `Vm::end_scope`
+    fn end_scope(&mut self, slot: usize) {
+        self.scope_stack.pop();
+        self.next_slot = slot;
+    }
+
+    fn declare_local(&mut self, identifier: &Identifier) {
+        let slot = self.next_slot;
+        self.next_slot += 1;
+        self.declare(identifier, SymbolKind::Local { slot });
+    }
+}
+
+impl<'a> Visitor<()> for Resolver<'a> {
+    fn visit_program(&mut self, node: &Program) {
+        // functions can be called before they are declared, the prototypes and the definition
+        // of a function share one symbol
+        let mut defined = FxHashSet::default();
+        for decl in node.declarations.iter() {
+            let (id, is_definition) = match decl {
+                Declaration::FunctionDeclaration(func) => (&func.id, true),
+                Declaration::FunctionPrototype(prototype) => (&prototype.id, false),
+                _ => continue,
+            };
+            let declared = self.scope_stack[0].get(&id.value).copied().filter(|&symbol| {
+                self.symbol_table.symbol(symbol).kind == SymbolKind::Function
+            });
+            match declared {
+                Some(symbol) if !(is_definition && defined.contains(&id.value)) => {
+                    self.symbol_table.references.insert(id.start, symbol);
+                }
+                _ => self.declare(id, SymbolKind::Function),
+            }
+            if is_definition {
+                defined.insert(id.value.clone());
+            }
+        }
+        for decl in node.declarations.iter() {
+            self.visit_declaration(decl);
+        }
+    }
+
+    fn visit_function_declaration(&mut self, node: &FunctionDeclaration) {
+        self.next_slot = 0;
+        let slot = self.begin_scope();
+        self.visit_params(&node.params);
+        // params and the outermost locals share one scope, like the interpreter
+        for decl in node.body.local_declaration.iter() {
+            self.visit_var_declaration(decl);
+        }
+        for stmt in node.body.statement_list.iter() {
+            self.visit_statement(stmt);
+        }
+        self.end_scope(slot);
+    }
+
+    fn visit_function_prototype(&mut self, node: &FunctionPrototype) {
+        self.next_slot = 0;
+        let slot = self.begin_scope();
+        self.visit_params(&node.params);
+        self.end_scope(slot);
+    }
+
+    fn visit_var_declaration(&mut self, node: &VarDeclaration) {
+        for size in node.dimensions.iter() {
+            self.resolve_array_size(size);
+        }
+        // the initializer can't see the variable being declared
+        if let Some(ref initializer) = node.initializer {
+            self.visit_expression(initializer);
+        }
+        if let Some(ref initializer) = node.array_initializer {
+            for init in initializer {
+                self.visit_initializer(init);
+            }
+        }
+        if self.scope_stack.len() == 1 {
+            self.declare(&node.id, SymbolKind::Global);
+        } else {
+            self.declare_local(&node.id);
+        }
+        if node.is_const {
+            self.declare_const(node);
+        }
+    }
+
+    // struct names and fields aren't values, they are checked by the type checker, only the sizes
+    // of the array fields are resolved
+    fn visit_struct_declaration(&mut self, node: &StructDeclaration) {
+        for size in node.fields.iter().flat_map(|field| field.dimensions.iter()) {
+            self.resolve_array_size(size);
+        }
+    }
+
+    fn visit_initializer(&mut self, node: &Initializer) {
+        match node {
+            Initializer::Expression(expr) => self.visit_expression(expr),
+            Initializer::List(list) => {
+                for init in list.elements.iter() {
+                    self.visit_initializer(init);
+                }
+            }
+        }
+    }
+
+    fn visit_declaration(&mut self, node: &Declaration) {
+        match node {
+            Declaration::FunctionDeclaration(func) => self.visit_function_declaration(func),
+            Declaration::FunctionPrototype(prototype) => self.visit_function_prototype(prototype),
+            Declaration::VarDeclaration(var) => self.visit_var_declaration(var),
+            Declaration::StructDeclaration(decl) => self.visit_struct_declaration(decl),
+            Declaration::Error(error) => self.visit_error_node(error),
+        }
+    }
+
+    fn visit_identifier(&mut self, node: &Identifier) {
+        self.reference(node);
+    }
+
+    fn visit_number_literal(&mut self, _node: &NumberLiteral) {}
+
+    fn visit_boolean_literal(&mut self, _node: &BooleanLiteral) {}
+
+    fn visit_char_literal(&mut self, _node: &CharLiteral) {}
+
+    fn visit_string_literal(&mut self, _node: &StringLiteral) {}
+
+    fn visit_type_specifier(&mut self, _node: &TypeSpecifier) {}
+
+    fn visit_params(&mut self, node: &Params) {
+        if let Params::ParamsList { params } = node {
+            for param in params {
+                self.visit_parameter(param);
+            }
+        }
+    }
+
+    fn visit_parameter(&mut self, node: &Parameter) {
+        for size in node.dimensions.iter() {
+            self.resolve_array_size(size);
+        }
+        let slot = self.next_slot;
+        self.next_slot += 1;
+        self.declare(&node.id, SymbolKind::Parameter { slot });
+    }
+
+    fn visit_compound_statement(&mut self, node: &CompoundStatement) {
+        let slot = self.begin_scope();
+        for decl in node.local_declaration.iter() {
+            self.visit_var_declaration(decl);
+        }
+        for stmt in node.statement_list.iter() {
+            self.visit_statement(stmt);
+        }
+        self.end_scope(slot);
+    }
+
+    fn visit_statement(&mut self, node: &Statement) {
+        match node {
+            Statement::CompoundStatement(stmt) => self.visit_compound_statement(stmt),
+            Statement::ExpressionStatement(stmt) => self.visit_expression_statement(stmt),
+            Statement::SelectionStatement(stmt) => self.visit_selection_statement(stmt),
+            Statement::IterationStatement(stmt) => self.visit_iteration_statement(stmt),
+            Statement::ForStatement(stmt) => self.visit_for_statement(stmt),
+            Statement::DoWhileStatement(stmt) => self.visit_do_while_statement(stmt),
+            Statement::SwitchStatement(stmt) => self.visit_switch_statement(stmt),
+            Statement::BreakStatement(stmt) => self.visit_break_statement(stmt),
+            Statement::ContinueStatement(stmt) => self.visit_continue_statement(stmt),
+            Statement::ReturnStatement(stmt) => self.visit_return_statement(stmt),
+            Statement::Error(error) => self.visit_error_node(error),
+        }
+    }
+
+    fn visit_selection_statement(&mut self, node: &SelectionStatement) {
+        self.visit_expression(&node.test);
+        self.visit_statement(&node.consequent);
+        if let Some(ref alternative) = node.alternative {
+            self.visit_statement(alternative);
+        }
+    }
+
+    fn visit_iteration_statement(&mut self, node: &IterationStatement) {
+        self.visit_expression(&node.test);
+        self.visit_statement(&node.body);
+    }
+
+    fn visit_for_statement(&mut self, node: &ForStatement) {
+        for expr in [&node.init, &node.test, &node.update].iter().copied().flatten() {
+            self.visit_expression(expr);
+        }
+        self.visit_statement(&node.body);
+    }
+
+    fn visit_do_while_statement(&mut self, node: &DoWhileStatement) {
+        self.visit_statement(&node.body);
+        self.visit_expression(&node.test);
+    }
+
+    fn visit_switch_statement(&mut self, node: &SwitchStatement) {
+        self.visit_expression(&node.test);
+        for stmt in node.cases.iter().flat_map(|case| case.statements.iter()) {
+            self.visit_statement(stmt);
+        }
+    }
+
+    fn visit_break_statement(&mut self, _node: &BreakStatement) {}
+
+    fn visit_continue_statement(&mut self, _node: &ContinueStatement) {}
+
+    fn visit_return_statement(&mut self, node: &ReturnStatement) {
+        if let Some(ref expr) = node.expression {
+            self.visit_expression(expr);
+        }
+    }
+
+    fn visit_expression_statement(&mut self, node: &ExpressionStatement) {
+        if let Some(ref expr) = node.expression {
+            self.visit_expression(expr);
+        }
+    }
+
+    fn visit_expression(&mut self, node: &Expression) {
+        match node {
+            Expression::Assignment(expr) => self.visit_assignment_expression(expr),
+            Expression::BinaryExpression(expr) => self.visit_binary_expression(expr),
+            Expression::LogicExpression(expr) => self.visit_logic_expression(expr),
+            Expression::Conditional(expr) => self.visit_conditional_expression(expr),
+            Expression::UnaryExpression(expr) => self.visit_unary_expression(expr),
+            Expression::UpdateExpression(expr) => self.visit_update_expression(expr),
+            Expression::Address(expr) => self.visit_address_expression(expr),
+            Expression::Dereference(expr) => self.visit_dereference_expression(expr),
+            Expression::Factor(factor) => self.visit_factor(factor),
+        }
+    }
+
+    fn visit_assignment_expression(&mut self, node: &AssignmentExpression) {
+        self.visit_target(&node.lhs);
+        self.check_assignable(&node.lhs);
+        self.visit_expression(&node.rhs);
+    }
+
+    fn visit_var(&mut self, node: &Var) {
+        self.visit_identifier(&node.id);
+        // a member is looked up in the struct by the type checker
+        for accessor in node.accessors.iter() {
+            if let Accessor::Index(index) = accessor {
+                self.visit_expression(index);
+            }
+        }
+    }
+
+    fn visit_target(&mut self, node: &Target) {
+        match node {
+            Target::Var(var) => self.visit_var(var),
+            Target::Dereference(expr) => self.visit_dereference_expression(expr),
+        }
+    }
+
+    fn visit_address_expression(&mut self, node: &AddressExpression) {
+        self.visit_var(&node.var);
+        if self.is_const(&node.var.id) {
+            self.report(
+                node.start..node.end,
+                format!("can't take the address of const `{}`", node.var.id.value),
+            );
+        }
+    }
+
+    fn visit_dereference_expression(&mut self, node: &DereferenceExpression) {
+        self.visit_expression(&node.pointer);
+    }
+
+    fn visit_logic_expression(&mut self, node: &LogicExpression) {
+        self.visit_expression(&node.left);
+        self.visit_expression(&node.right);
+    }
+
+    fn visit_conditional_expression(&mut self, node: &ConditionalExpression) {
+        self.visit_expression(&node.test);
+        self.visit_expression(&node.consequent);
+        self.visit_expression(&node.alternative);
+    }
+
+    fn visit_unary_expression(&mut self, node: &UnaryExpression) {
+        self.visit_expression(&node.expression);
+    }
+
+    fn visit_update_expression(&mut self, node: &UpdateExpression) {
+        self.visit_target(&node.target);
+        self.check_assignable(&node.target);
+    }
+
+    fn visit_binary_expression(&mut self, node: &BinaryExpression) {
+        self.visit_expression(&node.left);
+        self.visit_expression(&node.right);
+    }
+
+    fn visit_operation(&mut self, _node: &Operation) {}
+
+    fn visit_factor(&mut self, node: &Factor) {
+        match node {
+            Factor::Expression(expr) => self.visit_expression(expr),
+            Factor::Var(var) => {
+                self.visit_var(var);
+                // there are no pointers to const, the elements could be written through it
+                if self.is_const_array(var) {
+                    self.report(
+                        var.start..var.end,
+                        format!("const array `{}` can't be converted to a pointer", var.id.value),
+                    );
+                }
+            }
+            Factor::CallExpression(call) => self.visit_call_expression(call),
+            Factor::NumberLiteral(num) => self.visit_number_literal(num),
+            Factor::BooleanLiteral(boolean) => self.visit_boolean_literal(boolean),
+            Factor::CharLiteral(char) => self.visit_char_literal(char),
+            Factor::StringLiteral(string) => self.visit_string_literal(string),
+        }
+    }
+
+    fn visit_call_expression(&mut self, node: &CallExpression) {
+        // `print`, `println` and `input` are built in, they have no declaration
+        if !matches!(node.id.value.as_str(), "print" | "println" | "input") {
+            self.visit_identifier(&node.id);
+        }
+        let is_print = matches!(node.id.value.as_str(), "print" | "println");
+        for arg in node.arguments.iter() {
+            let var = match arg {
+                Expression::Factor(Factor::Var(var)) => var,
+                _ => {
+                    self.visit_expression(arg);
+                    continue;
+                }
+            };
+            self.visit_var(var);
+            // a row of a multi-dimensional array is passed by reference as well, only printing
+            // doesn't modify an array
+            if !is_print && self.is_const_array(var) {
+                self.report(
+                    var.start..var.end,
+                    format!(
+                        "const array `{}` can't be passed to function `{}`",
+                        var.id.value, node.id.value
+                    ),
+                );
+            }
+        }
+    }
+
+    fn visit_error_node(&mut self, _node: &ErrorNode) {}
+}
@@ -0,0 +1,1065 @@
+use std::fmt::Display;
+use std::ops::Range;
+
+use fxhash::{FxHashMap, FxHashSet};
+use smol_str::SmolStr;
+
+use crate::{
+    error_emit::{ErrorReporter, FileId, FileSpan},
+    native::Natives,
+    parser::{ast::*, visitor::Visitor, Codespan},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Int,
+    Boolean,
+    Char,
+    Void,
+    // the element and the length, only the outermost dimension of a parameter is unsized
+    Array(Box<Type>, Option<usize>),
+    Struct(SmolStr),
+    Pointer(Box<Type>),
+    // the type of an expression which already has an error, never reported again
+    Unknown,
+}
+
+impl Type {
+    /// `dimensions` are the lengths of an array from the outermost, empty for a scalar
+    fn from_type_specifier(type_specifier: &TypeSpecifier, dimensions: &[Option<usize>]) -> Type {
+        let ty = Type::from_kind(&type_specifier.kind);
+        dimensions
+            .iter()
+            .rev()
+            .fold(ty, |ty, length| Type::Array(Box::new(ty), *length))
+    }
+
+    /// the constants named by the sizes are looked up by `constant`
+    fn from_declaration(
+        type_specifier: &TypeSpecifier,
+        dimensions: &[ArraySize],
+        constant: &dyn Fn(&SmolStr) -> Option<i32>,
+    ) -> Type {
+        let lengths = array_lengths(dimensions, constant)
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+        Type::from_type_specifier(type_specifier, &lengths)
+    }
+
+    fn from_kind(kind: &TypeSpecifierKind) -> Type {
+        match kind {
+            TypeSpecifierKind::Int => Type::Int,
+            TypeSpecifierKind::Boolean => Type::Boolean,
+            TypeSpecifierKind::Char => Type::Char,
+            TypeSpecifierKind::Void => Type::Void,
+            TypeSpecifierKind::Struct(name) => Type::Struct(name.clone()),
+            TypeSpecifierKind::Pointer(kind) => Type::Pointer(Box::new(Type::from_kind(kind))),
+        }
+    }
+
+    fn from_parameter(param: &Parameter, constant: &dyn Fn(&SmolStr) -> Option<i32>) -> Type {
+        let ty = Type::from_declaration(&param.type_specifier, &param.dimensions, constant);
+        if param.is_array {
+            Type::Array(Box::new(ty), None)
+        } else {
+            ty
+        }
+    }
+
+    fn is_unknown(&self) -> bool {
+        matches!(self, Type::Unknown)
+    }
+
+    /// an array is converted to the pointer to its first element when it's used as a pointer
+    fn decayed(self) -> Type {
+        match self {
+            Type::Array(element, _) => Type::Pointer(element),
+            ty => ty,
+        }
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Int => write!(f, "int"),
+            Type::Boolean => write!(f, "bool"),
+            Type::Char => write!(f, "char"),
+            Type::Void => write!(f, "void"),
+            Type::Array(..) => {
+                // `int[2][3]`, the lengths follow the type of the innermost element
+                let mut ty = self;
+                let mut lengths = String::new();
+                while let Type::Array(element, length) = ty {
+                    match length {
+                        Some(length) => lengths.push_str(&format!("[{}]", length)),
+                        None => lengths.push_str("[]"),
+                    }
+                    ty = element;
+                }
+                write!(f, "{}{}", ty, lengths)
+            }
+            Type::Struct(name) => write!(f, "struct {}", name),
+            Type::Pointer(element) => write!(f, "{}*", element),
+            Type::Unknown => write!(f, "{{unknown}}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionSignature {
+    pub params: Vec<Type>,
+    pub return_type: Type,
+}
+
+impl FunctionSignature {
+    pub fn new(params: Vec<Type>, return_type: Type) -> Self {
+        Self {
+            params,
+            return_type,
+        }
+    }
+
+    fn from_declaration(
+        type_specifier: &TypeSpecifier,
+        params: &Params,
+        constant: &dyn Fn(&SmolStr) -> Option<i32>,
+    ) -> Self {
+        let params = match params {
+            Params::Void { .. } => vec![],
+            Params::ParamsList { params } => params
+                .iter()
+                .map(|param| Type::from_parameter(param, constant))
+                .collect(),
+        };
+        Self::new(params, Type::from_type_specifier(type_specifier, &[]))
+    }
+}
+
+/// `int(int[], char)`
+impl Display for FunctionSignature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let params = self
+            .params
+            .iter()
+            .map(|param| param.to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+        write!(f, "{}({})", self.return_type, params)
+    }
+}
+
+pub struct TypeChecker<'a> {
+    scope_stack: Vec<FxHashMap<SmolStr, Type>>,
+    functions: FxHashMap<SmolStr, FunctionSignature>,
+    // the fields of every declared struct in declaration order
+    structs: FxHashMap<SmolStr, Vec<(SmolStr, Type)>>,
+    // the value of every global `const int` initialized by a constant expression, an array size
+    // can name one
+    constants: FxHashMap<SmolStr, i32>,
+    // return type of the function being checked
+    return_type: Type,
+    has_error: bool,
+    file_id: FileId,
+    pub error_reporter: ErrorReporter<'a>,
+}
+
+/// the lengths of the dimensions of an array, the resolver checks that every constant named by a
+/// size is known
+fn array_lengths(
+    dimensions: &[ArraySize],
+    constant: &dyn Fn(&SmolStr) -> Option<i32>,
+) -> Vec<usize> {
+    dimensions
+        .iter()
+        .map(|size| size.value(constant).unwrap_or(0) as usize)
+        .collect()
+}
+
+/// the source text of the arithmetic operations used by compound assignment and `++`/`--`
+fn operation_symbol(operation: &Operation) -> &'static str {
+    match operation {
+        Operation::PLUS(..) => "+",
+        Operation::MINUS(..) => "-",
+        Operation::MULTIPLY(..) => "*",
+        Operation::DIVIDE(..) => "/",
+        Operation::MOD(..) => "%",
+        _ => unreachable!(),
+    }
+}
+
+impl<'a> TypeChecker<'a> {
+    /// `file_name` is the path printed in diagnostics
+    pub fn new(file_name: &'a str, source_file: &'a str) -> TypeChecker<'a> {
+        let mut error_reporter = ErrorReporter::new();
+        let file_id = error_reporter.add_file(file_name, source_file.to_string());
+        Self {
+            scope_stack: vec![FxHashMap::default()],
+            functions: FxHashMap::default(),
+            structs: FxHashMap::default(),
+            constants: FxHashMap::default(),
+            return_type: Type::Void,
+            has_error: false,
+            file_id,
+            error_reporter,
+        }
+    }
+
+    /// native functions are checked like the declared ones, a declaration with the same name is
+    /// reported as a redefinition
+    pub fn declare_natives(&mut self, natives: &Natives) {
+        for native in natives.iter() {
+            self.functions
+                .insert(native.name().clone(), native.signature().clone());
+        }
+    }
+
+    pub fn check_program(&mut self, program: &Program) -> Result<(), ()> {
+        self.visit_program(program);
+        if self.has_error {
+            Err(())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn report(&mut self, range: Range<usize>, message: String) {
+        self.has_error = true;
+        self.error_reporter
+            .add_diagnostic(FileSpan::new(self.file_id, range), message);
+    }
+
+    /// report a mismatch unless either side already has an error
+    fn expect_type(&mut self, expected: &Type, actual: &Type, range: Range<usize>) {
+        if expected != actual && !expected.is_unknown() && !actual.is_unknown() {
+            self.report(
+                range,
+                format!("mismatched types, expected `{}`, found `{}`", expected, actual),
+            );
+        }
+    }
+
+    /// like `expect_type`, but an array can be assigned to a pointer to its elements and to an
+    /// array parameter whose outermost dimension is unsized
+    fn expect_assignable(&mut self, expected: &Type, actual: &Type, range: Range<usize>) {
+        match (expected, actual) {
+            (Type::Pointer(_), Type::Array(..)) => {
+                self.expect_type(expected, &actual.clone().decayed(), range)
+            }
+            (Type::Array(element, None), Type::Array(actual_element, Some(_)))
+                if element == actual_element => {}
+            _ => self.expect_type(expected, actual, range),
+        }
+    }
+
+    /// a pointer is the element of an array or an argument of an array parameter
+    fn check_array_element(&mut self, element_type: &Type, range: Range<usize>) {
+        if let Type::Pointer(_) = element_type {
+            self.report(range, format!("array of `{}` isn't supported", element_type));
+        }
+    }
+
+    /// like `Type::from_type_specifier`, a struct should be declared before it's used
+    fn resolve_type(&mut self, type_specifier: &TypeSpecifier) -> Type {
+        let mut kind = &type_specifier.kind;
+        while let TypeSpecifierKind::Pointer(pointee) = kind {
+            kind = pointee;
+        }
+        if let TypeSpecifierKind::Struct(name) = kind {
+            if !self.structs.contains_key(name) {
+                self.report(
+                    type_specifier.start..type_specifier.end,
+                    format!("cannot find struct `{}` in this scope", name),
+                );
+                return Type::Unknown;
+            }
+        }
+        Type::from_type_specifier(type_specifier, &[])
+    }
+
+    /// the parser has checked the nesting of the lists, every expression initializes an element
+    /// and a list has at most as many elements as the dimension it initializes, `lengths` are
+    /// the lengths of the dimensions from this one
+    fn check_initializer_list(
+        &mut self,
+        element_type: &Type,
+        lengths: &[usize],
+        elements: &[Initializer],
+    ) {
+        if let (Some(excess), Some(last)) = (elements.get(lengths[0]), elements.last()) {
+            let ty = lengths
+                .iter()
+                .rev()
+                .fold(element_type.clone(), |ty, length| {
+                    Type::Array(Box::new(ty), Some(*length))
+                });
+            self.report(
+                excess.start()..last.end(),
+                format!("too many initializers for `{}`", ty),
+            );
+        }
+        for initializer in elements.iter() {
+            match initializer {
+                Initializer::Expression(expr) => {
+                    let init_type = self.visit_expression(expr);
+                    self.expect_type(element_type, &init_type, expr.start()..expr.end());
+                }
+                Initializer::List(list) => {
+                    self.check_initializer_list(element_type, &lengths[1..], &list.elements)
+                }
+            }
+        }
+    }
+
+    fn member_type(&mut self, ty: &Type, member: &Identifier) -> Type {
+        let field = match ty {
+            Type::Struct(name) => self.structs.get(name).and_then(|fields| {
+                fields
+                    .iter()
+                    .find(|(field, _)| field == &member.value)
+                    .map(|(_, ty)| ty.clone())
+            }),
+            Type::Unknown => return Type::Unknown,
+            _ => None,
+        };
+        field.unwrap_or_else(|| {
+            self.report(
+                member.start..member.end,
+                format!("no field `{}` on type `{}`", member.value, ty),
+            );
+            Type::Unknown
+        })
+    }
+
+    fn lookup(&self, name: &SmolStr) -> Option<&Type> {
+        self.scope_stack
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+    }
+
+    fn define(&mut self, id: &Identifier, ty: Type) {
+        let scope = self.scope_stack.last_mut().unwrap();
+        if scope.contains_key(&id.value) {
+            self.report(
+                id.start..id.end,
+                format!("`{}` has already defined in this scope", id.value),
+            );
+        } else {
+            scope.insert(id.value.clone(), ty);
+        }
+    }
+
+    /// record the value of a global `const int` initialized by a constant expression
+    fn define_constant(&mut self, node: &VarDeclaration) {
+        if !node.is_const
+            || !node.dimensions.is_empty()
+            || !matches!(node.type_specifier.kind, TypeSpecifierKind::Int)
+        {
+            return;
+        }
+        let value = node
+            .initializer
+            .as_ref()
+            .and_then(|init| init.constant_value(&|name| self.constant(name)));
+        if let Some(value) = value {
+            self.constants.insert(node.id.value.clone(), value);
+        }
+    }
+
+    fn constant(&self, name: &SmolStr) -> Option<i32> {
+        self.constants.get(name).copied()
+    }
+
+    /// `p + 1`, `1 + p` and `p - 1` move a pointer, `p - q` is the distance between two pointers
+    fn check_additive_operands(&mut self, node: &BinaryExpression) -> Type {
+        let left_type = self.visit_expression(&node.left).decayed();
+        let right_type = self.visit_expression(&node.right).decayed();
+        let is_plus = matches!(node.operation, Operation::PLUS(..));
+        match (&left_type, &right_type) {
+            (Type::Pointer(_), Type::Pointer(_)) if !is_plus => {
+                self.expect_type(&left_type, &right_type, node.right.start()..node.right.end());
+                Type::Int
+            }
+            (Type::Pointer(_), _) => {
+                self.expect_type(&Type::Int, &right_type, node.right.start()..node.right.end());
+                left_type
+            }
+            (_, Type::Pointer(_)) if is_plus => {
+                self.expect_type(&Type::Int, &left_type, node.left.start()..node.left.end());
+                right_type
+            }
+            _ => {
+                self.expect_type(&Type::Int, &left_type, node.left.start()..node.left.end());
+                self.expect_type(&Type::Int, &right_type, node.right.start()..node.right.end());
+                Type::Int
+            }
+        }
+    }
+
+    fn check_binary_operands(&mut self, left: &Expression, right: &Expression, operand: &Type) {
+        let left_type = self.visit_expression(left);
+        self.expect_type(operand, &left_type, left.start()..left.end());
+        let right_type = self.visit_expression(right);
+        self.expect_type(operand, &right_type, right.start()..right.end());
+    }
+}
+
+impl<'a> Visitor<Type> for TypeChecker<'a> {
+    fn visit_program(&mut self, node: &Program) -> Type {
+        // functions can be called before they are declared, like the vm's function table, the
+        // natives are already defined
+        let mut defined: FxHashSet<SmolStr> = self.functions.keys().cloned().collect();
+        // the params of the signatures may have sizes naming constants
+        for decl in node.declarations.iter() {
+            if let Declaration::VarDeclaration(var) = decl {
+                self.define_constant(var);
+            }
+        }
+        let constants = self.constants.clone();
+        let constant = |name: &SmolStr| constants.get(name).copied();
+        for decl in node.declarations.iter() {
+            let (id, signature, is_definition) = match decl {
+                Declaration::FunctionDeclaration(func) => (
+                    &func.id,
+                    FunctionSignature::from_declaration(
+                        &func.type_specifier,
+                        &func.params,
+                        &constant,
+                    ),
+                    true,
+                ),
+                Declaration::FunctionPrototype(prototype) => (
+                    &prototype.id,
+                    FunctionSignature::from_declaration(
+                        &prototype.type_specifier,
+                        &prototype.params,
+                        &constant,
+                    ),
+                    false,
+                ),
+                _ => continue,
+            };
+            if is_definition && !defined.insert(id.value.clone()) {
+                self.report(
+                    id.start..id.end,
+                    format!("function `{}` has already defined", id.value),
+                );
+                continue;
+            }
+            match self.functions.get(&id.value) {
+                Some(declared) if *declared != signature => {
+                    let message = format!(
+                        "conflicting types for function `{}`, declared as `{}`, found `{}`",
+                        id.value, declared, signature
+                    );
+                    self.report(id.start..id.end, message);
+                }
+                Some(_) => {}
+                None => {
+                    self.functions.insert(id.value.clone(), signature);
+                }
+            }
+        }
+        // a function called through a prototype has nothing to run
+        let mut reported = FxHashSet::default();
+        for decl in node.declarations.iter() {
+            if let Declaration::FunctionPrototype(prototype) = decl {
+                let id = &prototype.id;
+                if !defined.contains(&id.value) && reported.insert(id.value.clone()) {
+                    self.report(
+                        id.start..id.end,
+                        format!("function `{}` is declared but never defined", id.value),
+                    );
+                }
+            }
+        }
+        for decl in node.declarations.iter() {
+            self.visit_declaration(decl);
+        }
+        Type::Void
+    }
+
+    fn visit_function_declaration(&mut self, node: &FunctionDeclaration) -> Type {
+        self.return_type = self.resolve_type(&node.type_specifier);
+        self.scope_stack.push(FxHashMap::default());
+        self.visit_params(&node.params);
+        // params and the outermost locals share one scope, like the interpreter
+        for decl in node.body.local_declaration.iter() {
+            self.visit_var_declaration(decl);
+        }
+        for stmt in node.body.statement_list.iter() {
+            self.visit_statement(stmt);
+        }
+        self.scope_stack.pop();
+        Type::Void
+    }
+
+    fn visit_function_prototype(&mut self, node: &FunctionPrototype) -> Type {
+        self.resolve_type(&node.type_specifier);
+        self.scope_stack.push(FxHashMap::default());
+        self.visit_params(&node.params);
+        self.scope_stack.pop();
+        Type::Void
+    }
+
+    fn visit_var_declaration(&mut self, node: &VarDeclaration) -> Type {
+        let element_type = self.resolve_type(&node.type_specifier);
+        if element_type == Type::Void {
+            self.report(
+                node.type_specifier.start..node.type_specifier.end,
+                format!("variable `{}` can't be declared as `void`", node.id.value),
+            );
+        }
+        if let Some(ref initializer) = node.initializer {
+            let init_type = self.visit_expression(initializer);
+            self.expect_assignable(
+                &element_type,
+                &init_type,
+                initializer.start()..initializer.end(),
+            );
+        }
+        if !node.dimensions.is_empty() {
+            self.check_array_element(&element_type, node.start..node.end);
+        }
+        if let Some(ref initializer) = node.array_initializer {
+            let lengths = array_lengths(&node.dimensions, &|name| self.constant(name));
+            self.check_initializer_list(&element_type, &lengths, initializer);
+        }
+        let ty = if element_type.is_unknown() {
+            Type::Unknown
+        } else {
+            Type::from_declaration(&node.type_specifier, &node.dimensions, &|name| {
+                self.constant(name)
+            })
+        };
+        self.define(&node.id, ty);
+        Type::Void
+    }
+
+    fn visit_struct_declaration(&mut self, node: &StructDeclaration) -> Type {
+        let mut fields: Vec<(SmolStr, Type)> = vec![];
+        for field in node.fields.iter() {
+            let element_type = self.resolve_type(&field.type_specifier);
+            if element_type == Type::Void {
+                self.report(
+                    field.type_specifier.start..field.type_specifier.end,
+                    format!("field `{}` can't be declared as `void`", field.id.value),
+                );
+            }
+            if !field.dimensions.is_empty() {
+                self.check_array_element(&element_type, field.start..field.end);
+            }
+            if fields.iter().any(|(name, _)| name == &field.id.value) {
+                self.report(
+                    field.id.start..field.id.end,
+                    format!(
+                        "field `{}` is already declared in struct `{}`",
+                        field.id.value, node.id.value
+                    ),
+                );
+                continue;
+            }
+            let ty = if element_type.is_unknown() {
+                Type::Unknown
+            } else {
+                Type::from_declaration(&field.type_specifier, &field.dimensions, &|name| {
+                    self.constant(name)
+                })
+            };
+            fields.push((field.id.value.clone(), ty));
+        }
+        if self.structs.insert(node.id.value.clone(), fields).is_some() {
+            self.report(
+                node.id.start..node.id.end,
+                format!("struct `{}` has already defined", node.id.value),
+            );
+        }
+        Type::Void
+    }
+
+    fn visit_initializer(&mut self, node: &Initializer) -> Type {
+        match node {
+            Initializer::Expression(expr) => self.visit_expression(expr),
+            Initializer::List(list) => {
+                for init in list.elements.iter() {
+                    self.visit_initializer(init);
+                }
+                Type::Void
+            }
+        }
+    }
+
+    fn visit_declaration(&mut self, node: &Declaration) -> Type {
+        match node {
+            Declaration::FunctionDeclaration(func) => self.visit_function_declaration(func),
+            Declaration::FunctionPrototype(prototype) => self.visit_function_prototype(prototype),
+            Declaration::VarDeclaration(var) => self.visit_var_declaration(var),
+            Declaration::StructDeclaration(decl) => self.visit_struct_declaration(decl),
+            Declaration::Error(error) => self.visit_error_node(error),
+        }
+    }
+
+    fn visit_identifier(&mut self, node: &Identifier) -> Type {
+        if let Some(ty) = self.lookup(&node.value) {
+            ty.clone()
+        } else {
+            self.report(
+                node.start..node.end,
+                format!("cannot find value `{}` in this scope", node.value),
+            );
+            Type::Unknown
+        }
+    }
+
+    fn visit_number_literal(&mut self, _node: &NumberLiteral) -> Type {
+        Type::Int
+    }
+
+    fn visit_boolean_literal(&mut self, _node: &BooleanLiteral) -> Type {
+        Type::Boolean
+    }
+
+    fn visit_char_literal(&mut self, _node: &CharLiteral) -> Type {
+        Type::Char
+    }
+
+    fn visit_string_literal(&mut self, node: &StringLiteral) -> Type {
+        // the characters and the terminating `'\0'`
+        Type::Array(Box::new(Type::Char), Some(node.value.chars().count() + 1))
+    }
+
+    fn visit_type_specifier(&mut self, node: &TypeSpecifier) -> Type {
+        Type::from_type_specifier(node, &[])
+    }
+
+    fn visit_params(&mut self, node: &Params) -> Type {
+        if let Params::ParamsList { params } = node {
+            for param in params {
+                self.visit_parameter(param);
+            }
+        }
+        Type::Void
+    }
+
+    fn visit_parameter(&mut self, node: &Parameter) -> Type {
+        let element_type = self.resolve_type(&node.type_specifier);
+        if node.is_array {
+            self.check_array_element(&element_type, node.start..node.end);
+        }
+        let ty = match element_type {
+            Type::Unknown => Type::Unknown,
+            _ => Type::from_parameter(node, &|name| self.constant(name)),
+        };
+        self.define(&node.id, ty.clone());
+        ty
+    }
+
+    fn visit_compound_statement(&mut self, node: &CompoundStatement) -> Type {
+        self.scope_stack.push(FxHashMap::default());
+        for decl in node.local_declaration.iter() {
+            self.visit_var_declaration(decl);
+        }
+        for stmt in node.statement_list.iter() {
+            self.visit_statement(stmt);
+        }
+        self.scope_stack.pop();
+        Type::Void
+    }
+
+    fn visit_statement(&mut self, node: &Statement) -> Type {
+        match node {
+            Statement::CompoundStatement(stmt) => self.visit_compound_statement(stmt),
+            Statement::ExpressionStatement(stmt) => self.visit_expression_statement(stmt),
+            Statement::SelectionStatement(stmt) => self.visit_selection_statement(stmt),
+            Statement::IterationStatement(stmt) => self.visit_iteration_statement(stmt),
+            Statement::ForStatement(stmt) => self.visit_for_statement(stmt),
+            Statement::DoWhileStatement(stmt) => self.visit_do_while_statement(stmt),
+            Statement::SwitchStatement(stmt) => self.visit_switch_statement(stmt),
+            Statement::BreakStatement(stmt) => self.visit_break_statement(stmt),
+            Statement::ContinueStatement(stmt) => self.visit_continue_statement(stmt),
+            Statement::ReturnStatement(stmt) => self.visit_return_statement(stmt),
+            Statement::Error(error) => self.visit_error_node(error),
+        }
+    }
+
+    fn visit_selection_statement(&mut self, node: &SelectionStatement) -> Type {
+        let test_type = self.visit_expression(&node.test);
+        self.expect_type(&Type::Boolean, &test_type, node.test.start()..node.test.end());
+        self.visit_statement(&node.consequent);
+        if let Some(ref alternative) = node.alternative {
+            self.visit_statement(alternative);
+        }
+        Type::Void
+    }
+
+    fn visit_iteration_statement(&mut self, node: &IterationStatement) -> Type {
+        let test_type = self.visit_expression(&node.test);
+        self.expect_type(&Type::Boolean, &test_type, node.test.start()..node.test.end());
+        self.visit_statement(&node.body);
+        Type::Void
+    }
+
+    fn visit_for_statement(&mut self, node: &ForStatement) -> Type {
+        if let Some(ref init) = node.init {
+            self.visit_expression(init);
+        }
+        if let Some(ref test) = node.test {
+            let test_type = self.visit_expression(test);
+            self.expect_type(&Type::Boolean, &test_type, test.start()..test.end());
+        }
+        if let Some(ref update) = node.update {
+            self.visit_expression(update);
+        }
+        self.visit_statement(&node.body);
+        Type::Void
+    }
+
+    fn visit_do_while_statement(&mut self, node: &DoWhileStatement) -> Type {
+        self.visit_statement(&node.body);
+        let test_type = self.visit_expression(&node.test);
+        self.expect_type(&Type::Boolean, &test_type, node.test.start()..node.test.end());
+        Type::Void
+    }
+
+    fn visit_switch_statement(&mut self, node: &SwitchStatement) -> Type {
+        let test_type = self.visit_expression(&node.test);
+        self.expect_type(&Type::Int, &test_type, node.test.start()..node.test.end());
+        for stmt in node.cases.iter().flat_map(|case| case.statements.iter()) {
+            self.visit_statement(stmt);
+        }
+        Type::Void
+    }
+
+    fn visit_break_statement(&mut self, _node: &BreakStatement) -> Type {
+        Type::Void
+    }
+
+    fn visit_continue_statement(&mut self, _node: &ContinueStatement) -> Type {
+        Type::Void
+    }
+
+    fn visit_return_statement(&mut self, node: &ReturnStatement) -> Type {
+        let return_type = self.return_type.clone();
+        match node.expression {
+            Some(ref expr) => {
+                let ty = self.visit_expression(expr);
+                if return_type == Type::Void {
+                    self.report(
+                        expr.start()..expr.end(),
+                        "function with `void` return type can't return a value".into(),
+                    );
+                } else {
+                    self.expect_assignable(&return_type, &ty, expr.start()..expr.end());
+                }
+            }
+            None => {
+                if return_type != Type::Void {
+                    self.report(
+                        node.start..node.end,
+                        format!("expected a return value of `{}`", return_type),
+                    );
+                }
+            }
+        }
+        Type::Void
+    }
+
+    fn visit_expression_statement(&mut self, node: &ExpressionStatement) -> Type {
+        if let Some(ref expr) = node.expression {
+            self.visit_expression(expr);
+        }
+        Type::Void
+    }
+
+    fn visit_expression(&mut self, node: &Expression) -> Type {
+        match node {
+            Expression::Assignment(expr) => self.visit_assignment_expression(expr),
+            Expression::BinaryExpression(expr) => self.visit_binary_expression(expr),
+            Expression::LogicExpression(expr) => self.visit_logic_expression(expr),
+            Expression::Conditional(expr) => self.visit_conditional_expression(expr),
+            Expression::UnaryExpression(expr) => self.visit_unary_expression(expr),
+            Expression::UpdateExpression(expr) => self.visit_update_expression(expr),
+            Expression::Address(expr) => self.visit_address_expression(expr),
+            Expression::Dereference(expr) => self.visit_dereference_expression(expr),
+            Expression::Factor(factor) => self.visit_factor(factor),
+        }
+    }
+
+    fn visit_assignment_expression(&mut self, node: &AssignmentExpression) -> Type {
+        let lhs_type = self.visit_target(&node.lhs);
+        if let Type::Array(..) = lhs_type {
+            let message = match node.lhs {
+                Target::Var(ref var) => format!("array `{}` can't be assigned", var.id.value),
+                Target::Dereference(_) => "array can't be assigned".into(),
+            };
+            self.report(node.lhs.start()..node.lhs.end(), message);
+        }
+        let rhs_type = self.visit_expression(&node.rhs);
+        if let Some(ref operation) = node.operation {
+            // `a += b` is checked like `a + b`, a pointer is only moved by `+=` and `-=`
+            let applicable = match lhs_type {
+                Type::Boolean | Type::Char | Type::Struct(_) => false,
+                Type::Pointer(_) => matches!(operation, Operation::PLUS(..) | Operation::MINUS(..)),
+                _ => true,
+            };
+            if !applicable {
+                self.report(
+                    operation.start()..operation.end(),
+                    format!(
+                        "`{}=` can't be applied to `{}`",
+                        operation_symbol(operation),
+                        lhs_type
+                    ),
+                );
+            }
+            self.expect_type(&Type::Int, &rhs_type, node.rhs.start()..node.rhs.end());
+        } else {
+            self.expect_assignable(&lhs_type, &rhs_type, node.rhs.start()..node.rhs.end());
+        }
+        lhs_type
+    }
+
+    fn visit_target(&mut self, node: &Target) -> Type {
+        match node {
+            Target::Var(var) => self.visit_var(var),
+            Target::Dereference(expr) => self.visit_dereference_expression(expr),
+        }
+    }
+
+    fn visit_address_expression(&mut self, node: &AddressExpression) -> Type {
+        if let Some(Accessor::Member(member)) = node.var.accessors.last() {
+            self.visit_var(&node.var);
+            self.report(
+                node.start..node.end,
+                format!("can't take the address of field `{}`", member.value),
+            );
+            return Type::Unknown;
+        }
+        match self.visit_var(&node.var) {
+            Type::Unknown => Type::Unknown,
+            ty => Type::Pointer(Box::new(ty)),
+        }
+    }
+
+    fn visit_dereference_expression(&mut self, node: &DereferenceExpression) -> Type {
+        match self.visit_expression(&node.pointer).decayed() {
+            Type::Pointer(element) => *element,
+            Type::Unknown => Type::Unknown,
+            ty => {
+                self.report(
+                    node.start..node.end,
+                    format!("type `{}` can't be dereferenced", ty),
+                );
+                Type::Unknown
+            }
+        }
+    }
+
+    fn visit_var(&mut self, node: &Var) -> Type {
+        let mut ty = self.visit_identifier(&node.id);
+        // every accessor selects an element or a member of the value on its left
+        for accessor in node.accessors.iter() {
+            ty = match accessor {
+                Accessor::Index(index) => {
+                    let index_type = self.visit_expression(index);
+                    self.expect_type(&Type::Int, &index_type, index.start()..index.end());
+                    match ty {
+                        Type::Array(element, _) => *element,
+                        Type::Unknown => Type::Unknown,
+                        ty => {
+                            self.report(
+                                node.start..node.end,
+                                format!("cannot index into a value of type `{}`", ty),
+                            );
+                            Type::Unknown
+                        }
+                    }
+                }
+                Accessor::Member(member) => self.member_type(&ty, member),
+            };
+        }
+        ty
+    }
+
+    fn visit_logic_expression(&mut self, node: &LogicExpression) -> Type {
+        self.check_binary_operands(&node.left, &node.right, &Type::Boolean);
+        Type::Boolean
+    }
+
+    /// the arms should have the same type, an array arm decays when the other one is a pointer
+    fn visit_conditional_expression(&mut self, node: &ConditionalExpression) -> Type {
+        let test_type = self.visit_expression(&node.test);
+        self.expect_type(&Type::Boolean, &test_type, node.test.start()..node.test.end());
+        let consequent = self.visit_expression(&node.consequent);
+        let alternative = self.visit_expression(&node.alternative);
+        match (consequent, alternative) {
+            (Type::Unknown, ty) | (ty, Type::Unknown) => ty,
+            (consequent, alternative) if consequent == alternative => consequent,
+            (consequent, alternative)
+                if consequent.clone().decayed() == alternative.clone().decayed() =>
+            {
+                consequent.decayed()
+            }
+            (consequent, alternative) => {
+                self.report(
+                    node.consequent.start()..node.alternative.end(),
+                    format!(
+                        "`?:` arms have mismatched types `{}` and `{}`",
+                        consequent, alternative
+                    ),
+                );
+                Type::Unknown
+            }
+        }
+    }
+
+    fn visit_unary_expression(&mut self, node: &UnaryExpression) -> Type {
+        let operand = match node.operation {
+            Operation::NOT(..) => Type::Boolean,
+            _ => Type::Int,
+        };
+        let ty = self.visit_expression(&node.expression);
+        self.expect_type(
+            &operand,
+            &ty,
+            node.expression.start()..node.expression.end(),
+        );
+        operand
+    }
+
+    fn visit_update_expression(&mut self, node: &UpdateExpression) -> Type {
+        let ty = self.visit_target(&node.target);
+        match ty {
+            Type::Pointer(_) => return ty,
+            Type::Int | Type::Unknown => {}
+            _ => {
+                let symbol = operation_symbol(&node.operation);
+                self.report(
+                    node.start..node.end,
+                    format!("`{}{}` can't be applied to `{}`", symbol, symbol, ty),
+                );
+            }
+        }
+        Type::Int
+    }
+
+    fn visit_binary_expression(&mut self, node: &BinaryExpression) -> Type {
+        match node.operation {
+            Operation::EQ(..) | Operation::NE(..) => {
+                let left_type = self.visit_expression(&node.left);
+                let right_type = self.visit_expression(&node.right);
+                if let Type::Array(..) | Type::Struct(_) | Type::Void = left_type {
+                    self.report(
+                        node.left.start()..node.left.end(),
+                        format!("`{}` can't be compared", left_type),
+                    );
+                } else {
+                    self.expect_assignable(
+                        &left_type,
+                        &right_type,
+                        node.right.start()..node.right.end(),
+                    );
+                }
+                Type::Boolean
+            }
+            Operation::GT(..) | Operation::LT(..) | Operation::GE(..) | Operation::LE(..) => {
+                // chars are ordered like ints, but the two kinds can't be mixed
+                let left_type = self.visit_expression(&node.left);
+                let operand = if let Type::Char | Type::Pointer(_) = left_type {
+                    left_type
+                } else {
+                    self.expect_type(&Type::Int, &left_type, node.left.start()..node.left.end());
+                    Type::Int
+                };
+                let right_type = self.visit_expression(&node.right);
+                self.expect_assignable(&operand, &right_type, node.right.start()..node.right.end());
+                Type::Boolean
+            }
+            Operation::PLUS(..) | Operation::MINUS(..) => self.check_additive_operands(node),
+            _ => {
+                self.check_binary_operands(&node.left, &node.right, &Type::Int);
+                Type::Int
+            }
+        }
+    }
+
+    fn visit_operation(&mut self, _node: &Operation) -> Type {
+        Type::Unknown
+    }
+
+    fn visit_factor(&mut self, node: &Factor) -> Type {
+        match node {
+            Factor::Expression(expr) => self.visit_expression(expr),
+            Factor::Var(var) => self.visit_var(var),
+            Factor::CallExpression(call) => self.visit_call_expression(call),
+            Factor::NumberLiteral(num) => self.visit_number_literal(num),
+            Factor::BooleanLiteral(boolean) => self.visit_boolean_literal(boolean),
+            Factor::CharLiteral(char) => self.visit_char_literal(char),
+            Factor::StringLiteral(string) => self.visit_string_literal(string),
+        }
+    }
+
+    fn visit_call_expression(&mut self, node: &CallExpression) -> Type {
+        if node.id.value == "print" || node.id.value == "println" {
+            for arg in node.arguments.iter() {
+                let ty = self.visit_expression(arg);
+                if let Type::Pointer(_) = ty {
+                    self.report(
+                        arg.start()..arg.end(),
+                        format!("`{}` can't be printed", ty),
+                    );
+                }
+            }
+            return Type::Void;
+        }
+        if node.id.value == "input" {
+            if !node.arguments.is_empty() {
+                self.report(
+                    node.start..node.end,
+                    format!(
+                        "function `input` takes 0 arguments but {} were supplied",
+                        node.arguments.len()
+                    ),
+                );
+            }
+            return Type::Int;
+        }
+        let signature = match self.functions.get(&node.id.value) {
+            Some(signature) => signature.clone(),
+            None => {
+                for arg in node.arguments.iter() {
+                    self.visit_expression(arg);
+                }
+                self.report(
+                    node.id.start..node.id.end,
+                    format!("cannot find function `{}`", node.id.value),
+                );
+                return Type::Unknown;
+            }
+        };
+        if signature.params.len() != node.arguments.len() {
+            self.report(
+                node.start..node.end,
+                format!(
+                    "function `{}` takes {} arguments but {} were supplied",
+                    node.id.value,
+                    signature.params.len(),
+                    node.arguments.len()
+                ),
+            );
+        }
+        for (i, arg) in node.arguments.iter().enumerate() {
+            let ty = self.visit_expression(arg);
+            if let Some(param) = signature.params.get(i) {
+                self.expect_assignable(param, &ty, arg.start()..arg.end());
+            }
+        }
+        signature.return_type
+    }
+
+    fn visit_error_node(&mut self, _node: &ErrorNode) -> Type {
+        Type::Unknown
+    }
+}
@@ -18,6 +18,19 @@ impl EmitOperationCode for Program {
         } else {
             return Err(RuntimeError("last declaration should be function".to_string()).into());
         }
+        // declare every function ahead, so a function can call the one declared after it
+        for decl in self.declarations.iter() {
+            if let Declaration::FunctionDeclaration(func) = decl {
+                let params = match func.params {
+                    Params::Void { .. } => 0,
+                    Params::ParamsList { ref params } => params.len(),
+                };
+                let index = vm.declare_function(func.id.value.clone(), params)?;
+                if &func.id.value == "main" {
+                    vm.set_entry(index);
+                }
+            }
+        }
         for decl in self.declarations.iter_mut() {
             decl.emit(vm)?;
         }
@@ -29,12 +42,15 @@ impl EmitOperationCode for Declaration {
     fn emit(&mut self, vm: &mut Vm) -> anyhow::Result<()> {
         match self {
             Declaration::FunctionDeclaration(decl) => {
-                // TODO: params
-                decl.body.emit(vm)?;
+                decl.emit(vm)?;
             }
+            // a call is resolved to the definition in the function table
+            Declaration::FunctionPrototype(_) => {}
             Declaration::VarDeclaration(var_decl) => {
                 var_decl.emit(vm)?;
             }
+            Declaration::StructDeclaration(decl) => vm.declare_struct(decl)?,
+            Declaration::Error(_) => unreachable!("syntax errors are reported by the parser"),
         }
         Ok(())
     }
@@ -43,12 +59,37 @@ impl EmitOperationCode for VarDeclaration {
     fn emit(&mut self, vm: &mut Vm) -> anyhow::Result<()> {
         let name = &self.id.value;
         let (start, end) = (self.start, self.end);
-        if let Some(ref mut init) = self.initializer {
+        // the initializer can't see the variable being declared
+        let constant = match (self.is_const, &self.type_specifier.kind, &self.initializer) {
+            (true, TypeSpecifierKind::Int, Some(init)) if self.dimensions.is_empty() => {
+                init.constant_value(&|name| vm.resolve_constant(name))
+            }
+            _ => None,
+        };
+        if !self.dimensions.is_empty() {
+            emit_array(
+                &self.type_specifier,
+                &self.dimensions,
+                self.array_initializer.as_deref_mut(),
+                vm,
+                start..end,
+            )?;
+        } else if let Some(value) = constant {
+            let init = self.initializer.as_ref().unwrap();
+            vm.add_instruction(ConstantI32(value), init.start()..init.end());
+        } else if let Some(ref mut init) = self.initializer {
             init.emit(vm)?;
+            // the variable has its own copy of the struct
+            if let TypeSpecifierKind::Struct(_) = self.type_specifier.kind {
+                vm.add_instruction(Copy, start..end);
+            }
         } else {
-            vm.add_instruction(Nil, start..end);
+            emit_default_value(&self.type_specifier, vm)?;
         }
         vm.define_variable(name.clone(), start..end)?;
+        if let Some(value) = constant {
+            vm.define_constant(name.clone(), value);
+        }
         if vm.scope_depth() == 0 {
             vm.add_instruction(Pop, 10000000..10000000);
         }
@@ -56,6 +97,86 @@ impl EmitOperationCode for VarDeclaration {
     }
 }
 
+/// a row of a multi-dimensional array is created for every index, rather than filled with
+/// one row, so the rows don't share elements
+fn emit_array(
+    type_specifier: &TypeSpecifier,
+    dimensions: &[ArraySize],
+    initializer: Option<&mut [Initializer]>,
+    vm: &mut Vm,
+    range: std::ops::Range<usize>,
+) -> anyhow::Result<()> {
+    let length = match &dimensions[0] {
+        ArraySize::Number(num) => num.value,
+        ArraySize::Constant(id) => vm
+            .global_constant(&id.value)
+            .ok_or_else(|| RuntimeError(format!("{} isn't a global const int", id.value)))?,
+    } as usize;
+    if dimensions.len() == 1 {
+        emit_default_value(type_specifier, vm)?;
+        let mut count = 0;
+        if let Some(initializer) = initializer {
+            for init in initializer.iter_mut() {
+                match init {
+                    Initializer::Expression(expr) => expr.emit(vm)?,
+                    Initializer::List(_) => unreachable!("elements are initialized by expressions"),
+                }
+            }
+            count = initializer.len();
+        }
+        vm.add_instruction(NewArray(length, count), range);
+        return Ok(());
+    }
+    // the fill value is never used, since every row is pushed
+    vm.add_instruction(Nil, range.clone());
+    let mut rows = initializer.map(|init| init.iter_mut()).into_iter().flatten();
+    for _ in 0..length {
+        let row_initializer = match rows.next() {
+            Some(Initializer::List(list)) => Some(&mut list.elements[..]),
+            Some(Initializer::Expression(_)) => unreachable!("rows are initialized by lists"),
+            None => None,
+        };
+        emit_array(
+            type_specifier,
+            &dimensions[1..],
+            row_initializer,
+            vm,
+            range.clone(),
+        )?;
+    }
+    vm.add_instruction(NewArray(length, length), range);
+    Ok(())
+}
+
+/// uninitialized variables and array elements have the same default value as the interpreter
+fn emit_default_value(type_specifier: &TypeSpecifier, vm: &mut Vm) -> anyhow::Result<()> {
+    let range = type_specifier.start..type_specifier.end;
+    match type_specifier.kind {
+        TypeSpecifierKind::Int => vm.add_instruction(ConstantI32(0), range),
+        TypeSpecifierKind::Boolean => vm.add_instruction(ConstantBoolean(false), range),
+        TypeSpecifierKind::Char => vm.add_instruction(ConstantChar('\0'), range),
+        TypeSpecifierKind::Void => vm.add_instruction(Nil, range),
+        TypeSpecifierKind::Pointer(_) => vm.add_instruction(NullPointer, range),
+        // every field has its default value
+        TypeSpecifierKind::Struct(ref name) => {
+            let decl = vm
+                .resolve_struct(name)
+                .ok_or_else(|| RuntimeError(format!("struct {} is not defined", name)))?;
+            for field in decl.fields.iter() {
+                if field.dimensions.is_empty() {
+                    emit_default_value(&field.type_specifier, vm)?;
+                } else {
+                    let range = field.start..field.end;
+                    emit_array(&field.type_specifier, &field.dimensions, None, vm, range)?;
+                }
+            }
+            let names = decl.fields.iter().map(|field| field.id.value.clone()).collect();
+            vm.add_instruction(NewStruct(names), range);
+        }
+    }
+    Ok(())
+}
+
 impl EmitOperationCode for Statement {
     fn emit(&mut self, vm: &mut Vm) -> anyhow::Result<()> {
         use Statement::*;
@@ -72,20 +193,169 @@ impl EmitOperationCode for Statement {
             IterationStatement(stmt) => {
                 stmt.emit(vm)?;
             }
-            ReturnStatement(_) => todo!(),
+            ForStatement(stmt) => {
+                stmt.emit(vm)?;
+            }
+            DoWhileStatement(stmt) => {
+                stmt.emit(vm)?;
+            }
+            SwitchStatement(stmt) => {
+                stmt.emit(vm)?;
+            }
+            BreakStatement(stmt) => {
+                vm.emit_break(stmt.start..stmt.end)?;
+            }
+            ContinueStatement(stmt) => {
+                vm.emit_continue(stmt.start..stmt.end)?;
+            }
+            ReturnStatement(stmt) => {
+                stmt.emit(vm)?;
+            }
+            Error(_) => unreachable!("syntax errors are reported by the parser"),
         }
         Ok(())
     }
 }
+impl EmitOperationCode for ReturnStatement {
+    fn emit(&mut self, vm: &mut Vm) -> anyhow::Result<()> {
+        if let Some(ref mut expr) = self.expression {
+            expr.emit(vm)?;
+        } else {
+            vm.add_instruction(Nil, self.start..self.end);
+        }
+        vm.add_instruction(Return, self.start..self.end);
+        Ok(())
+    }
+}
 impl EmitOperationCode for IterationStatement {
     fn emit(&mut self, vm: &mut Vm) -> anyhow::Result<()> {
         let loop_start = vm.operations().len();
         self.test.emit(vm)?;
         let exit_jump = vm.emit_jump(JumpIfFalse(0), self.start..self.end);
         vm.add_instruction(Pop, self.test.start()..self.test.end());
+        vm.begin_loop(Some(loop_start));
+        self.body.emit(vm)?;
+        vm.emit_loop(loop_start, self.body.start()..self.body.end());
+        vm.patch_jump(exit_jump)?;
+        vm.add_instruction(Pop, self.test.start()..self.test.end());
+        // `break` skips the pop of the test value, it is already popped in the body
+        vm.end_loop()?;
+        Ok(())
+    }
+}
+
+impl EmitOperationCode for ForStatement {
+    fn emit(&mut self, vm: &mut Vm) -> anyhow::Result<()> {
+        if let Some(ref mut init) = self.init {
+            init.emit(vm)?;
+            vm.add_instruction(Pop, init.start()..init.end());
+        }
+        let loop_start = vm.operations().len();
+        let mut exit_jump = None;
+        if let Some(ref mut test) = self.test {
+            test.emit(vm)?;
+            exit_jump = Some(vm.emit_jump(JumpIfFalse(0), self.start..self.end));
+            vm.add_instruction(Pop, test.start()..test.end());
+        }
+        vm.begin_loop(None);
+        self.body.emit(vm)?;
+        vm.patch_continue_jumps()?;
+        if let Some(ref mut update) = self.update {
+            update.emit(vm)?;
+            vm.add_instruction(Pop, update.start()..update.end());
+        }
+        vm.emit_loop(loop_start, self.body.start()..self.body.end());
+        if let (Some(exit_jump), Some(test)) = (exit_jump, &self.test) {
+            vm.patch_jump(exit_jump)?;
+            vm.add_instruction(Pop, test.start()..test.end());
+        }
+        vm.end_loop()?;
+        Ok(())
+    }
+}
+
+impl EmitOperationCode for DoWhileStatement {
+    fn emit(&mut self, vm: &mut Vm) -> anyhow::Result<()> {
+        let loop_start = vm.operations().len();
+        vm.begin_loop(None);
         self.body.emit(vm)?;
+        vm.patch_continue_jumps()?;
+        self.test.emit(vm)?;
+        let exit_jump = vm.emit_jump(JumpIfFalse(0), self.start..self.end);
+        vm.add_instruction(Pop, self.test.start()..self.test.end());
         vm.emit_loop(loop_start, self.body.start()..self.body.end());
         vm.patch_jump(exit_jump)?;
+        vm.add_instruction(Pop, self.test.start()..self.test.end());
+        vm.end_loop()?;
+        Ok(())
+    }
+}
+
+/// a `JumpTable` is used when the labels are dense, otherwise the value is compared with each
+/// label in order
+impl EmitOperationCode for SwitchStatement {
+    fn emit(&mut self, vm: &mut Vm) -> anyhow::Result<()> {
+        let range = self.test.start()..self.test.end();
+        self.test.emit(vm)?;
+        let labels: Vec<(usize, i32)> = self
+            .cases
+            .iter()
+            .enumerate()
+            .filter_map(|(case, item)| item.label.as_ref().map(|label| (case, label.value)))
+            .collect();
+        let low = labels.iter().map(|(_, value)| *value).min().unwrap_or(0);
+        let high = labels.iter().map(|(_, value)| *value).max().unwrap_or(0);
+        let table_len = high as i64 - low as i64 + 1;
+        let table = if labels.len() >= 3 && table_len <= 2 * labels.len() as i64 {
+            Some(vm.emit_jump(JumpTable(low, vec![], 0), range.clone()))
+        } else {
+            None
+        };
+        let mut case_jumps = vec![];
+        let mut default_jump = None;
+        if table.is_none() {
+            for &(case, value) in labels.iter() {
+                vm.add_instruction(Duplicate(1), range.clone());
+                vm.add_instruction(ConstantI32(value), range.clone());
+                vm.add_instruction(Equal, range.clone());
+                let next_jump = vm.emit_jump(JumpIfFalse(0), range.clone());
+                vm.add_instruction(Pop, range.clone());
+                vm.add_instruction(Pop, range.clone());
+                case_jumps.push((case, vm.emit_jump(Jump(0), range.clone())));
+                vm.patch_jump(next_jump)?;
+                vm.add_instruction(Pop, range.clone());
+            }
+            vm.add_instruction(Pop, range.clone());
+            default_jump = Some(vm.emit_jump(Jump(0), range));
+        }
+        vm.begin_switch();
+        let mut case_starts = vec![];
+        for case in self.cases.iter_mut() {
+            case_starts.push(vm.operations().len());
+            for stmt in case.statements.iter_mut() {
+                stmt.emit(vm)?;
+            }
+        }
+        let default_target = self
+            .cases
+            .iter()
+            .position(|case| case.label.is_none())
+            .map_or(vm.operations().len(), |case| case_starts[case]);
+        if let Some(index) = table {
+            let mut targets = vec![default_target; table_len as usize];
+            // the first of the duplicate labels wins, same as the comparisons
+            for &(case, value) in labels.iter().rev() {
+                targets[(value as i64 - low as i64) as usize] = case_starts[case];
+            }
+            vm.patch_jump_table(index, &targets, default_target)?;
+        }
+        for (case, index) in case_jumps {
+            vm.patch_jump_to(index, case_starts[case])?;
+        }
+        if let Some(index) = default_jump {
+            vm.patch_jump_to(index, default_target)?;
+        }
+        vm.end_loop()?;
         Ok(())
     }
 }
@@ -94,6 +364,7 @@ impl EmitOperationCode for ExpressionStatement {
     fn emit(&mut self, vm: &mut Vm) -> anyhow::Result<()> {
         if let Some(ref mut expr) = self.expression {
             expr.emit(vm)?;
+            vm.add_instruction(Pop, self.start..self.end);
         }
         Ok(())
     }
@@ -131,7 +402,22 @@ impl EmitOperationCode for CompoundStatement {
 
 impl EmitOperationCode for FunctionDeclaration {
     fn emit(&mut self, vm: &mut Vm) -> anyhow::Result<()> {
-        // TODO:
+        let index = vm.resolve_function(&self.id.value).ok_or_else(|| {
+            RuntimeError(format!("function {} should be declared", self.id.value))
+        })?;
+        vm.begin_function(index);
+        // params take the first slots of the call frame, in the order of arguments
+        vm.begin_scope();
+        if let Params::ParamsList { ref params } = self.params {
+            for param in params {
+                vm.define_variable(param.id.value.clone(), param.start..param.end)?;
+            }
+        }
+        self.body.emit(vm)?;
+        // a function without return statement at the end returns nothing
+        vm.add_instruction(Nil, self.end..self.end);
+        vm.add_instruction(Return, self.end..self.end);
+        vm.end_function();
         Ok(())
     }
 }
@@ -139,14 +425,38 @@ impl EmitOperationCode for FunctionDeclaration {
 impl EmitOperationCode for Expression {
     fn emit(&mut self, vm: &mut Vm) -> anyhow::Result<()> {
         match self {
+            // a compound assignment reads the target before the rhs, an element is read with
+            // a copy of the array and the index, so the index is evaluated once
             Expression::Assignment(assign) => {
+                let lhs = &mut assign.lhs;
+                let is_place = emit_target(lhs, vm)?;
+                if assign.operation.is_some() {
+                    emit_duplicate_get(lhs, is_place, vm);
+                }
                 assign.rhs.emit(vm)?;
-                let lhs = &assign.lhs;
-                if let Some(index) = vm.resolve_local(&lhs.id.value) {
-                    vm.add_instruction(SetLocal(index), lhs.id.start..lhs.id.end);
-                } else {
-                    unreachable!();
-                    // vm.add_operation(GetGlobal(var.id.value.clone()), var.start..var.end);
+                if let Some(ref operation) = assign.operation {
+                    emit_arithmetic(operation, vm);
+                }
+                emit_set_target(lhs, is_place, vm);
+            }
+            Expression::UpdateExpression(update) => {
+                let target = &mut update.target;
+                let range = update.operation.start()..update.operation.end();
+                let (apply, revert) = match update.operation {
+                    Operation::PLUS(..) => (AddI32, SubtractI32),
+                    Operation::MINUS(..) => (SubtractI32, AddI32),
+                    _ => unreachable!(),
+                };
+                let is_place = emit_target(target, vm)?;
+                emit_duplicate_get(target, is_place, vm);
+                vm.add_instruction(ConstantI32(1), range.clone());
+                vm.add_instruction(apply, range.clone());
+                emit_set_target(target, is_place, vm);
+                if !update.prefix {
+                    // the updated value is reverted to the previous one, which is exact since
+                    // the arithmetic wraps
+                    vm.add_instruction(ConstantI32(1), range.clone());
+                    vm.add_instruction(revert, range);
                 }
             }
             Expression::BinaryExpression(expr) => {
@@ -183,24 +493,64 @@ impl EmitOperationCode for Expression {
                     Operation::DIVIDE(s, e) => {
                         vm.add_instruction(DivideI32, s..e);
                     }
+                    Operation::MOD(s, e) => {
+                        vm.add_instruction(ModuloI32, s..e);
+                    }
+                    Operation::BITAND(s, e) => {
+                        vm.add_instruction(BitAndI32, s..e);
+                    }
+                    Operation::BITOR(s, e) => {
+                        vm.add_instruction(BitOrI32, s..e);
+                    }
+                    Operation::BITXOR(s, e) => {
+                        vm.add_instruction(BitXorI32, s..e);
+                    }
+                    Operation::SHL(s, e) => {
+                        vm.add_instruction(ShiftLeftI32, s..e);
+                    }
+                    Operation::SHR(s, e) => {
+                        vm.add_instruction(ShiftRightI32, s..e);
+                    }
                     _ => unreachable!(),
                 }
             }
+            // the right operand is skipped when the left one decides the result, which is
+            // left on the stack
             Expression::LogicExpression(expr) => {
                 expr.left.emit(vm)?;
-                expr.right.emit(vm)?;
                 match expr.operation {
                     Operation::AND(s, e) => {
-                        vm.add_instruction(And, s..e);
+                        let end_jump = vm.emit_jump(JumpIfFalse(0), s..e);
+                        vm.add_instruction(Pop, s..e);
+                        expr.right.emit(vm)?;
+                        vm.patch_jump(end_jump)?;
                     }
                     Operation::OR(s, e) => {
-                        vm.add_instruction(Or, s..e);
+                        let else_jump = vm.emit_jump(JumpIfFalse(0), s..e);
+                        let end_jump = vm.emit_jump(Jump(0), s..e);
+                        vm.patch_jump(else_jump)?;
+                        vm.add_instruction(Pop, s..e);
+                        expr.right.emit(vm)?;
+                        vm.patch_else_jump(end_jump)?;
                     }
                     _ => {
                         unreachable!();
                     }
                 }
             }
+            // the test is popped in both arms, only one of them is evaluated
+            Expression::Conditional(expr) => {
+                let range = expr.start..expr.end;
+                expr.test.emit(vm)?;
+                let else_jump = vm.emit_jump(JumpIfFalse(0), range.clone());
+                vm.add_instruction(Pop, range.clone());
+                expr.consequent.emit(vm)?;
+                let end_jump = vm.emit_jump(Jump(0), range.clone());
+                vm.patch_jump(else_jump)?;
+                vm.add_instruction(Pop, range);
+                expr.alternative.emit(vm)?;
+                vm.patch_else_jump(end_jump)?;
+            }
             Expression::UnaryExpression(expr) => {
                 expr.expression.emit(vm)?;
                 match expr.operation {
@@ -210,34 +560,214 @@ impl EmitOperationCode for Expression {
                     Operation::POS(s, e) => {
                         vm.add_instruction(Pos, s..e);
                     }
+                    Operation::BITNOT(s, e) => {
+                        vm.add_instruction(BitNot, s..e);
+                    }
+                    Operation::NOT(s, e) => {
+                        vm.add_instruction(Not, s..e);
+                    }
                     _ => {
                         unreachable!();
                     }
                 }
             }
+            Expression::Address(expr) => {
+                let var = &mut expr.var;
+                let range = expr.start..expr.end;
+                match var.accessors.last() {
+                    None => match vm.resolve_local(&var.id.value) {
+                        Some(index) => vm.add_instruction(AddressOfLocal(index), range),
+                        None => vm.add_instruction(AddressOfGlobal(var.id.value.clone()), range),
+                    },
+                    Some(Accessor::Index(_)) => {
+                        emit_place(var, vm)?;
+                        vm.add_instruction(AddressOfIndex, range);
+                    }
+                    Some(Accessor::Member(id)) => {
+                        return Err(RuntimeError(format!(
+                            "can't take the address of field {}",
+                            id.value
+                        ))
+                        .into());
+                    }
+                }
+            }
+            Expression::Dereference(expr) => {
+                expr.pointer.emit(vm)?;
+                vm.add_instruction(Dereference, expr.start..expr.end);
+            }
             Expression::Factor(expr) => match expr {
                 Factor::Expression(expr) => {
                     expr.emit(vm)?;
                 }
+                Factor::Var(var) if var.accessors.is_empty() => {
+                    emit_get_variable(&var.id, vm);
+                }
                 Factor::Var(var) => {
-                    if let Some(index) = vm.resolve_local(&var.id.value) {
-                        vm.add_instruction(GetLocal(index), var.start..var.end);
-                    } else {
-                        vm.add_instruction(GetGlobal(var.id.value.clone()), var.start..var.end);
+                    emit_place(var, vm)?;
+                    match var.accessors.last().unwrap() {
+                        Accessor::Index(_) => vm.add_instruction(GetIndex, var.start..var.end),
+                        Accessor::Member(id) => {
+                            vm.add_instruction(GetField(id.value.clone()), var.start..var.end)
+                        }
                     }
                 }
-                Factor::CallExpression(_) => todo!(),
+                Factor::CallExpression(call) => {
+                    call.emit(vm)?;
+                }
                 Factor::NumberLiteral(NumberLiteral { value, start, end }) => {
                     vm.add_instruction(ConstantI32(*value), *start..*end);
                 }
                 Factor::BooleanLiteral(BooleanLiteral { value, start, end }) => {
                     vm.add_instruction(ConstantBoolean(*value), *start..*end);
                 }
+                Factor::CharLiteral(CharLiteral { value, start, end }) => {
+                    vm.add_instruction(ConstantChar(*value), *start..*end);
+                }
+                Factor::StringLiteral(StringLiteral { value, start, end }) => {
+                    // the fill value, then every char and the ending '\0'
+                    vm.add_instruction(ConstantChar('\0'), *start..*end);
+                    let mut length = 1;
+                    for c in value.chars() {
+                        vm.add_instruction(ConstantChar(c), *start..*end);
+                        length += 1;
+                    }
+                    vm.add_instruction(ConstantChar('\0'), *start..*end);
+                    vm.add_instruction(NewArray(length, length), *start..*end);
+                }
             },
         }
         Ok(())
     }
 }
-// impl EmitOperationCode for Expression {
+/// push the array holding the element of `var` and the last index, or the struct holding the
+/// last member. Every accessor before the last one selects a row or a nested struct
+fn emit_place(var: &mut Var, vm: &mut Vm) -> anyhow::Result<()> {
+    emit_get_variable(&var.id, vm);
+    let (last, accessors) = var.accessors.split_last_mut().unwrap();
+    for accessor in accessors.iter_mut() {
+        match accessor {
+            Accessor::Index(index) => {
+                index.emit(vm)?;
+                vm.add_instruction(GetIndex, var.start..var.end);
+            }
+            Accessor::Member(id) => {
+                vm.add_instruction(GetField(id.value.clone()), var.start..var.end);
+            }
+        }
+    }
+    if let Accessor::Index(index) = last {
+        index.emit(vm)?;
+    }
+    Ok(())
+}
+
+/// push the operands of the store to `target`, return false if the target is a variable
+/// without accessors which is stored by `SetLocal` or `SetGlobal` without operands
+fn emit_target(target: &mut Target, vm: &mut Vm) -> anyhow::Result<bool> {
+    match target {
+        Target::Var(var) if var.accessors.is_empty() => Ok(false),
+        Target::Var(var) => emit_place(var, vm).map(|_| true),
+        Target::Dereference(expr) => expr.pointer.emit(vm).map(|_| true),
+    }
+}
+
+/// read the target and keep the operands pushed by `emit_target` for the store
+fn emit_duplicate_get(target: &Target, is_place: bool, vm: &mut Vm) {
+    let range = target.start()..target.end();
+    match target {
+        Target::Var(var) if !is_place => emit_get_variable(&var.id, vm),
+        Target::Var(var) => match var.accessors.last().unwrap() {
+            Accessor::Index(_) => {
+                vm.add_instruction(Duplicate(2), range.clone());
+                vm.add_instruction(GetIndex, range);
+            }
+            Accessor::Member(id) => {
+                vm.add_instruction(Duplicate(1), range.clone());
+                vm.add_instruction(GetField(id.value.clone()), range);
+            }
+        },
+        Target::Dereference(_) => {
+            vm.add_instruction(Duplicate(1), range.clone());
+            vm.add_instruction(Dereference, range);
+        }
+    }
+}
+
+fn emit_set_target(target: &Target, is_place: bool, vm: &mut Vm) {
+    let range = target.start()..target.end();
+    match target {
+        Target::Var(var) if !is_place => emit_set_variable(&var.id, vm),
+        Target::Var(var) => match var.accessors.last().unwrap() {
+            Accessor::Index(_) => vm.add_instruction(SetIndex, range),
+            Accessor::Member(id) => vm.add_instruction(SetField(id.value.clone()), range),
+        },
+        Target::Dereference(_) => vm.add_instruction(SetDereference, range),
+    }
+}
+fn emit_set_variable(id: &Identifier, vm: &mut Vm) {
+    if let Some(index) = vm.resolve_local(&id.value) {
+        vm.add_instruction(SetLocal(index), id.start..id.end);
+    } else {
+        vm.add_instruction(SetGlobal(id.value.clone()), id.start..id.end);
+    }
+}
+
+/// the operation of a compound assignment
+fn emit_arithmetic(operation: &Operation, vm: &mut Vm) {
+    let range = operation.start()..operation.end();
+    match operation {
+        Operation::PLUS(..) => vm.add_instruction(AddI32, range),
+        Operation::MINUS(..) => vm.add_instruction(SubtractI32, range),
+        Operation::MULTIPLY(..) => vm.add_instruction(MultiplyI32, range),
+        Operation::DIVIDE(..) => vm.add_instruction(DivideI32, range),
+        Operation::MOD(..) => vm.add_instruction(ModuloI32, range),
+        _ => unreachable!(),
+    }
+}
+
+/// a `const int` is read as its value
+fn emit_get_variable(id: &Identifier, vm: &mut Vm) {
+    if let Some(value) = vm.resolve_constant(&id.value) {
+        vm.add_instruction(ConstantI32(value), id.start..id.end);
+    } else if let Some(index) = vm.resolve_local(&id.value) {
+        vm.add_instruction(GetLocal(index), id.start..id.end);
+    } else {
+        vm.add_instruction(GetGlobal(id.value.clone()), id.start..id.end);
+    }
+}
 
-// }
+impl EmitOperationCode for CallExpression {
+    fn emit(&mut self, vm: &mut Vm) -> anyhow::Result<()> {
+        for arg in self.arguments.iter_mut() {
+            arg.emit(vm)?;
+        }
+        let range = self.start..self.end;
+        match self.id.value.as_str() {
+            "print" => vm.add_instruction(Print(self.arguments.len()), range),
+            "println" => vm.add_instruction(Println(self.arguments.len()), range),
+            "input" if self.arguments.is_empty() => vm.add_instruction(Input, range),
+            name if vm.resolve_function(&self.id.value).is_none() => {
+                let index = vm.natives().resolve(name).ok_or_else(|| {
+                    RuntimeError(format!("function {} is not defined", name))
+                })?;
+                vm.add_instruction(CallNative(index, self.arguments.len()), range);
+            }
+            name => {
+                let index = vm.resolve_function(&self.id.value).unwrap();
+                let params = vm.function_params(index);
+                if params != self.arguments.len() {
+                    return Err(RuntimeError(format!(
+                        "function {} expected {} arguments, found {}",
+                        name,
+                        params,
+                        self.arguments.len()
+                    ))
+                    .into());
+                }
+                vm.add_instruction(Call(index), range);
+            }
+        }
+        Ok(())
+    }
+}
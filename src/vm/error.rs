@@ -1,6 +1,29 @@
+use std::ops::Range;
+
 use thiserror::Error;
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("Error occurred exec vm, caused by `{0}`")]
     RuntimeError(String),
+    #[error("error at range: {range:?}, index {index} out of bounds for array of length {length}")]
+    IndexOutOfBounds {
+        index: i32,
+        length: usize,
+        range: Range<usize>,
+    },
+}
+
+impl Error {
+    /// the message without the range, `Vm::report` labels the range in the source instead
+    pub fn message(&self) -> String {
+        match self {
+            Error::RuntimeError(message) => message
+                .strip_prefix("error at range: ")
+                .and_then(|message| message.split_once(", "))
+                .map_or_else(|| message.clone(), |(_, message)| message.to_string()),
+            Error::IndexOutOfBounds { index, length, .. } => {
+                format!("index {} out of bounds for array of length {}", index, length)
+            }
+        }
+    }
 }
@@ -1,18 +1,46 @@
+use std::ops::Range;
+
+use smol_str::SmolStr;
+
 use super::op_code::OpCode;
 
 #[derive(Debug)]
-struct Function {
+pub struct Function {
     pub(crate) params: usize,
     pub(crate) instructions: Vec<OpCode>,
-    name: String,
+    pub(crate) line_number: Vec<Range<usize>>,
+    pub(crate) name: SmolStr,
 }
 
 impl Function {
-    pub(crate) fn new(name: String) -> Self {
+    pub(crate) fn new(name: SmolStr, params: usize) -> Self {
         Function {
-            params: 0,
+            params,
             instructions: vec![],
+            line_number: vec![],
             name,
         }
     }
+
+    pub fn name(&self) -> &SmolStr {
+        &self.name
+    }
+
+    pub fn instructions(&self) -> &Vec<OpCode> {
+        &self.instructions
+    }
+
+    /// the source range of every instruction
+    pub fn line_number(&self) -> &[Range<usize>] {
+        &self.line_number
+    }
+}
+
+/// the caller state saved by `Call`, restored by `Return`
+#[derive(Debug)]
+pub(crate) struct CallFrame {
+    pub(crate) function: usize,
+    pub(crate) ip: usize,
+    pub(crate) slot_base: usize,
+    pub(crate) frame_id: usize,
 }
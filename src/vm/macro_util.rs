@@ -11,9 +11,12 @@ macro_rules! expect_value {
 macro_rules! trace {
     ($type:ident, $op:ident) => {{
         // DEBUG: start
-        if cfg!(debug_assertions) {
+        if cfg!(feature = "trace") {
             println!("-------start--------");
-            disassemble_instruction($op, $type.line_number[$type.ip].clone());
+            disassemble_instruction(
+                $op,
+                $type.functions[$type.function].line_number[$type.ip].clone(),
+            );
             println!("stack: {:?}", $type.stack);
             println!("locals: {:?}", $type.compiler.locals);
         }
@@ -1,5 +1,5 @@
 pub mod op_code;
-pub(crate) mod function;
+pub mod function;
 pub mod value;
 pub(crate) mod emit_op;
 mod macro_util;
@@ -2,6 +2,8 @@ use std::ops::Range;
 
 use smol_str::SmolStr;
 
+use super::function::Function;
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum OpCode {
     // the first param to store the index in constant pool
@@ -11,6 +13,12 @@ pub enum OpCode {
     MultiplyI32,
     AddI32,
     DivideI32,
+    ModuloI32,
+    BitAndI32,
+    BitOrI32,
+    BitXorI32,
+    ShiftLeftI32,
+    ShiftRightI32,
     Equal,
     NotEqual,
     Greater,
@@ -21,14 +29,24 @@ pub enum OpCode {
     Or,
     Neg,
     Pos,
+    BitNot,
+    Not,
 
     Pop,
+    // pop a local whose scope ended, the pointers to it dangle from now on
+    PopLocal,
+    // push a copy of the top n values in the same order
+    Duplicate(usize),
+    // replace the top value with the copy saved by a store, only a struct is changed
+    Copy,
 
     ConstantI32(i32),
     ConstantBoolean(bool),
+    ConstantChar(char),
 
     DefineGlobal(SmolStr),
     GetGlobal(SmolStr),
+    SetGlobal(SmolStr),
 
     GetLocal(usize),
     SetLocal(usize),
@@ -37,8 +55,54 @@ pub enum OpCode {
     Jump(usize),
 
     Loop(usize),
+    // pop an int and jump by the offset at `value - low` in the table, or by the default offset
+    // when the value is outside of the table
+    JumpTable(i32, Vec<usize>, usize),
+
+    // the index of callee in function table
+    Call(usize),
+    // the index of callee in `Natives` and the count of arguments
+    CallNative(usize, usize),
+
+    // the length of array and the count of initializer values on the stack,
+    // below them is the value used to fill the rest of the array
+    NewArray(usize, usize),
+    GetIndex,
+    SetIndex,
+    // the names of the fields, their values are on the stack in the same order
+    NewStruct(Vec<SmolStr>),
+    GetField(SmolStr),
+    SetField(SmolStr),
+    NullPointer,
+    // push a pointer to the local in the slot, or to the global
+    AddressOfLocal(usize),
+    AddressOfGlobal(SmolStr),
+    // replace the array and the index with a pointer to the element
+    AddressOfIndex,
+    // replace the pointer with the value it points to
+    Dereference,
+    // the pointer is below the value, the value is left on the stack
+    SetDereference,
+    // the param is the count of arguments
+    Print(usize),
+    Println(usize),
+    // read an int from the host
+    Input,
 }
 
 pub fn disassemble_instruction(op: &OpCode, line_number: Range<usize>) {
     println!("op: {:?}, line: {:?}", op, line_number);
 }
+
+/// the name of the function then an instruction per line, `location` describes where the
+/// instruction comes from
+pub fn disassemble(function: &Function, location: impl Fn(&Range<usize>) -> String) -> String {
+    let mut output = format!("{}:\n", function.name());
+    let lines = function.instructions().iter().zip(function.line_number());
+    for (index, (op, range)) in lines.enumerate() {
+        let line = format!("{:>6}  {:<40} {}", index, format!("{:?}", op), location(range));
+        output += line.trim_end();
+        output.push('\n');
+    }
+    output
+}
@@ -1,28 +1,187 @@
-use std::ops::{Add, Div, Mul, Sub};
+use enum_as_inner::EnumAsInner;
+use smol_str::SmolStr;
+use std::{
+    cell::RefCell,
+    ops::{Add, BitAnd, BitOr, BitXor, Div, Mul, Rem, Shl, Shr, Sub},
+    rc::Rc,
+};
 
-#[derive(Debug, Eq, PartialEq, Clone, PartialOrd, Copy)]
+#[derive(Debug, Eq, PartialEq, Clone, PartialOrd, EnumAsInner)]
 pub enum Value {
     I32(i32),
     Boolean(bool),
+    Char(char),
+    // arrays are passed by reference like the interpreter's `ArrayType`
+    Array(Rc<RefCell<Vec<Value>>>),
+    // the fields in declaration order, a struct is copied when it's stored
+    Struct(Rc<RefCell<Vec<(SmolStr, Value)>>>),
+    Pointer(Pointer),
     Nil
 }
 
+/// the same memory model as the interpreter's `Pointer`, the offset is checked when the
+/// pointer is dereferenced
+#[derive(Debug, Clone)]
+pub struct Pointer {
+    pub(crate) address: Address,
+    pub(crate) offset: i32,
+}
+
+#[derive(Debug, Clone)]
+pub enum Address {
+    Null,
+    /// `slot` is the index in the stack, the pointer dangles once the frame `frame` returns or
+    /// the local in the slot is popped, which changes the generation of the slot
+    Local {
+        slot: usize,
+        frame: usize,
+        generation: usize,
+    },
+    Global(SmolStr),
+    Element(Rc<RefCell<Vec<Value>>>),
+}
+
+impl Pointer {
+    pub(crate) fn new(address: Address, offset: i32) -> Self {
+        Self { address, offset }
+    }
+
+    fn offset_by(&self, n: i32) -> Self {
+        Self::new(self.address.clone(), self.offset.wrapping_add(n))
+    }
+
+    /// only the pointers to the same variable or the same array can be subtracted and ordered
+    pub(crate) fn same_target(&self, other: &Pointer) -> bool {
+        match (&self.address, &other.address) {
+            (Address::Null, Address::Null) => true,
+            (
+                Address::Local {
+                    slot,
+                    frame,
+                    generation,
+                },
+                Address::Local {
+                    slot: s,
+                    frame: f,
+                    generation: g,
+                },
+            ) => slot == s && frame == f && generation == g,
+            (Address::Global(a), Address::Global(b)) => a == b,
+            (Address::Element(a), Address::Element(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl PartialEq for Pointer {
+    fn eq(&self, other: &Self) -> bool {
+        self.same_target(other) && self.offset == other.offset
+    }
+}
+
+impl Eq for Pointer {}
+
+impl PartialOrd for Pointer {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        if self.same_target(other) {
+            self.offset.partial_cmp(&other.offset)
+        } else {
+            None
+        }
+    }
+}
+
 impl std::fmt::Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Value::I32(v) => write!(f, "integer({})", v),
             Value::Boolean(v) => write!(f, "boolean({})", v),
+            Value::Char(v) => write!(f, "char({:?})", v),
+            Value::Array(v) => write!(f, "array({})", v.borrow().len()),
+            Value::Struct(v) => write!(f, "struct({})", v.borrow().len()),
+            Value::Pointer(v) => write!(f, "pointer({})", v.offset),
             Value::Nil => write!(f, "nil"),
         }
     }
 }
+impl Value {
+    /// the representation used by `print` and `println`, same as the interpreter
+    pub fn to_print_string(&self) -> String {
+        match self {
+            Value::I32(v) => v.to_string(),
+            Value::Boolean(v) => v.to_string(),
+            Value::Char(v) => v.to_string(),
+            // a char array is printed as a string, which ends at the first '\0'
+            Value::Array(v) if matches!(v.borrow().first(), Some(Value::Char(_))) => v
+                .borrow()
+                .iter()
+                .map_while(|item| item.as_char().copied())
+                .take_while(|c| *c != '\0')
+                .collect(),
+            Value::Array(v) => format!(
+                "[{}]",
+                v.borrow()
+                    .iter()
+                    .map(|item| item.to_print_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Value::Struct(v) => format!(
+                "{{{}}}",
+                v.borrow()
+                    .iter()
+                    .map(|(_, field)| field.to_print_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Value::Pointer(_) => "pointer".to_string(),
+            Value::Nil => "void".to_string(),
+        }
+    }
+
+    /// the value saved by a store, a struct is copied with the arrays in it while an array is
+    /// shared, same as `Binding::stored`
+    pub fn stored(&self) -> Value {
+        match self {
+            Value::Struct(_) => self.deep_copy(),
+            value => value.clone(),
+        }
+    }
+
+    /// an array is converted to the pointer to its first element when it's used as a pointer
+    pub fn decayed(self) -> Value {
+        match self {
+            Value::Array(array) => Value::Pointer(Pointer::new(Address::Element(array), 0)),
+            value => value,
+        }
+    }
+
+    fn deep_copy(&self) -> Value {
+        match self {
+            Value::Array(v) => Value::Array(Rc::new(RefCell::new(
+                v.borrow().iter().map(Value::deep_copy).collect(),
+            ))),
+            Value::Struct(v) => Value::Struct(Rc::new(RefCell::new(
+                v.borrow()
+                    .iter()
+                    .map(|(name, field)| (name.clone(), field.deep_copy()))
+                    .collect(),
+            ))),
+            value => value.clone(),
+        }
+    }
+}
 impl Add for Value {
     type Output = Value;
 
     fn add(self, rhs: Self) -> Self::Output {
         use Value::*;
         match (self, rhs) {
-            (I32(left), I32(right)) => I32(left + right),
+            (I32(left), I32(right)) => I32(left.wrapping_add(right)),
+            (I32(n), pointer) | (pointer, I32(n)) => match pointer.decayed() {
+                Pointer(pointer) => Pointer(pointer.offset_by(n)),
+                a => unreachable!("{} can't be added to {}", a, I32(n)),
+            },
             (a, b) => unreachable!("{} can't be added to {}", a, b)
         }
     }
@@ -33,7 +192,7 @@ impl Mul for Value {
     fn mul(self, rhs: Self) -> Self::Output {
         use Value::*;
         match (self, rhs) {
-            (I32(left), I32(right)) => I32(left * right),
+            (I32(left), I32(right)) => I32(left.wrapping_mul(right)),
             (a, b) => unreachable!("{} can't be multiplied to {}", a, b)
         }
     }
@@ -44,7 +203,7 @@ impl Div for Value {
     fn div(self, rhs: Self) -> Self::Output {
         use Value::*;
         match (self, rhs) {
-            (I32(left), I32(right)) => I32(left / right),
+            (I32(left), I32(right)) => I32(left.wrapping_div(right)),
             (a, b) => unreachable!("{} can't be divided to {}", a, b)
         }
     }
@@ -56,9 +215,83 @@ impl Sub for Value {
     fn sub(self, rhs: Self) -> Self::Output {
         use Value::*;
         match (self, rhs) {
-            (I32(left), I32(right)) => I32(left - right),
-            (a, b) => unreachable!("{} can't be subtracted to {}", a, b)
+            (I32(left), I32(right)) => I32(left.wrapping_sub(right)),
+            (pointer, I32(n)) => match pointer.decayed() {
+                Pointer(pointer) => Pointer(pointer.offset_by(n.wrapping_neg())),
+                a => unreachable!("{} can't be subtracted to {}", I32(n), a),
+            },
+            // the vm checks that both point to the same variable or array
+            (a, b) => match (a.decayed(), b.decayed()) {
+                (Pointer(a), Pointer(b)) => I32(a.offset.wrapping_sub(b.offset)),
+                (a, b) => unreachable!("{} can't be subtracted to {}", b, a),
+            },
         }
     }
 }
 
+impl Rem for Value {
+    type Output = Value;
+
+    fn rem(self, rhs: Self) -> Self::Output {
+        use Value::*;
+        match (self, rhs) {
+            (I32(left), I32(right)) => I32(left.wrapping_rem(right)),
+            (a, b) => unreachable!("{} can't be divided to {}", a, b)
+        }
+    }
+}
+impl BitAnd for Value {
+    type Output = Value;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        use Value::*;
+        match (self, rhs) {
+            (I32(left), I32(right)) => I32(left & right),
+            (a, b) => unreachable!("{} can't be combined with {}", a, b)
+        }
+    }
+}
+impl BitOr for Value {
+    type Output = Value;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        use Value::*;
+        match (self, rhs) {
+            (I32(left), I32(right)) => I32(left | right),
+            (a, b) => unreachable!("{} can't be combined with {}", a, b)
+        }
+    }
+}
+impl BitXor for Value {
+    type Output = Value;
+
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        use Value::*;
+        match (self, rhs) {
+            (I32(left), I32(right)) => I32(left ^ right),
+            (a, b) => unreachable!("{} can't be combined with {}", a, b)
+        }
+    }
+}
+impl Shl for Value {
+    type Output = Value;
+
+    fn shl(self, rhs: Self) -> Self::Output {
+        use Value::*;
+        match (self, rhs) {
+            (I32(left), I32(right)) => I32(left.wrapping_shl(right as u32)),
+            (a, b) => unreachable!("{} can't be shifted by {}", a, b)
+        }
+    }
+}
+impl Shr for Value {
+    type Output = Value;
+
+    fn shr(self, rhs: Self) -> Self::Output {
+        use Value::*;
+        match (self, rhs) {
+            (I32(left), I32(right)) => I32(left.wrapping_shr(right as u32)),
+            (a, b) => unreachable!("{} can't be shifted by {}", a, b)
+        }
+    }
+}
@@ -1,22 +1,38 @@
+use std::cell::RefCell;
 use std::ops::Range;
 use std::rc::Rc;
 
 use super::error::Error::{self, *};
+use super::function::{CallFrame, Function};
 use super::op_code::disassemble_instruction;
 use super::{
     op_code::OpCode::{self, *},
-    value::Value,
+    value::{Address, Pointer, Value},
 };
+use crate::error_emit::{ErrorReporter, FileSpan};
 use crate::expect_value;
+use crate::host::{CapturedHost, Host};
+use crate::native::{NativeError, NativeValue, Natives};
+use crate::parser::ast::StructDeclaration;
+use crate::semantic::FunctionSignature;
 use crate::trace;
 use crate::util::variant_eq;
+use crate::MAX_CALL_DEPTH;
 use fxhash::FxHashMap;
 use smol_str::SmolStr;
+
 #[derive(Debug)]
 struct Compiler {
     locals: Vec<Local>,
     scope_depth: i32,
-    
+    // index of the function whose instructions are being emitted
+    function: usize,
+    // the loops enclosing the statement being emitted, the innermost is the last
+    loops: Vec<LoopContext>,
+    // the layouts used to emit the default value of a struct
+    structs: FxHashMap<SmolStr, Rc<StructDeclaration>>,
+    // the values of the global `const int`s, their reads are folded to `ConstantI32`
+    constants: FxHashMap<SmolStr, i32>,
 }
 
 impl Compiler {
@@ -24,13 +40,13 @@ impl Compiler {
         Self {
             scope_depth: 0,
             locals: Vec::with_capacity(256),
+            function: 0,
+            loops: vec![],
+            structs: FxHashMap::default(),
+            constants: FxHashMap::default(),
         }
     }
 
-    pub fn local_count(&self) -> usize {
-        self.locals.len()
-    }
-
     fn begin_scope(&mut self) {
         self.scope_depth += 1;
     }
@@ -40,49 +56,175 @@ impl Compiler {
     }
 }
 
+#[derive(Debug)]
+struct LoopContext {
+    // the scope depth outside of the loop body, locals deeper than it are popped before a jump
+    scope_depth: i32,
+    // where `continue` jumps back to, `None` until the target is emitted
+    continue_target: Option<usize>,
+    continue_jumps: Vec<usize>,
+    break_jumps: Vec<usize>,
+    // a switch is only left by `break`, `continue` goes to the enclosing loop
+    is_switch: bool,
+}
+
 #[derive(Debug)]
 struct Local {
     pub(crate) name: SmolStr,
     pub(crate) depth: i32,
+    // the value of a `const int`
+    pub(crate) constant: Option<i32>,
 }
 #[derive(Debug)]
 pub struct Vm {
-    instructions: Vec<OpCode>,
-    line_number: Vec<Range<usize>>,
+    // the first function is the top level script
+    functions: Vec<Function>,
+    function_table: FxHashMap<SmolStr, usize>,
+    frames: Vec<CallFrame>,
     stack: Vec<Value>,
     globals: FxHashMap<SmolStr, Value>,
     compiler: Compiler,
+    // the executing function and the stack index of its first local
+    function: usize,
+    slot_base: usize,
+    // every call gets a new id, a pointer to a local dangles once its frame isn't on the stack
+    frame_id: usize,
+    next_frame_id: usize,
+    // bumped when the local in the slot is popped, a pointer to a local of an ended block
+    // records an older generation
+    generations: Vec<usize>,
     ip: usize,
+    // the function called after the top level script finished, normally `main`
+    entry: Option<usize>,
+    host: Box<dyn Host>,
+    natives: Natives,
 }
 
 impl Vm {
+    /// the output of the program is discarded, use `Vm::with_host` to keep it
     pub fn new() -> Self {
+        Self::with_host(Box::new(CapturedHost::default()))
+    }
+
+    pub fn with_host(host: Box<dyn Host>) -> Self {
         Self {
-            instructions: vec![],
-            line_number: vec![],
+            functions: vec![Function::new("<script>".into(), 0)],
+            function_table: FxHashMap::default(),
+            frames: Vec::with_capacity(64),
             stack: Vec::with_capacity(256),
             globals: FxHashMap::default(),
             compiler: Compiler::new(),
+            function: 0,
+            slot_base: 0,
+            frame_id: 0,
+            next_frame_id: 0,
+            generations: vec![],
             ip: 0,
+            entry: None,
+            host,
+            natives: Natives::default(),
         }
     }
+
+    /// the same as `Interpreter::register_native`, natives must be registered before the program
+    /// is emitted
+    pub fn register_native(
+        &mut self,
+        name: &str,
+        signature: FunctionSignature,
+        closure: impl Fn(&[NativeValue]) -> Result<NativeValue, String> + 'static,
+    ) {
+        self.natives.register(name, signature, closure);
+    }
+
+    pub fn natives(&self) -> &Natives {
+        &self.natives
+    }
+
+    /// render an error returned by `exec` against the source file, it's located at the
+    /// instruction which failed. `file_name` is the path printed in the report
+    pub fn report<'a>(
+        &self,
+        error: &anyhow::Error,
+        file_name: &'a str,
+        source_file: &'a str,
+    ) -> ErrorReporter<'a> {
+        let range = self.functions[self.function]
+            .line_number
+            .get(self.ip)
+            .cloned()
+            .unwrap_or(0..0);
+        let message = match error.downcast_ref::<Error>() {
+            Some(error) => error.message(),
+            None => error.to_string(),
+        };
+        let mut error_reporter = ErrorReporter::new();
+        let file_id = error_reporter.add_file(file_name, source_file.to_string());
+        error_reporter.add_labeled_diagnostic(
+            FileSpan::new(file_id, range),
+            "runtime error".into(),
+            message,
+        );
+        error_reporter
+    }
     pub fn operations(&self) -> &Vec<OpCode> {
-        &self.instructions
+        &self.functions[self.compiler.function].instructions
+    }
+
+    pub fn functions(&self) -> &Vec<Function> {
+        &self.functions
+    }
+
+    /// the int returned by `main` once `exec` has finished, `None` if `main` returns nothing
+    pub fn main_result(&self) -> Option<i32> {
+        match self.stack.last() {
+            Some(Value::I32(value)) => Some(*value),
+            _ => None,
+        }
     }
 
     pub fn exec(&mut self) -> anyhow::Result<()> {
-        while self.ip < self.instructions.len() {
-            let op = &self.instructions[self.ip];
+        loop {
+            let function = &self.functions[self.function];
+            if self.ip >= function.instructions.len() {
+                if let Some(entry) = self.entry.take() {
+                    self.frames.push(CallFrame {
+                        function: self.function,
+                        ip: self.ip,
+                        slot_base: self.slot_base,
+                        frame_id: self.frame_id,
+                    });
+                    self.next_frame_id += 1;
+                    self.frame_id = self.next_frame_id;
+                    self.function = entry;
+                    self.slot_base = self.stack.len();
+                    self.ip = 0;
+                    continue;
+                }
+                break;
+            }
+            let op = &function.instructions[self.ip];
             match op {
                 ConstantI32(i) => {
                     self.stack.push(Value::I32(*i));
                 }
                 Return => {
-                    self.stack.pop();
+                    let value = expect_value!(self);
+                    if let Some(frame) = self.frames.pop() {
+                        self.stack.truncate(self.slot_base);
+                        self.stack.push(value);
+                        self.function = frame.function;
+                        self.ip = frame.ip;
+                        self.slot_base = frame.slot_base;
+                        self.frame_id = frame.frame_id;
+                        continue;
+                    }
+                    break;
                 }
                 SubtractI32 => {
                     let b = expect_value!(self);
                     let a = expect_value!(self);
+                    let (a, b) = Self::pointer_operands(a, b, &function.line_number[self.ip])?;
                     self.stack.push(a - b);
                 }
                 MultiplyI32 => {
@@ -99,14 +241,63 @@ impl Vm {
                 DivideI32 => {
                     let b = expect_value!(self);
                     let a = expect_value!(self);
+                    if b == Value::I32(0) {
+                        return Err(RuntimeError(format!(
+                            "error at range: {:?}, attempt to divide by zero",
+                            function.line_number[self.ip]
+                        ))
+                        .into());
+                    }
                     self.stack.push(a / b);
                 }
+                ModuloI32 => {
+                    let b = expect_value!(self);
+                    let a = expect_value!(self);
+                    if b == Value::I32(0) {
+                        return Err(RuntimeError(format!(
+                            "error at range: {:?}, attempt to calculate the remainder with a divisor of zero",
+                            function.line_number[self.ip]
+                        ))
+                        .into());
+                    }
+                    self.stack.push(a % b);
+                }
+                BitAndI32 => {
+                    let b = expect_value!(self);
+                    let a = expect_value!(self);
+                    self.stack.push(a & b);
+                }
+                BitOrI32 => {
+                    let b = expect_value!(self);
+                    let a = expect_value!(self);
+                    self.stack.push(a | b);
+                }
+                BitXorI32 => {
+                    let b = expect_value!(self);
+                    let a = expect_value!(self);
+                    self.stack.push(a ^ b);
+                }
+                ShiftLeftI32 => {
+                    let b = expect_value!(self);
+                    let a = expect_value!(self);
+                    self.stack.push(a << b);
+                }
+                ShiftRightI32 => {
+                    let b = expect_value!(self);
+                    let a = expect_value!(self);
+                    self.stack.push(a >> b);
+                }
                 ConstantBoolean(b) => {
                     self.stack.push(Value::Boolean(*b));
                 }
+                ConstantChar(c) => {
+                    self.stack.push(Value::Char(*c));
+                }
                 Equal => {
                     let b = expect_value!(self);
                     let a = expect_value!(self);
+                    // an array is compared as a pointer, by identity
+                    let (a, b) = (a.decayed(), b.decayed());
                     assert!(variant_eq(&a, &b));
                     let res = Value::Boolean(a == b);
                     self.stack.push(res);
@@ -114,6 +305,8 @@ impl Vm {
                 NotEqual => {
                     let b = expect_value!(self);
                     let a = expect_value!(self);
+                    // an array is compared as a pointer, by identity
+                    let (a, b) = (a.decayed(), b.decayed());
                     assert!(variant_eq(&a, &b));
                     let res = Value::Boolean(a != b);
                     self.stack.push(res);
@@ -121,29 +314,49 @@ impl Vm {
                 Greater => {
                     let b = expect_value!(self);
                     let a = expect_value!(self);
-                    assert!(matches!(a, Value::I32(..)));
-                    assert!(matches!(b, Value::I32(..)));
+                    let (a, b) = Self::pointer_operands(a, b, &function.line_number[self.ip])?;
+                    assert!(matches!(
+                        (&a, &b),
+                        (Value::I32(..), Value::I32(..))
+                            | (Value::Char(..), Value::Char(..))
+                            | (Value::Pointer(..), Value::Pointer(..))
+                    ));
                     self.stack.push(Value::Boolean(a > b));
                 }
                 Less => {
                     let b = expect_value!(self);
                     let a = expect_value!(self);
-                    assert!(matches!(a, Value::I32(..)));
-                    assert!(matches!(b, Value::I32(..)));
+                    let (a, b) = Self::pointer_operands(a, b, &function.line_number[self.ip])?;
+                    assert!(matches!(
+                        (&a, &b),
+                        (Value::I32(..), Value::I32(..))
+                            | (Value::Char(..), Value::Char(..))
+                            | (Value::Pointer(..), Value::Pointer(..))
+                    ));
                     self.stack.push(Value::Boolean(a < b));
                 }
                 GreaterEqual => {
                     let b = expect_value!(self);
                     let a = expect_value!(self);
-                    assert!(matches!(a, Value::I32(..)));
-                    assert!(matches!(b, Value::I32(..)));
+                    let (a, b) = Self::pointer_operands(a, b, &function.line_number[self.ip])?;
+                    assert!(matches!(
+                        (&a, &b),
+                        (Value::I32(..), Value::I32(..))
+                            | (Value::Char(..), Value::Char(..))
+                            | (Value::Pointer(..), Value::Pointer(..))
+                    ));
                     self.stack.push(Value::Boolean(a >= b));
                 }
                 LessEqual => {
                     let b = expect_value!(self);
                     let a = expect_value!(self);
-                    assert!(matches!(a, Value::I32(..)));
-                    assert!(matches!(b, Value::I32(..)));
+                    let (a, b) = Self::pointer_operands(a, b, &function.line_number[self.ip])?;
+                    assert!(matches!(
+                        (&a, &b),
+                        (Value::I32(..), Value::I32(..))
+                            | (Value::Char(..), Value::Char(..))
+                            | (Value::Pointer(..), Value::Pointer(..))
+                    ));
                     self.stack.push(Value::Boolean(a <= b));
                 }
 
@@ -155,7 +368,7 @@ impl Vm {
                     } else {
                         return Err(RuntimeError(format!(
                             "error at range: {:?}, expected boolean value",
-                            self.line_number[self.ip]
+                            function.line_number[self.ip]
                         ))
                         .into());
                     }
@@ -168,7 +381,7 @@ impl Vm {
                     } else {
                         return Err(RuntimeError(format!(
                             "error at range: {:?}, expected boolean value",
-                            self.line_number[self.ip]
+                            function.line_number[self.ip]
                         ))
                         .into());
                     }
@@ -176,11 +389,11 @@ impl Vm {
                 Neg => {
                     let a = expect_value!(self);
                     if let Value::I32(v) = a {
-                        self.stack.push(Value::I32(-v));
+                        self.stack.push(Value::I32(v.wrapping_neg()));
                     } else {
                         return Err(RuntimeError(format!(
                             "error at range: {:?}, expected integer value, operation negative",
-                            self.line_number[self.ip]
+                            function.line_number[self.ip]
                         ))
                         .into());
                     }
@@ -192,7 +405,31 @@ impl Vm {
                     } else {
                         return Err(RuntimeError(format!(
                             "error at range: {:?}, expected integer value, operation positive",
-                            self.line_number[self.ip]
+                            function.line_number[self.ip]
+                        ))
+                        .into());
+                    }
+                }
+                Not => {
+                    let a = expect_value!(self);
+                    if let Value::Boolean(v) = a {
+                        self.stack.push(Value::Boolean(!v));
+                    } else {
+                        return Err(RuntimeError(format!(
+                            "error at range: {:?}, expected boolean value, operation not",
+                            function.line_number[self.ip]
+                        ))
+                        .into());
+                    }
+                }
+                BitNot => {
+                    let a = expect_value!(self);
+                    if let Value::I32(v) = a {
+                        self.stack.push(Value::I32(!v));
+                    } else {
+                        return Err(RuntimeError(format!(
+                            "error at range: {:?}, expected integer value, operation bitwise not",
+                            function.line_number[self.ip]
                         ))
                         .into());
                     }
@@ -200,63 +437,451 @@ impl Vm {
                 Pop => {
                     self.stack.pop();
                 }
+                PopLocal => {
+                    self.stack.pop();
+                    let slot = self.stack.len();
+                    if slot >= self.generations.len() {
+                        self.generations.resize(slot + 1, 0);
+                    }
+                    self.generations[slot] += 1;
+                }
+                Duplicate(count) => {
+                    let start = self.stack.len() - *count;
+                    self.stack.extend_from_within(start..);
+                }
+                Copy => {
+                    let value = expect_value!(self);
+                    self.stack.push(value.stored());
+                }
                 DefineGlobal(name) => {
                     let value = expect_value!(self);
                     self.globals.insert(name.clone(), value);
                 }
-                Nil => todo!(),
+                Nil => {
+                    self.stack.push(Value::Nil);
+                }
                 GetGlobal(name) => {
                     if let Some(value) = self.globals.get(name) {
                         self.stack.push(value.clone());
                     } else {
                         return Err(RuntimeError(format!(
                             "error at range: {:?}, variable {} not defined",
-                            self.line_number[self.ip], name
+                            function.line_number[self.ip], name
+                        ))
+                        .into());
+                    }
+                }
+                SetGlobal(name) => {
+                    let value = self.stack.last().cloned().ok_or_else(|| {
+                        Error::RuntimeError("expected peek of stack is a value".to_string())
+                    })?;
+                    if let Some(global) = self.globals.get_mut(name) {
+                        *global = value.stored();
+                    } else {
+                        return Err(RuntimeError(format!(
+                            "error at range: {:?}, variable {} not defined",
+                            function.line_number[self.ip], name
                         ))
                         .into());
                     }
                 }
                 GetLocal(index) => {
-                    self.stack.push(self.stack[*index].clone());
+                    self.stack.push(self.stack[self.slot_base + *index].clone());
                 }
                 SetLocal(index) => {
-                    self.stack[*index] = self.stack.last().unwrap().clone();
+                    self.stack[self.slot_base + *index] = self.stack.last().unwrap().stored();
                 }
                 JumpIfFalse(offset) => {
                     if let Some(Value::Boolean(v)) = self.stack.last() {
                         if !*v {
                             trace!(self, op);
-                            println!("ip: {}, offset: {}", self.ip, offset);
                             self.ip += offset;
                             continue;
                         }
                     } else {
                         return Err(RuntimeError(format!(
-                            "error at {:?}, peek of stack should be a boolean",
-                            self.line_number[self.ip]
+                            "error at range: {:?}, peek of stack should be a boolean",
+                            function.line_number[self.ip]
                         ))
                         .into());
                     }
                 }
                 Jump(offset) => {
                     trace!(self, op);
-                    println!("ip: {}, offset: {}", self.ip, offset);
                     self.ip += offset;
                     continue;
                 }
                 Loop(offset) => {
+                    trace!(self, op);
                     self.ip -= offset;
-                },
+                    continue;
+                }
+                JumpTable(low, offsets, default_offset) => {
+                    let value = match self.stack.pop() {
+                        Some(Value::I32(value)) => value,
+                        value => {
+                            return Err(RuntimeError(format!(
+                                "switch value should be an integer, found {:?}",
+                                value
+                            ))
+                            .into())
+                        }
+                    };
+                    let index = value as i64 - *low as i64;
+                    let offset = if (0..offsets.len() as i64).contains(&index) {
+                        offsets[index as usize]
+                    } else {
+                        *default_offset
+                    };
+                    trace!(self, op);
+                    self.ip += offset;
+                    continue;
+                }
+                Call(index) => {
+                    trace!(self, op);
+                    let callee = *index;
+                    if self.frames.len() >= MAX_CALL_DEPTH {
+                        return Err(RuntimeError(format!(
+                            "error at range: {:?}, stack overflow, more than {} nested calls",
+                            function.line_number[self.ip], MAX_CALL_DEPTH
+                        ))
+                        .into());
+                    }
+                    self.frames.push(CallFrame {
+                        function: self.function,
+                        ip: self.ip + 1,
+                        slot_base: self.slot_base,
+                        frame_id: self.frame_id,
+                    });
+                    self.next_frame_id += 1;
+                    self.frame_id = self.next_frame_id;
+                    self.slot_base = self.stack.len() - self.functions[callee].params;
+                    // a struct argument is passed by value
+                    for value in self.stack[self.slot_base..].iter_mut() {
+                        *value = value.stored();
+                    }
+                    self.function = callee;
+                    self.ip = 0;
+                    continue;
+                }
+                CallNative(index, count) => {
+                    let native = self.natives.function(*index).clone();
+                    let range = &function.line_number[self.ip];
+                    let arguments = self
+                        .stack
+                        .drain(self.stack.len() - *count..)
+                        .map(|value| match value {
+                            Value::I32(value) => Ok(NativeValue::Int(value)),
+                            Value::Boolean(value) => Ok(NativeValue::Boolean(value)),
+                            Value::Nil => Ok(NativeValue::Void),
+                            value => Err(RuntimeError(format!(
+                                "error at range: {:?}, {} can't be passed to native function {}",
+                                range,
+                                value,
+                                native.name()
+                            ))),
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let value = match native.call(&arguments) {
+                        Ok(NativeValue::Int(value)) => Value::I32(value),
+                        Ok(NativeValue::Boolean(value)) => Value::Boolean(value),
+                        Ok(NativeValue::Void) => Value::Nil,
+                        Err(NativeError::ArgumentMismatch(message))
+                        | Err(NativeError::Failed(message)) => {
+                            return Err(
+                                RuntimeError(format!("error at range: {:?}, {}", range, message))
+                                    .into(),
+                            );
+                        }
+                    };
+                    self.stack.push(value);
+                }
+                NewArray(length, count) => {
+                    let start = self.stack.len() - *count;
+                    let mut array = self.stack.drain(start..).collect::<Vec<_>>();
+                    let fill = expect_value!(self);
+                    // the type checker rejects more initializers than elements
+                    array.truncate(*length);
+                    // every element gets its own copy of a struct
+                    for value in array.iter_mut() {
+                        *value = value.stored();
+                    }
+                    array.resize_with(*length, || fill.stored());
+                    self.stack.push(Value::Array(Rc::new(RefCell::new(array))));
+                }
+                GetIndex => {
+                    let index = expect_value!(self);
+                    let array = expect_value!(self);
+                    let i = Self::index_array(&array, &index, &function.line_number[self.ip])?;
+                    let value = array.as_array().unwrap().borrow()[i].clone();
+                    self.stack.push(value);
+                }
+                SetIndex => {
+                    let value = expect_value!(self);
+                    let index = expect_value!(self);
+                    let array = expect_value!(self);
+                    let i = Self::index_array(&array, &index, &function.line_number[self.ip])?;
+                    array.as_array().unwrap().borrow_mut()[i] = value.stored();
+                    self.stack.push(value);
+                }
+                NewStruct(names) => {
+                    let start = self.stack.len() - names.len();
+                    let fields = names
+                        .iter()
+                        .cloned()
+                        .zip(self.stack.drain(start..))
+                        .collect::<Vec<_>>();
+                    self.stack.push(Value::Struct(Rc::new(RefCell::new(fields))));
+                }
+                GetField(name) => {
+                    let value = expect_value!(self);
+                    let i = Self::field_index(&value, name, &function.line_number[self.ip])?;
+                    let field = value.as_struct().unwrap().borrow()[i].1.clone();
+                    self.stack.push(field);
+                }
+                SetField(name) => {
+                    let field = expect_value!(self);
+                    let value = expect_value!(self);
+                    let i = Self::field_index(&value, name, &function.line_number[self.ip])?;
+                    value.as_struct().unwrap().borrow_mut()[i].1 = field.stored();
+                    self.stack.push(field);
+                }
+                NullPointer => {
+                    self.stack.push(Value::Pointer(Pointer::new(Address::Null, 0)));
+                }
+                AddressOfLocal(index) => {
+                    let slot = self.slot_base + *index;
+                    let address = Address::Local {
+                        slot,
+                        frame: self.frame_id,
+                        generation: self.generation(slot),
+                    };
+                    self.stack.push(Value::Pointer(Pointer::new(address, 0)));
+                }
+                AddressOfGlobal(name) => {
+                    let address = Address::Global(name.clone());
+                    self.stack.push(Value::Pointer(Pointer::new(address, 0)));
+                }
+                AddressOfIndex => {
+                    let index = expect_value!(self);
+                    let array = expect_value!(self);
+                    let i = Self::index_array(&array, &index, &function.line_number[self.ip])?;
+                    let address = Address::Element(array.as_array().unwrap().clone());
+                    self.stack.push(Value::Pointer(Pointer::new(address, i as i32)));
+                }
+                Dereference => {
+                    let pointer = expect_value!(self);
+                    let range = &function.line_number[self.ip];
+                    let value = match self.check_pointer(pointer, range)? {
+                        Pointer {
+                            address: Address::Local { slot, .. },
+                            ..
+                        } => self.stack[slot].clone(),
+                        Pointer {
+                            address: Address::Global(name),
+                            ..
+                        } => self.globals[&name].clone(),
+                        Pointer {
+                            address: Address::Element(array),
+                            offset,
+                        } => array.borrow()[offset as usize].clone(),
+                        Pointer {
+                            address: Address::Null,
+                            ..
+                        } => unreachable!("a null pointer is reported by `check_pointer`"),
+                    };
+                    self.stack.push(value);
+                }
+                SetDereference => {
+                    let value = expect_value!(self);
+                    let pointer = expect_value!(self);
+                    let range = &function.line_number[self.ip];
+                    match self.check_pointer(pointer, range)? {
+                        Pointer {
+                            address: Address::Local { slot, .. },
+                            ..
+                        } => self.stack[slot] = value.stored(),
+                        Pointer {
+                            address: Address::Global(name),
+                            ..
+                        } => {
+                            self.globals.insert(name, value.stored());
+                        }
+                        Pointer {
+                            address: Address::Element(array),
+                            offset,
+                        } => array.borrow_mut()[offset as usize] = value.stored(),
+                        Pointer {
+                            address: Address::Null,
+                            ..
+                        } => unreachable!("a null pointer is reported by `check_pointer`"),
+                    }
+                    self.stack.push(value);
+                }
+                Print(count) => {
+                    let output = self
+                        .stack
+                        .drain(self.stack.len() - *count..)
+                        .map(|value| value.to_print_string())
+                        .collect::<String>();
+                    self.host.write(&output);
+                    self.stack.push(Value::Nil);
+                }
+                Println(count) => {
+                    let output = self
+                        .stack
+                        .drain(self.stack.len() - *count..)
+                        .map(|value| value.to_print_string())
+                        .collect::<String>();
+                    self.host.write(&(output + "\n"));
+                    self.stack.push(Value::Nil);
+                }
+                Input => {
+                    let value = self.host.read_int().ok_or_else(|| {
+                        RuntimeError(format!(
+                            "error at range: {:?}, failed to read an int from input",
+                            function.line_number[self.ip]
+                        ))
+                    })?;
+                    self.stack.push(Value::I32(value));
+                }
             }
             trace!(self, op);
             self.ip += 1;
         }
+        self.host.flush();
         Ok(())
     }
 
+    /// check the operands of `GetIndex` and `SetIndex`, return the checked index
+    fn index_array(array: &Value, index: &Value, range: &Range<usize>) -> anyhow::Result<usize> {
+        match (array, index) {
+            (Value::Array(array), Value::I32(index)) => {
+                let length = array.borrow().len();
+                if *index < 0 || *index as usize >= length {
+                    return Err(IndexOutOfBounds {
+                        index: *index,
+                        length,
+                        range: range.clone(),
+                    }
+                    .into());
+                }
+                Ok(*index as usize)
+            }
+            (Value::Array(_), index) => Err(RuntimeError(format!(
+                "error at range: {:?}, index of array should be integer, found {}",
+                range, index
+            ))
+            .into()),
+            (value, _) => Err(RuntimeError(format!(
+                "error at range: {:?}, only array can be indexed, found {}",
+                range, value
+            ))
+            .into()),
+        }
+    }
+
+    /// check the operand of `Dereference` and `SetDereference` like the interpreter, the
+    /// pointer must point to a live variable or an element in the bounds of its array
+    fn check_pointer(&self, value: Value, range: &Range<usize>) -> anyhow::Result<Pointer> {
+        let pointer = match value.decayed() {
+            Value::Pointer(pointer) => pointer,
+            value => {
+                return Err(RuntimeError(format!(
+                    "error at range: {:?}, only pointer can be dereferenced, found {}",
+                    range, value
+                ))
+                .into());
+            }
+        };
+        let length = match pointer.address {
+            Address::Null => {
+                return Err(RuntimeError(format!(
+                    "error at range: {:?}, attempt to dereference a null pointer",
+                    range
+                ))
+                .into());
+            }
+            Address::Local {
+                slot,
+                frame,
+                generation,
+            } if !self.is_live(slot, frame, generation) => {
+                return Err(RuntimeError(format!(
+                    "error at range: {:?}, the local pointed to has gone out of scope",
+                    range
+                ))
+                .into());
+            }
+            Address::Local { .. } | Address::Global(_) => 1,
+            Address::Element(ref array) => array.borrow().len(),
+        };
+        if pointer.offset < 0 || pointer.offset as usize >= length {
+            return Err(IndexOutOfBounds {
+                index: pointer.offset,
+                length,
+                range: range.clone(),
+            }
+            .into());
+        }
+        Ok(pointer)
+    }
+
+    fn generation(&self, slot: usize) -> usize {
+        self.generations.get(slot).copied().unwrap_or(0)
+    }
+
+    /// the frame of the local hasn't returned and the block declaring it hasn't ended
+    fn is_live(&self, slot: usize, frame: usize, generation: usize) -> bool {
+        let frame_live = frame == self.frame_id || self.frames.iter().any(|f| f.frame_id == frame);
+        frame_live && slot < self.stack.len() && generation == self.generation(slot)
+    }
+
+    /// an array operand is converted to a pointer, two pointers must point to the same variable
+    /// or array to be subtracted or ordered
+    fn pointer_operands(
+        a: Value,
+        b: Value,
+        range: &Range<usize>,
+    ) -> anyhow::Result<(Value, Value)> {
+        match (a.decayed(), b.decayed()) {
+            (Value::Pointer(a), Value::Pointer(b)) if !a.same_target(&b) => {
+                Err(RuntimeError(format!(
+                    "error at range: {:?}, pointers to different objects can't be compared",
+                    range
+                ))
+                .into())
+            }
+            operands => Ok(operands),
+        }
+    }
+
+    /// check the operand of `GetField` and `SetField`, return the index of the field
+    fn field_index(value: &Value, name: &str, range: &Range<usize>) -> anyhow::Result<usize> {
+        match value {
+            Value::Struct(fields) => fields
+                .borrow()
+                .iter()
+                .position(|(field, _)| field == name)
+                .ok_or_else(|| {
+                    RuntimeError(format!(
+                        "error at range: {:?}, no field {} on struct",
+                        range, name
+                    ))
+                    .into()
+                }),
+            value => Err(RuntimeError(format!(
+                "error at range: {:?}, only struct has fields, found {}",
+                range, value
+            ))
+            .into()),
+        }
+    }
+
     pub fn add_instruction(&mut self, op: OpCode, line_number: Range<usize>) {
-        self.instructions.push(op);
-        self.line_number.push(line_number);
+        let function = &mut self.functions[self.compiler.function];
+        function.instructions.push(op);
+        function.line_number.push(line_number);
     }
 
     pub fn stack(&self) -> &Vec<Value> {
@@ -267,7 +892,11 @@ impl Vm {
         if self.compiler.scope_depth > 0 {
             self.check_if_variable_defined_in_same_scope(&name)?;
             let depth = self.compiler.scope_depth;
-            let local = Local { depth, name };
+            let local = Local {
+                depth,
+                name,
+                constant: None,
+            };
             self.compiler.locals.push(local);
             // println!("local: {:?}", self.compiler.locals);
         } else {
@@ -286,7 +915,7 @@ impl Vm {
             if local.depth <= depth {
                 break;
             }
-            self.add_instruction(Pop, 0..0);
+            self.add_instruction(PopLocal, 0..0);
             self.compiler.locals.pop();
         }
     }
@@ -306,11 +935,83 @@ impl Vm {
         Ok(())
     }
 
+    /// the variable defined last is a `const int` with the value
+    pub(crate) fn define_constant(&mut self, name: SmolStr, value: i32) {
+        if self.compiler.scope_depth > 0 {
+            self.compiler.locals.last_mut().unwrap().constant = Some(value);
+        } else {
+            self.compiler.constants.insert(name, value);
+        }
+    }
+
+    /// the value of the `const int` called `name` in the current scope
+    pub(crate) fn resolve_constant(&self, name: &SmolStr) -> Option<i32> {
+        match self.resolve_local(name) {
+            Some(index) => self.compiler.locals[index].constant,
+            None => self.compiler.constants.get(name).copied(),
+        }
+    }
+
+    /// the value of the global `const int` called `name`, array sizes only name globals
+    pub(crate) fn global_constant(&self, name: &SmolStr) -> Option<i32> {
+        self.compiler.constants.get(name).copied()
+    }
+
     pub(crate) fn resolve_local(&self, name: &SmolStr) -> Option<usize> {
         self.compiler
             .locals
             .iter()
-            .position(|item| &item.name == name)
+            .rposition(|item| &item.name == name)
+    }
+
+    pub(crate) fn declare_function(&mut self, name: SmolStr, params: usize) -> anyhow::Result<usize> {
+        if self.function_table.contains_key(&name) {
+            return Err(RuntimeError(format!("function {} has already defined", name)).into());
+        }
+        let index = self.functions.len();
+        self.functions.push(Function::new(name.clone(), params));
+        self.function_table.insert(name, index);
+        Ok(index)
+    }
+
+    pub(crate) fn resolve_function(&self, name: &SmolStr) -> Option<usize> {
+        self.function_table.get(name).copied()
+    }
+
+    pub(crate) fn function_params(&self, index: usize) -> usize {
+        self.functions[index].params
+    }
+
+    /// redirect the emitted instructions to the body of a declared function,
+    /// function declarations only appear at top level so there is no enclosing local
+    pub(crate) fn begin_function(&mut self, index: usize) {
+        self.compiler.function = index;
+        self.compiler.locals.clear();
+        self.compiler.loops.clear();
+        self.compiler.scope_depth = 0;
+    }
+
+    pub(crate) fn end_function(&mut self) {
+        self.compiler.function = 0;
+        self.compiler.locals.clear();
+        self.compiler.scope_depth = 0;
+    }
+
+    pub(crate) fn set_entry(&mut self, index: usize) {
+        self.entry = Some(index);
+    }
+
+    pub(crate) fn declare_struct(&mut self, decl: &StructDeclaration) -> anyhow::Result<()> {
+        let name = decl.id.value.clone();
+        if self.compiler.structs.contains_key(&name) {
+            return Err(RuntimeError(format!("struct {} has already defined", name)).into());
+        }
+        self.compiler.structs.insert(name, Rc::new(decl.clone()));
+        Ok(())
+    }
+
+    pub(crate) fn resolve_struct(&self, name: &str) -> Option<Rc<StructDeclaration>> {
+        self.compiler.structs.get(name).cloned()
     }
 
     pub fn scope_depth(&self) -> i32 {
@@ -319,12 +1020,13 @@ impl Vm {
 
     pub(crate) fn emit_jump(&mut self, op: OpCode, range: Range<usize>) -> usize {
         self.add_instruction(op, range);
-        self.instructions.len() - 1
+        self.operations().len() - 1
     }
 
     pub(crate) fn patch_jump(&mut self, index: usize) -> anyhow::Result<()> {
-        let new_offset = self.instructions.len() - index;
-        if let JumpIfFalse(ref mut offset) = self.instructions[index] {
+        let instructions = &mut self.functions[self.compiler.function].instructions;
+        let new_offset = instructions.len() - index;
+        if let JumpIfFalse(ref mut offset) = instructions[index] {
             *offset = new_offset;
         } else {
             return Err(RuntimeError(format!(
@@ -337,8 +1039,9 @@ impl Vm {
     }
 
     pub(crate) fn patch_else_jump(&mut self, index: usize) -> anyhow::Result<()> {
-        let new_offset = self.instructions.len() - index - 1;
-        if let Jump(ref mut offset) = self.instructions[index] {
+        let instructions = &mut self.functions[self.compiler.function].instructions;
+        let new_offset = instructions.len() - index;
+        if let Jump(ref mut offset) = instructions[index] {
             *offset = new_offset;
         } else {
             return Err(
@@ -348,8 +1051,138 @@ impl Vm {
         Ok(())
     }
 
+    /// point the `Jump` at `index` to `target`, which is after it
+    pub(crate) fn patch_jump_to(&mut self, index: usize, target: usize) -> anyhow::Result<()> {
+        let instructions = &mut self.functions[self.compiler.function].instructions;
+        if let Jump(ref mut offset) = instructions[index] {
+            *offset = target - index;
+        } else {
+            return Err(
+                RuntimeError(format!("operation should be Jump at index {}", index)).into(),
+            );
+        }
+        Ok(())
+    }
+
+    /// fill the `JumpTable` at `index` with the targets of the values from its low value
+    pub(crate) fn patch_jump_table(
+        &mut self,
+        index: usize,
+        targets: &[usize],
+        default_target: usize,
+    ) -> anyhow::Result<()> {
+        let instructions = &mut self.functions[self.compiler.function].instructions;
+        if let JumpTable(_, ref mut offsets, ref mut default_offset) = instructions[index] {
+            *offsets = targets.iter().map(|target| target - index).collect();
+            *default_offset = default_target - index;
+        } else {
+            return Err(RuntimeError(format!(
+                "operation should be JumpTable at index {}",
+                index
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
     pub(crate) fn emit_loop(&mut self, loop_start: usize, range: Range<usize>) {
-        let offset = self.instructions.len() - loop_start;
+        let offset = self.operations().len() - loop_start;
         self.add_instruction(Loop(offset), range);
     }
+
+    /// start a loop whose body is emitted next, `continue_target` is the index `continue`
+    /// jumps back to if it is already emitted, otherwise call `patch_continue_jumps` later
+    pub(crate) fn begin_loop(&mut self, continue_target: Option<usize>) {
+        self.compiler.loops.push(LoopContext {
+            scope_depth: self.compiler.scope_depth,
+            continue_target,
+            continue_jumps: vec![],
+            break_jumps: vec![],
+            is_switch: false,
+        });
+    }
+
+    /// start a switch whose cases are emitted next, call `end_loop` to patch the `break`s
+    pub(crate) fn begin_switch(&mut self) {
+        self.compiler.loops.push(LoopContext {
+            scope_depth: self.compiler.scope_depth,
+            continue_target: None,
+            continue_jumps: vec![],
+            break_jumps: vec![],
+            is_switch: true,
+        });
+    }
+
+    /// the next instruction is the target of `continue` in the innermost loop
+    pub(crate) fn patch_continue_jumps(&mut self) -> anyhow::Result<()> {
+        let target = self.operations().len();
+        let context = self.compiler.loops.last_mut().unwrap();
+        context.continue_target = Some(target);
+        for index in std::mem::take(&mut context.continue_jumps) {
+            self.patch_else_jump(index)?;
+        }
+        Ok(())
+    }
+
+    /// the next instruction is the target of `break` in the innermost loop or switch
+    pub(crate) fn end_loop(&mut self) -> anyhow::Result<()> {
+        let context = self.compiler.loops.pop().unwrap();
+        for index in context.break_jumps {
+            self.patch_else_jump(index)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn emit_break(&mut self, range: Range<usize>) -> anyhow::Result<()> {
+        let context = self.jump_context("break", range.clone(), |_| true)?;
+        self.emit_loop_exit(context, range.clone());
+        let index = self.emit_jump(Jump(0), range);
+        self.compiler.loops[context].break_jumps.push(index);
+        Ok(())
+    }
+
+    pub(crate) fn emit_continue(&mut self, range: Range<usize>) -> anyhow::Result<()> {
+        let context = self.jump_context("continue", range.clone(), |context| !context.is_switch)?;
+        self.emit_loop_exit(context, range.clone());
+        match self.compiler.loops[context].continue_target {
+            Some(target) => self.emit_loop(target, range),
+            None => {
+                let index = self.emit_jump(Jump(0), range);
+                self.compiler.loops[context].continue_jumps.push(index);
+            }
+        }
+        Ok(())
+    }
+
+    /// the index of the innermost context the jump leaves
+    fn jump_context(
+        &self,
+        keyword: &str,
+        range: Range<usize>,
+        predicate: impl Fn(&LoopContext) -> bool,
+    ) -> anyhow::Result<usize> {
+        match self.compiler.loops.iter().rposition(predicate) {
+            Some(context) => Ok(context),
+            None => Err(RuntimeError(format!(
+                "error at range: {:?}, `{}` outside of a loop",
+                range, keyword
+            ))
+            .into()),
+        }
+    }
+
+    /// pop the locals declared inside the loop body, they stay defined for the rest of the body
+    fn emit_loop_exit(&mut self, context: usize, range: Range<usize>) {
+        let depth = self.compiler.loops[context].scope_depth;
+        let count = self
+            .compiler
+            .locals
+            .iter()
+            .rev()
+            .take_while(|local| local.depth > depth)
+            .count();
+        for _ in 0..count {
+            self.add_instruction(PopLocal, range.clone());
+        }
+    }
 }
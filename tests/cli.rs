@@ -0,0 +1,151 @@
+#[cfg(test)]
+mod cli {
+    use std::{
+        io::Write,
+        process::{Command, Stdio},
+    };
+
+    /// the exit status, stdout and stderr of `cminus args..`, the files are in the cli fixtures
+    fn cminus(args: &[&str]) -> (i32, String, String) {
+        let output = Command::new(env!("CARGO_BIN_EXE_cminus"))
+            .current_dir("tests/fixtures/cli")
+            .args(args)
+            .output()
+            .unwrap();
+        (
+            output.status.code().unwrap(),
+            String::from_utf8(output.stdout).unwrap(),
+            String::from_utf8(output.stderr).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_run() {
+        for args in [&["run", "main.cm"][..], &["run", "--backend", "vm", "main.cm"]].iter() {
+            let (code, stdout, stderr) = cminus(args);
+            assert_eq!(stdout, "0 1 4 9 16 \n");
+            assert_eq!(stderr, "");
+            // the int returned by `main`
+            assert_eq!(code, 3);
+        }
+        let (code, stdout, stderr) = cminus(&["run", "--backend=interp", "runtime_error.cm"]);
+        assert_eq!(code, 70);
+        assert_eq!(stdout, "before\n");
+        assert!(stderr.contains("division by zero"));
+        assert!(stderr.contains("runtime_error.cm:"));
+        assert!(stderr.contains("1 / (a - a)"));
+        let (code, stdout, stderr) = cminus(&["run", "--backend", "vm", "runtime_error.cm"]);
+        assert_eq!(code, 70);
+        assert_eq!(stdout, "before\n");
+        assert!(stderr.contains("attempt to divide by zero"));
+        assert!(stderr.contains("runtime_error.cm:4:11"), "{}", stderr);
+        // the error is located in the file which included another one
+        let (code, stdout, stderr) = cminus(&["run", "--backend", "vm", "include_error.cm"]);
+        assert_eq!((code, stdout.as_str()), (70, "9\n"));
+        assert!(stderr.contains("include_error.cm:5:12"), "{}", stderr);
+        assert!(stderr.contains("index 5 out of bounds for array of length 2"));
+    }
+
+    #[test]
+    fn test_check() {
+        let (code, stdout, stderr) = cminus(&["check", "main.cm"]);
+        assert_eq!((code, stdout.as_str(), stderr.as_str()), (0, "", ""));
+
+        let (code, _, stderr) = cminus(&["check", "type_error.cm"]);
+        assert_eq!(code, 67);
+        assert!(stderr.contains("type_error.cm:3:"));
+        // the program isn't run if it doesn't check
+        let (code, _, _) = cminus(&["run", "type_error.cm"]);
+        assert_eq!(code, 67);
+
+        let (code, _, stderr) = cminus(&["check", "syntax_error.cm"]);
+        assert_eq!(code, 65);
+        assert!(stderr.contains("syntax_error.cm:2:"), "{}", stderr);
+        let (code, _, stderr) = cminus(&["check", "missing.cm"]);
+        assert_eq!(code, 66);
+        assert!(stderr.contains("can't read `missing.cm`"));
+    }
+
+    #[test]
+    fn test_output() {
+        let (code, stdout, _) = cminus(&["tokens", "main.cm"]);
+        assert_eq!(code, 0);
+        let first = stdout.lines().next().unwrap();
+        assert!(first.starts_with("lib.cm:2:1"), "{}", first);
+        assert!(first.ends_with("\"int\""), "{}", first);
+
+        let (code, stdout, _) = cminus(&["ast", "main.cm"]);
+        assert_eq!(code, 0);
+        assert!(stdout.starts_with("Program @"));
+        let (code, stdout, _) = cminus(&["ast", "--format", "json", "main.cm"]);
+        assert_eq!(code, 0);
+        let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+        let declaration = &json["declarations"][0]["FunctionDeclaration"];
+        assert_eq!(declaration["id"]["value"], "square");
+        let (code, stdout, _) = cminus(&["ast", "--format=dot", "main.cm"]);
+        assert_eq!(code, 0);
+        assert!(stdout.starts_with("digraph ast {"));
+        assert!(stdout.contains("n1 -> n2;"));
+
+        let (code, stdout, _) = cminus(&["disasm", "main.cm"]);
+        assert_eq!(code, 0);
+        assert!(stdout.contains("square:\n"));
+        assert!(stdout.contains("MultiplyI32"));
+        assert!(stdout.contains("main.cm:10:5"));
+    }
+
+    #[test]
+    fn test_fmt() {
+        let (code, stdout, _) = cminus(&["fmt", "lib.cm"]);
+        assert_eq!(code, 0);
+        assert_eq!(stdout, "#define N 5\nint square(int x) {\n    return x * x;\n}\n");
+        let (code, stdout, stderr) = cminus(&["fmt", "--check", "lib.cm"]);
+        assert_eq!((code, stdout.as_str()), (1, ""));
+        assert!(stderr.contains("`lib.cm` isn't formatted"));
+        let (code, _, stderr) = cminus(&["fmt", "main.cm", "--check"]);
+        assert_eq!((code, stderr.as_str()), (0, ""));
+
+        let (code, _, stderr) = cminus(&["fmt", "syntax_error.cm"]);
+        assert_eq!(code, 65);
+        assert!(stderr.contains("syntax_error.cm:2:"), "{}", stderr);
+    }
+
+    #[test]
+    fn test_usage() {
+        let (code, stdout, _) = cminus(&["--help"]);
+        assert_eq!(code, 0);
+        assert!(stdout.starts_with("usage: cminus"));
+        let errors = [
+            (&["frob", "main.cm"][..], "unknown command `frob`"),
+            (&[], "a command is expected"),
+            (&["run"], "a file is expected"),
+            (&["run", "main.cm", "lib.cm"], "only one file is expected"),
+            (&["run", "--backend", "jit", "main.cm"], "unknown value `jit` for `--backend`"),
+            (&["run", "main.cm", "--backend"], "`--backend` expects a value"),
+            (&["check", "--format", "json", "main.cm"], "unknown option `--format` for `check`"),
+            (&["repl", "main.cm"], "`repl` doesn't take a file"),
+            (&["fmt", "--backend", "vm", "main.cm"], "unknown option `--backend` for `fmt`"),
+        ];
+        for (args, message) in errors.iter() {
+            let (code, _, stderr) = cminus(args);
+            assert_eq!(code, 64);
+            assert!(stderr.contains(message), "{}", stderr);
+        }
+    }
+
+    #[test]
+    fn test_repl() {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_cminus"))
+            .arg("repl")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let input = "int f(int x) {\n  return x + 1;\n}\nf(1)\nprintln(f(2));\n:quit\nf(3)\n";
+        child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(stdout.ends_with("> ... ... > 2\n> 3\n> "), "{}", stdout);
+    }
+}
@@ -4,15 +4,20 @@ mod emit_op {
 
     use anyhow::{anyhow, Result};
     use tinylang_rs::{
+        host::{CapturedHost, Host, ScriptedHost},
+        interpreter,
         lexer::lex,
         parser::parse::Parser,
         vm::{EmitOperationCode, Vm},
     };
     fn get_vm_after_emit(content: &str) -> Result<Vm> {
+        get_vm_with_host(content, Box::new(CapturedHost::default()))
+    }
+    fn get_vm_with_host(content: &str, host: Box<dyn Host>) -> Result<Vm> {
         let mut lex = lex::Lexer::new(content);
         let list = lex.lex();
         // println!("{:?}", list);
-        let mut parser = Parser::new(list, content);
+        let mut parser = Parser::new(list, "main.cm", content);
         let mut program = match parser.parse_program() {
             Ok(prog) => prog,
             Err(_) => {
@@ -20,10 +25,29 @@ mod emit_op {
                 return Err(anyhow!(string));
             }
         };
-        let mut vm = Vm::new();
+        let mut vm = Vm::with_host(host);
         program.emit(&mut vm)?;
         Ok(vm)
     }
+    fn interpret_output(content: &str) -> Result<String> {
+        let mut lex = lex::Lexer::new(content);
+        let list = lex.lex();
+        let mut parser = Parser::new(list, "main.cm", content);
+        let mut program = parser
+            .parse_program()
+            .map_err(|_| anyhow!(parser.error_reporter.emit_string()))?;
+        let host = CapturedHost::default();
+        interpreter::interpret(&mut program, Box::new(host.clone()))
+            .map_err(|error| anyhow!(error.to_string()))?;
+        Ok(host.output())
+    }
+    fn assert_same_output_as_interpreter(content: &str) -> Result<()> {
+        let host = CapturedHost::default();
+        let mut vm = get_vm_with_host(content, Box::new(host.clone()))?;
+        vm.exec()?;
+        assert_eq!(host.output(), interpret_output(content)?);
+        Ok(())
+    }
     #[test]
     fn test_global_var() -> Result<()> {
         use tinylang_rs::vm::op_code::OpCode::*;
@@ -89,6 +113,845 @@ mod emit_op {
             ]
         );
 
+        // negation wraps like the other arithmetic operations
+        let content = "int m = -2147483647 - 1; void main() { println(-m, -(m + 1)); }";
+        let host = CapturedHost::default();
+        let mut vm = get_vm_with_host(content, Box::new(host.clone()))?;
+        vm.exec()?;
+        assert_eq!(host.output(), "-21474836482147483647\n");
+        assert_same_output_as_interpreter(content)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_call_expression() -> Result<()> {
+        use tinylang_rs::vm::op_code::OpCode::*;
+
+        let content = r#"
+        int add(int a, int b) {
+            return a + b;
+        }
+        void main() {
+            add(1, 2);
+        }
+        "#;
+        let vm = get_vm_after_emit(&content)?;
+        assert_eq!(
+            vm.functions()[1].instructions(),
+            &vec![GetLocal(0), GetLocal(1), AddI32, Return, Nil, Return]
+        );
+        assert_eq!(
+            vm.functions()[2].instructions(),
+            &vec![ConstantI32(1), ConstantI32(2), Call(1), Pop, Nil, Return]
+        );
+
+        let content = r#"
+        int count = 0;
+        bool isEven(int n) {
+            count = count + 1;
+            if (n == 0) {
+                return true;
+            }
+            return isOdd(n - 1);
+        }
+        bool isOdd(int n) {
+            if (n == 0) {
+                return false;
+            }
+            return isEven(n - 1);
+        }
+        void main() {
+            println(isEven(10), isOdd(7), isEven(3));
+            println(count);
+        }
+        "#;
+        assert_same_output_as_interpreter(content)?;
+
+        let content = "int f(int n) { return f(n + 1); } void main() { f(0); }";
+        let mut vm = get_vm_after_emit(content)?;
+        let error = vm.exec().unwrap_err().to_string();
+        assert!(error.contains("22..30, stack overflow"), "{}", error);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fixtures_with_function() -> Result<()> {
+        for fixture in &[
+            "tests/fixtures/interpreter/fib.txt",
+            "tests/fixtures/interpreter/bubble_sort.txt",
+            "tests/fixtures/interpreter/insert_sort.txt",
+            "benches/corpus/fibonacci.cm",
+        ] {
+            let content = read_to_string(path::Path::new(fixture))?;
+            assert_same_output_as_interpreter(&content)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_array() -> Result<()> {
+        use tinylang_rs::vm::op_code::OpCode::*;
+
+        let content = r#"
+        void main() {
+            int a[3] = {1, 2};
+            a[2] = a[0];
+        }
+        "#;
+        let vm = get_vm_after_emit(&content)?;
+        assert_eq!(
+            vm.functions()[1].instructions(),
+            &vec![
+                ConstantI32(0),
+                ConstantI32(1),
+                ConstantI32(2),
+                NewArray(3, 2),
+                GetLocal(0),
+                ConstantI32(2),
+                GetLocal(0),
+                ConstantI32(0),
+                GetIndex,
+                SetIndex,
+                Pop,
+                PopLocal,
+                Nil,
+                Return
+            ]
+        );
+
+        let content = r#"
+        bool flags[4];
+        void flip(bool f[], int i) {
+            f[i] = true;
+        }
+        void main() {
+            int a[5] = {5, 4, 3};
+            flip(flags, 2);
+            println(a, flags, a[1] + a[4]);
+        }
+        "#;
+        assert_same_output_as_interpreter(content)?;
+
+        let content = r#"
+        void main() {
+            int a[2];
+            a[2] = 1;
+        }
+        "#;
+        let mut vm = get_vm_after_emit(&content)?;
+        let error = vm.exec().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "error at range: 57..61, index 2 out of bounds for array of length 2"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_input() -> Result<()> {
+        let content = r#"
+        void main() {
+            int a;
+            a = input();
+            println(a * input());
+        }
+        "#;
+        let host = CapturedHost::default();
+        let mut vm = get_vm_with_host(
+            content,
+            Box::new(ScriptedHost::new(vec![6, 7], host.clone())),
+        )?;
+        vm.exec()?;
+        assert_eq!(host.output(), "42\n");
+
+        let mut vm = get_vm_with_host(content, Box::new(ScriptedHost::new(vec![6], host)))?;
+        assert!(vm
+            .exec()
+            .unwrap_err()
+            .to_string()
+            .contains("failed to read an int from input"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_loops() -> Result<()> {
+        let content = r#"
+        void main() {
+            int i;
+            int sum;
+            sum = 0;
+            for (i = 0; i < 10; i = i + 1) {
+                int doubled;
+                doubled = i * 2;
+                if (i == 2) {
+                    continue;
+                }
+                if (doubled > 12) {
+                    int unused = 1;
+                    break;
+                }
+                sum = sum + doubled;
+            }
+            println(i, sum);
+            i = 0;
+            do {
+                i = i + 1;
+                if (i < 3) continue;
+                print(i);
+            } while (i < 5);
+            println();
+            while (true) {
+                i = i - 1;
+                if (i == 2) break;
+            }
+            for (;;) {
+                i = i + 1;
+                if (i > 3) break;
+            }
+            println(i);
+        }
+        "#;
+        let host = CapturedHost::default();
+        let mut vm = get_vm_with_host(content, Box::new(host.clone()))?;
+        vm.exec()?;
+        assert_eq!(host.output(), "738\n345\n4\n");
+        assert_same_output_as_interpreter(content)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_string() -> Result<()> {
+        let content = r#"
+        void upper(char s[]) {
+            int i = 0;
+            while (s[i] != '\0') {
+                if (s[i] >= 'a') {
+                    if (s[i] <= 'z') {
+                        s[i] = 'A';
+                    }
+                }
+                i = i + 1;
+            }
+        }
+        void main() {
+            char name[8] = "ab\tc";
+            char c = 'x';
+            print("sum = ", 1 + 2, "\n");
+            upper(name);
+            println(name, c, '\'');
+            name[1] = '\0';
+            println("[", name, "]");
+        }
+        "#;
+        let host = CapturedHost::default();
+        let mut vm = get_vm_with_host(content, Box::new(host.clone()))?;
+        vm.exec()?;
+        assert_eq!(host.output(), "sum = 3\nAA\tAx'\n[A]\n");
+        assert_same_output_as_interpreter(content)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_short_circuit() -> Result<()> {
+        use tinylang_rs::vm::op_code::OpCode::*;
+
+        let content = r#"
+        bool a = true || false && !true;
+        void main() {}
+        "#;
+        let vm = get_vm_after_emit(&content)?;
+        assert_eq!(
+            vm.operations(),
+            &vec![
+                ConstantBoolean(true),
+                JumpIfFalse(2),
+                Jump(7),
+                Pop,
+                ConstantBoolean(false),
+                JumpIfFalse(4),
+                Pop,
+                ConstantBoolean(true),
+                Not,
+                DefineGlobal("a".into()),
+                Pop
+            ]
+        );
+
+        let content = r#"
+        int calls = 0;
+        bool touch(bool result) {
+            calls = calls + 1;
+            return result;
+        }
+        void main() {
+            int a[3] = {1, 2, 3};
+            int i = 3;
+            if (i < 3 && a[i] > 0) {
+                println("unreachable");
+            }
+            println(touch(false) && touch(true), touch(true) || touch(false), calls);
+            println(!touch(false) && !(i == 3), !false || touch(true), calls);
+        }
+        "#;
+        let host = CapturedHost::default();
+        let mut vm = get_vm_with_host(content, Box::new(host.clone()))?;
+        vm.exec()?;
+        assert_eq!(host.output(), "falsetrue2\nfalsetrue3\n");
+        assert_same_output_as_interpreter(content)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_dimensional_array() -> Result<()> {
+        use tinylang_rs::vm::op_code::OpCode::*;
+
+        let content = r#"
+        void main() {
+            int m[2][2] = {{1}};
+            m[1][0] = m[0][0];
+        }
+        "#;
+        let vm = get_vm_after_emit(&content)?;
+        assert_eq!(
+            vm.functions()[1].instructions(),
+            &vec![
+                Nil,
+                ConstantI32(0),
+                ConstantI32(1),
+                NewArray(2, 1),
+                ConstantI32(0),
+                NewArray(2, 0),
+                NewArray(2, 2),
+                GetLocal(0),
+                ConstantI32(1),
+                GetIndex,
+                ConstantI32(0),
+                GetLocal(0),
+                ConstantI32(0),
+                GetIndex,
+                ConstantI32(0),
+                GetIndex,
+                SetIndex,
+                Pop,
+                PopLocal,
+                Nil,
+                Return
+            ]
+        );
+
+        let content = r#"
+        int identity[2][2] = {{1, 0}, {0, 1}};
+        void multiply(int a[][2], int b[][2], int c[][2]) {
+            int i;
+            int j;
+            int k;
+            for (i = 0; i < 2; i++) {
+                for (j = 0; j < 2; j++) {
+                    for (k = 0; k < 2; k++) {
+                        c[i][j] += a[i][k] * b[k][j];
+                    }
+                }
+            }
+        }
+        int sum(int row[]) {
+            return row[0] + row[1];
+        }
+        void main() {
+            int m[2][2] = {{1, 2}, {3, 4}};
+            int c[2][2];
+            int cube[2][2][2];
+            char names[2][4] = {"ab", "cd"};
+            multiply(m, identity, c);
+            println(c, ' ', sum(c[1]));
+            cube[1][0][1] = 5;
+            cube[1][0][1]++;
+            println(cube, cube[1]);
+            names[1][0] = 'x';
+            println(names, names[1]);
+        }
+        "#;
+        let host = CapturedHost::default();
+        let mut vm = get_vm_with_host(content, Box::new(host.clone()))?;
+        vm.exec()?;
+        assert_eq!(
+            host.output(),
+            "[[1, 2], [3, 4]] 7\n[[[0, 0], [0, 0]], [[0, 6], [0, 0]]][[0, 6], [0, 0]]\n\
+            [ab, xd]xd\n"
+        );
+        assert_same_output_as_interpreter(content)?;
+
+        let content = r#"
+        void main() {
+            int m[2][3];
+            m[1][3] = 1;
+        }
+        "#;
+        let mut vm = get_vm_after_emit(&content)?;
+        let error = vm.exec().unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("index 3 out of bounds for array of length 3"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_compound_assignment() -> Result<()> {
+        use tinylang_rs::vm::op_code::OpCode::*;
+
+        let content = r#"
+        void main() {
+            int a[2];
+            a[1] *= 3;
+        }
+        "#;
+        let vm = get_vm_after_emit(&content)?;
+        assert!(vm.functions()[1].instructions().ends_with(&[
+            GetLocal(0),
+            ConstantI32(1),
+            Duplicate(2),
+            GetIndex,
+            ConstantI32(3),
+            MultiplyI32,
+            SetIndex,
+            Pop,
+            PopLocal,
+            Nil,
+            Return
+        ]));
+
+        let content = r#"
+        int calls = 0;
+        int next() {
+            calls++;
+            return calls - 1;
+        }
+        void main() {
+            int a[3] = {10, 20, 30};
+            int i = 5;
+            int j;
+            a[next()] += 5;
+            a[next()] -= a[0];
+            a[next()]++;
+            println(a, calls);
+            i *= 2 + 1;
+            i /= 4;
+            i %= 2;
+            println(i);
+            j = i++ + ++i;
+            println(i, ' ', j, ' ', i--, ' ', --i, ' ', -a[2]--, ' ', a[2]);
+            for (j = 0; j < 3; j++) {
+                a[j] += j;
+            }
+            println(a);
+        }
+        "#;
+        let host = CapturedHost::default();
+        let mut vm = get_vm_with_host(content, Box::new(host.clone()))?;
+        vm.exec()?;
+        assert_eq!(
+            host.output(),
+            "[15, 5, 31]3\n1\n3 4 3 1 -31 30\n[15, 6, 32]\n"
+        );
+        assert_same_output_as_interpreter(content)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_bitwise_operators() -> Result<()> {
+        let content = r#"
+        void main() {
+            int a = 13;
+            int b = 6;
+            println(a % 4, ' ', a & b, ' ', a | b, ' ', a ^ b, ' ', ~a);
+            println(1 << 4 + 1, ' ', 256 >> 2 * 2, ' ', -17 % 5, ' ', -16 >> 2);
+            println(1 | 2 ^ 3 & 5, ' ', 6 & 3 << 1, ' ', a % 4 * 2, ' ', (a & b) < 5);
+        }
+        "#;
+        let host = CapturedHost::default();
+        let mut vm = get_vm_with_host(content, Box::new(host.clone()))?;
+        vm.exec()?;
+        assert_eq!(host.output(), "1 4 15 11 -14\n32 16 -2 -4\n3 6 2 true\n");
+        assert_same_output_as_interpreter(content)?;
+
+        let content = r#"
+        void main() {
+            int zero = 0;
+            println(1 % zero);
+        }
+        "#;
+        let mut vm = get_vm_after_emit(content)?;
+        let error = vm.exec().unwrap_err().to_string();
+        assert!(error.contains("attempt to calculate the remainder with a divisor of zero"));
+        let error = interpret_output(content).unwrap_err().to_string();
+        assert!(error.contains("attempt to calculate the remainder with a divisor of zero"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_struct() -> Result<()> {
+        use tinylang_rs::vm::op_code::OpCode::*;
+
+        let content = r#"
+        struct P {
+            int x;
+            bool ok;
+        };
+        void main() {
+            struct P p;
+            p.x = 1;
+        }
+        "#;
+        let vm = get_vm_after_emit(&content)?;
+        assert_eq!(
+            vm.functions()[1].instructions(),
+            &vec![
+                ConstantI32(0),
+                ConstantBoolean(false),
+                NewStruct(vec!["x".into(), "ok".into()]),
+                GetLocal(0),
+                ConstantI32(1),
+                SetField("x".into()),
+                Pop,
+                PopLocal,
+                Nil,
+                Return
+            ]
+        );
+
+        let content = r#"
+        struct Point {
+            int x;
+            int y;
+        };
+        struct Shape {
+            struct Point points[2];
+            char name[4];
+            struct Point origin;
+        };
+        struct Point moved(struct Point p) {
+            p.x++;
+            p.y += 10;
+            return p;
+        }
+        void main() {
+            struct Point a;
+            struct Point b;
+            struct Shape s;
+            struct Shape t;
+            a.x = 1;
+            b = a;
+            b.x = 2;
+            println(a, b, moved(a), a);
+            s.points[1] = a;
+            s.points[1].y = 3;
+            s.name[0] = 'o';
+            s.origin.x = -1;
+            t = s;
+            t.name[0] = 't';
+            t.points[0].x = 9;
+            println(s, ' ', t);
+            println(s.points, s.name, s.origin.x * s.points[1].y);
+        }
+        "#;
+        let host = CapturedHost::default();
+        let mut vm = get_vm_with_host(content, Box::new(host.clone()))?;
+        vm.exec()?;
+        assert_eq!(
+            host.output(),
+            "{1, 0}{2, 0}{2, 10}{1, 0}\n{[{0, 0}, {1, 3}], o, {-1, 0}} \
+            {[{9, 0}, {1, 3}], t, {-1, 0}}\n[{0, 0}, {1, 3}]o-3\n"
+        );
+        assert_same_output_as_interpreter(content)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_pointer() -> Result<()> {
+        use tinylang_rs::vm::op_code::OpCode::*;
+        let vm = get_vm_after_emit("void main() { int x; int *p = &x; *p += 1; }")?;
+        assert_eq!(
+            vm.functions()[1].instructions(),
+            &vec![
+                ConstantI32(0),
+                AddressOfLocal(0),
+                GetLocal(1),
+                Duplicate(1),
+                Dereference,
+                ConstantI32(1),
+                AddI32,
+                SetDereference,
+                Pop,
+                PopLocal,
+                PopLocal,
+                Nil,
+                Return
+            ]
+        );
+
+        let content = r#"
+        int g;
+        void swap(int *a, int *b) {
+            int t = *a;
+            *a = *b;
+            *b = t;
+        }
+        int sum(int *p, int n) {
+            int s = 0;
+            int *end = p + n;
+            while (p != end) {
+                s += *p++;
+            }
+            return s;
+        }
+        void main() {
+            int a[4] = {1, 2, 3, 4};
+            int m[2][2] = {{1, 2}, {3, 4}};
+            int x = 5;
+            int *p = a;
+            int **pp = &p;
+            swap(&x, &g);
+            println(x, ' ', g);
+            swap(&a[0], a + 3);
+            println(a, sum(a, 4), sum(a + 1, 2));
+            ++*p;
+            **pp += 10;
+            p = &a[3];
+            println(*p--, *p, p - a, p > a, p == a + 2, &a[2] - p);
+            *(*(m + 1) + 1) = 9;
+            println(m);
+        }
+        "#;
+        let host = CapturedHost::default();
+        let mut vm = get_vm_with_host(content, Box::new(host.clone()))?;
+        vm.exec()?;
+        assert_eq!(
+            host.output(),
+            "0 5\n[4, 2, 3, 1]105\n132truetrue0\n[[1, 2], [3, 9]]\n"
+        );
+        assert_same_output_as_interpreter(content)?;
+
+        // the local of a block dangles once the block ends, even if another local takes its slot
+        let dangling = [
+            "void main() { int *p; { int x = 5; p = &x; } println(*p); }",
+            "void main() { int *p; { int x = 5; p = &x; } { int y = 9; println(*p); } }",
+            "void main() { int *p; while (true) { int x; p = &x; break; } *p = 1; }",
+        ];
+        for content in dangling.iter() {
+            let mut vm = get_vm_after_emit(content)?;
+            let error = vm.exec().unwrap_err().to_string();
+            assert!(error.contains("the local pointed to has gone out of scope"), "{}", error);
+            let error = interpret_output(content).unwrap_err().to_string();
+            assert!(error.contains("has gone out of scope"), "{}", error);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_switch() -> Result<()> {
+        use tinylang_rs::vm::op_code::OpCode::*;
+        let vm = get_vm_after_emit(
+            "void main() { int x; switch (x) { case 1: x = 1; case 3: break; case 2: default: } }",
+        )?;
+        assert_eq!(
+            vm.functions()[1].instructions(),
+            &vec![
+                ConstantI32(0),
+                GetLocal(0),
+                JumpTable(1, vec![1, 5, 4], 5),
+                ConstantI32(1),
+                SetLocal(0),
+                Pop,
+                Jump(1),
+                PopLocal,
+                Nil,
+                Return
+            ]
+        );
+
+        let content = r#"
+        int name(int n) {
+            switch (n) {
+                case 0:
+                    return 10;
+                case 1:
+                case 2:
+                    n = n * 100;
+                case 3:
+                    return n + 3;
+                case 4:
+                    break;
+                default:
+                    return -1;
+            }
+            return 4;
+        }
+        void main() {
+            int i;
+            int total = 0;
+            for (i = -1; i < 7; i++) {
+                print(name(i), ' ');
+                switch (i * 1000) {
+                    case -1000:
+                        continue;
+                    case 2000: {
+                        int t = i;
+                        total += t;
+                        break;
+                    }
+                    default:
+                        total += 1;
+                    case 5000:
+                        total += 100;
+                }
+                if (i == 5) {
+                    break;
+                }
+            }
+            println(total);
+            switch (total) {
+                case 1:
+            }
+            switch (total) {
+                default:
+                    println(total + 1);
+            }
+        }
+        "#;
+        let host = CapturedHost::default();
+        let mut vm = get_vm_with_host(content, Box::new(host.clone()))?;
+        vm.exec()?;
+        assert_eq!(host.output(), "-1 10 103 203 6 4 -1 506\n507\n");
+        assert_same_output_as_interpreter(content)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_conditional() -> Result<()> {
+        use tinylang_rs::vm::op_code::OpCode::*;
+        let vm = get_vm_after_emit("void main() { bool b; print(b ? 1 : 2); }")?;
+        assert_eq!(
+            vm.functions()[1].instructions(),
+            &vec![
+                ConstantBoolean(false),
+                GetLocal(0),
+                JumpIfFalse(4),
+                Pop,
+                ConstantI32(1),
+                Jump(3),
+                Pop,
+                ConstantI32(2),
+                Print(1),
+                Pop,
+                PopLocal,
+                Nil,
+                Return
+            ]
+        );
+
+        let content = r#"
+        int calls;
+        int count(int n) {
+            calls++;
+            return n;
+        }
+        int sign(int n) {
+            return n > 0 ? 1 : n < 0 ? -1 : 0;
+        }
+        void main() {
+            int a[3] = {1, 2, 3};
+            int b[3] = {4, 5, 6};
+            int i;
+            for (i = -1; i < 2; i++) {
+                print(sign(i), ' ', *((i == 0 ? a : b) + i + 1), ' ');
+            }
+            println(i > 0 ? count(1) : count(2), calls);
+        }
+        "#;
+        let host = CapturedHost::default();
+        let mut vm = get_vm_with_host(content, Box::new(host.clone()))?;
+        vm.exec()?;
+        assert_eq!(host.output(), "-1 4 0 2 1 6 11\n");
+        assert_same_output_as_interpreter(content)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_function_prototype() -> Result<()> {
+        let content = r#"
+        bool odd(int n);
+        int square(int n);
+        int g = square(3);
+        bool even(int n) {
+            if (n == 0) {
+                return true;
+            }
+            return odd(n - 1);
+        }
+        bool odd(int n) {
+            if (n == 0) {
+                return false;
+            }
+            return even(n - 1);
+        }
+        int square(int n) {
+            return n * n;
+        }
+        void main() {
+            println(even(10), odd(7), even(3), g);
+        }
+        "#;
+        let host = CapturedHost::default();
+        let mut vm = get_vm_with_host(content, Box::new(host.clone()))?;
+        vm.exec()?;
+        assert_eq!(host.output(), "truetruefalse9\n");
+        assert_same_output_as_interpreter(content)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_const() -> Result<()> {
+        use tinylang_rs::vm::op_code::OpCode::*;
+        let vm =
+            get_vm_after_emit("const int N = -2; void main() { const int m = ~N; print(m); }")?;
+        assert_eq!(
+            vm.functions()[1].instructions(),
+            &vec![ConstantI32(1), ConstantI32(1), Print(1), Pop, PopLocal, Nil, Return]
+        );
+        let vm = get_vm_after_emit(
+            "const int N = 3; const int M = N * 2 + 1; void main() { print(M, M / (N - 3)); }",
+        )?;
+        assert!(vm.functions()[1].instructions().starts_with(&[
+            ConstantI32(7),
+            ConstantI32(7),
+            ConstantI32(3),
+            ConstantI32(3),
+            SubtractI32,
+            DivideI32,
+        ]));
+
+        let content = r#"
+        const int N = 3;
+        const int SIZE = N;
+        const int total[SIZE] = {1, 2, 3};
+        int count = N;
+        void main() {
+            int i;
+            int a[N];
+            const int step = count;
+            for (i = 0; i < SIZE; i++) {
+                a[i] = total[i] * step;
+            }
+            {
+                int N = 10;
+                println(a, N, SIZE);
+            }
+        }
+        "#;
+        let host = CapturedHost::default();
+        let mut vm = get_vm_with_host(content, Box::new(host.clone()))?;
+        vm.exec()?;
+        assert_eq!(host.output(), "[3, 6, 9]103\n");
+        assert_same_output_as_interpreter(content)?;
         Ok(())
     }
 }
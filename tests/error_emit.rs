@@ -0,0 +1,42 @@
+#[cfg(test)]
+mod error_emit {
+    use tinylang_rs::{
+        error_emit::{ErrorReporter, FileSpan},
+        lexer::lex,
+        parser::parse::Parser,
+    };
+
+    #[test]
+    fn test_file_name() {
+        let content = "void main() { int a }";
+        let mut lex = lex::Lexer::new(content);
+        let list = lex.lex();
+        let mut parser = Parser::new(list, "src/sort.cm", content);
+        assert!(parser.parse_program().is_err());
+        let error = parser.error_reporter.emit_string();
+        assert!(error.contains("src/sort.cm:1:"));
+        assert!(!error.contains("main.cm"));
+    }
+
+    #[test]
+    fn test_multiple_files() {
+        let mut reporter = ErrorReporter::new();
+        let main = reporter.add_file("main.cm", "void main() { f(1); }".to_string());
+        let lib = reporter.add_file("lib.cm", "void f() {}".to_string());
+        assert_ne!(main, lib);
+        assert_eq!(reporter.file_id("lib.cm"), Some(lib));
+        assert_eq!(reporter.file_name(main), Some("main.cm"));
+
+        reporter.add_diagnostic(
+            FileSpan::new(main, 14..18),
+            "function `f` takes 0 arguments but 1 were supplied".to_string(),
+        );
+        reporter.add_secondary_diagnostic(FileSpan::new(lib, 5..6), "defined here".to_string());
+        reporter.add_diagnostic(FileSpan::new(lib, 0..4), "unused function".to_string());
+        assert_eq!(reporter.len(), 2);
+        let error = reporter.emit_string();
+        assert!(error.contains("main.cm:1:15"));
+        assert!(error.contains("lib.cm:1:6"));
+        assert!(error.contains("lib.cm:1:1"));
+    }
+}
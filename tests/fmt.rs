@@ -0,0 +1,156 @@
+#[cfg(test)]
+mod fmt {
+    use tinylang_rs::{fmt::Formatter, host::CapturedHost, interpreter, lexer::lex, parser};
+
+    fn format(source: &str) -> String {
+        let mut formatter = Formatter::new("main.cm", source);
+        let formatted = formatter
+            .format()
+            .unwrap_or_else(|_| panic!("{}", formatter.error_reporter.emit_string()));
+        // formatting is idempotent
+        let mut formatter = Formatter::new("main.cm", &formatted);
+        assert_eq!(formatter.format().unwrap(), formatted);
+        formatted
+    }
+
+    fn run(source: &str) -> String {
+        let tokens = lex::Lexer::new(source).lex();
+        let mut parser = parser::parse::Parser::new(tokens, "main.cm", source);
+        let mut program = parser.parse_program().unwrap();
+        let host = CapturedHost::default();
+        interpreter::interpret(&mut program, Box::new(host.clone())).unwrap();
+        host.output()
+    }
+
+    #[test]
+    fn test_layout() {
+        let source = r#"struct point{int x;int y;};
+int m [2][2]={{1,2},{3,4}}; char s[6]="hi\n";
+int *p;
+int f(int a[],int g[][2],int*q);
+void main(void){int x=1;
+if(x)x=2;else if(x>1){x=3;}else x=4;
+while((x<10))x++;
+for(;;){break;}
+do x--;while(x>0);
+switch(x){case -1:x=1;break;default:x=0;}
+}
+"#;
+        let expected = r#"struct point {
+    int x;
+    int y;
+};
+int m[2][2] = {{1, 2}, {3, 4}};
+char s[6] = "hi\n";
+int *p;
+int f(int a[], int g[][2], int *q);
+void main(void) {
+    int x = 1;
+    if (x)
+        x = 2;
+    else if (x > 1) {
+        x = 3;
+    } else
+        x = 4;
+    while (x < 10)
+        x++;
+    for (;;) {
+        break;
+    }
+    do
+        x--;
+    while (x > 0);
+    switch (x) {
+    case -1:
+        x = 1;
+        break;
+    default:
+        x = 0;
+    }
+}
+"#;
+        assert_eq!(format(source), expected);
+        assert_eq!(format("void main() { }"), "void main() {}\n");
+    }
+
+    #[test]
+    fn test_parentheses() {
+        let cases = [
+            ("((a[0] + 1) * 2) - (3 - (4 + 5))", "(a[0] + 1) * 2 - (3 - (4 + 5))"),
+            ("(a * b) + (c % d) << (1)", "a * b + c % d << 1"),
+            ("(a | b) & (c ^ d)", "(a | b) & (c ^ d)"),
+            ("(a < b) == (c > d)", "(a < b) == (c > d)"),
+            ("!(a && (b || c)) || (d && e)", "!(a && (b || c)) || d && e"),
+            ("-(-a) + -(--a) - (-a)", "-(-a) + -(--a) - -a"),
+            ("a ? b : (c ? d : e)", "a ? b : c ? d : e"),
+            ("(a ? b : c) ? d : (a = e)", "(a ? b : c) ? d : (a = e)"),
+            ("*(p + 1) * -(*p)", "*(p + 1) * -*p"),
+            ("a = (b += (c))", "a = b += c"),
+        ];
+        for (expression, expected) in cases.iter() {
+            let source = format!("void main() {{\n    {};\n}}\n", expression);
+            let formatted = format(&source);
+            assert_eq!(formatted, format!("void main() {{\n    {};\n}}\n", expected));
+        }
+    }
+
+    #[test]
+    fn test_comments() {
+        let source = r#"/* header
+   comment */
+#include "lib.cm"
+int a; /* a */
+
+
+/* f */
+int f(int x) {
+  int y; /* y */
+
+  /* before */ y = x /* inside */ + 1; /* after */
+  return y;
+  /* last */
+}
+/* end */
+"#;
+        let expected = r#"/* header
+   comment */
+#include "lib.cm"
+int a; /* a */
+
+/* f */
+int f(int x) {
+    int y; /* y */
+
+    /* before */
+    /* inside */
+    y = x + 1; /* after */
+    return y;
+    /* last */
+}
+/* end */
+"#;
+        assert_eq!(format(source), expected);
+    }
+
+    #[test]
+    fn test_same_program() {
+        let source = r#"int fib(int n) { if ((n < 2)) return n;
+    return (fib(n - 1)) + fib((n - 2)); }
+void main() {
+    int i; char s[4] = "a\tb"; i = 0;
+    while (i < 10) { print(fib(i), " "); i += (1); }
+    println(s, ')', 10 - (3 - 2), (1 - 3) - 2, -(-1), 2 * (3 + 4) % 5);
+}
+"#;
+        let formatted = format(source);
+        assert!(formatted.contains("return fib(n - 1) + fib(n - 2);"), "{}", formatted);
+        assert_eq!(run(&formatted), run(source));
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        let mut formatter = Formatter::new("main.cm", "int f( {");
+        assert!(formatter.format().is_err());
+        assert!(formatter.error_reporter.emit_string().contains("main.cm:1:8"));
+    }
+}
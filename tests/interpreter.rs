@@ -0,0 +1,173 @@
+#[cfg(test)]
+mod interpreter {
+    use tinylang_rs::{
+        host::{CapturedHost, Host, ScriptedHost},
+        interpreter::{self, error::RuntimeErrorKind, RuntimeError},
+        lexer::lex,
+        parser::parse::Parser,
+        MAX_CALL_DEPTH,
+    };
+    fn run(content: &str) -> Result<String, RuntimeError> {
+        run_with_input(content, &[])
+    }
+    fn run_with_input(content: &str, input: &[i32]) -> Result<String, RuntimeError> {
+        let mut lex = lex::Lexer::new(content);
+        let list = lex.lex();
+        let mut parser = Parser::new(list, "main.cm", content);
+        let mut program = parser.parse_program().unwrap();
+        let host = CapturedHost::default();
+        let scripted: Box<dyn Host> = Box::new(ScriptedHost::new(input.to_vec(), host.clone()));
+        interpreter::interpret(&mut program, scripted).map(|_| host.output())
+    }
+
+    #[test]
+    fn test_index_out_of_bounds() {
+        let source = r#"
+        int get(int a[], int i) {
+            return a[i];
+        }
+        void main() {
+            int a[2];
+            println(get(a, 1));
+            println(get(a, 2));
+        }
+        "#;
+        let error = run(source).unwrap_err();
+        assert_eq!(error.kind, RuntimeErrorKind::IndexOutOfBounds);
+        assert_eq!(&source[error.start..error.end], "a[i]");
+        let backtrace = error
+            .backtrace
+            .iter()
+            .map(|frame| (frame.function.as_str(), &source[frame.start..frame.end]))
+            .collect::<Vec<_>>();
+        assert_eq!(backtrace, vec![("get", "get(a, 2)"), ("main", "main")]);
+
+        let report = error.report("main.cm", source).emit_string();
+        assert!(report.contains("runtime error: index out of bounds"));
+        assert!(report.contains("index 2 out of bounds for array `a` of length 2"));
+        assert!(report.contains("in the call to `get`"));
+    }
+
+    #[test]
+    fn test_runtime_error_kind() {
+        let error = run("void main() { int a; a = 1 / (a - a); }").unwrap_err();
+        assert_eq!(error.kind, RuntimeErrorKind::DivisionByZero);
+
+        let error = run("void main() { a = 1; }").unwrap_err();
+        assert_eq!(error.kind, RuntimeErrorKind::UndefinedVariable);
+        assert_eq!(error.message, "the variable `a` can't be found in this scope");
+
+        let error = run("void main() { int a; int a; }").unwrap_err();
+        assert_eq!(error.kind, RuntimeErrorKind::Redeclaration);
+
+        let error = run("void main() { f(); }").unwrap_err();
+        assert_eq!(error.kind, RuntimeErrorKind::UndefinedFunction);
+
+        let error = run("int f(int a) { return a; } void main() { f(true); }").unwrap_err();
+        assert_eq!(error.kind, RuntimeErrorKind::ArgumentMismatch);
+
+        let error = run("void main() { if (1) {} }").unwrap_err();
+        assert_eq!(error.kind, RuntimeErrorKind::TypeMismatch);
+
+        let error = run("int a;").unwrap_err();
+        assert_eq!(error.kind, RuntimeErrorKind::MissingMain);
+    }
+
+    #[test]
+    fn test_stack_overflow() {
+        let source = r#"
+        int f(int n) {
+            if (n == 0)
+                return 0;
+            return f(n - 1) + 1;
+        }
+        int main() {
+            println(f(900));
+            return f(100000);
+        }
+        "#;
+        // the test threads don't have the stack for `MAX_CALL_DEPTH` calls in debug builds
+        let error = std::thread::Builder::new()
+            .stack_size(256 << 20)
+            .spawn(move || run(source).unwrap_err())
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(error.kind, RuntimeErrorKind::StackOverflow);
+        assert_eq!(&source[error.start..error.end], "f(n - 1)");
+        assert_eq!(error.backtrace.len(), MAX_CALL_DEPTH + 1);
+        let outermost = &error.backtrace[MAX_CALL_DEPTH - 1];
+        assert_eq!(&source[outermost.start..outermost.end], "f(100000)");
+
+        let report = error.report("main.cm", source).emit_string();
+        assert!(report.contains("runtime error: stack overflow"));
+        assert!(report.contains("in the call to `f` (9999 times)"), "{}", report);
+    }
+
+    #[test]
+    fn test_boolean_array_initializer() {
+        let output = run(
+            r#"
+        void main() {
+            bool a[3] = {true, false};
+            println(a);
+        }
+        "#,
+        );
+        assert_eq!(output.unwrap(), "[true, false, false]\n");
+    }
+
+    #[test]
+    fn test_input() {
+        let source = r#"
+        void main() {
+            int n;
+            int sum;
+            n = input();
+            while (n > 0) {
+                sum = sum + input();
+                n = n - 1;
+            }
+            println(sum);
+        }
+        "#;
+        assert_eq!(run_with_input(source, &[3, 1, 2, 3]).unwrap(), "6\n");
+
+        let error = run_with_input(source, &[3, 1]).unwrap_err();
+        assert_eq!(error.kind, RuntimeErrorKind::InvalidInput);
+        assert_eq!(&source[error.start..error.end], "input()");
+    }
+
+    #[test]
+    fn test_pointer_errors() {
+        let source = r#"
+        int *local() {
+            int x = 1;
+            return &x;
+        }
+        void main() {
+            int *p = local();
+            println(*p);
+        }
+        "#;
+        let error = run(source).unwrap_err();
+        assert_eq!(error.kind, RuntimeErrorKind::DanglingPointer);
+        assert_eq!(&source[error.start..error.end], "*p");
+
+        let source = "void main() { int a[3]; int *p = a; *(p + 3) = 1; }";
+        let error = run(source).unwrap_err();
+        assert_eq!(error.kind, RuntimeErrorKind::IndexOutOfBounds);
+        assert_eq!(&source[error.start..error.end], "*(p + 3)");
+        assert_eq!(error.message, "offset 3 out of bounds for array of length 3");
+
+        let source = "void main() { int *p; { int x; p = &x; } *p = 1; }";
+        let error = run(source).unwrap_err();
+        assert_eq!(error.kind, RuntimeErrorKind::DanglingPointer);
+
+        let error = run("void main() { int *p; println(*p); }").unwrap_err();
+        assert_eq!(error.kind, RuntimeErrorKind::NullPointer);
+
+        let error = run("void main() { int a[1]; int b[1]; println(a < b); }").unwrap_err();
+        assert_eq!(error.kind, RuntimeErrorKind::TypeMismatch);
+    }
+}
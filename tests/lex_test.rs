@@ -339,6 +339,96 @@ mod test_lex {
         );
         Ok(())
     }
+    #[test]
+    fn test_lex_char_and_string() {
+        let file = "char c = '\\'';\nprint(\"a \\\"b\\\"\\n\", 'x');\n\"open\n".to_string();
+        let mut lexer = Lexer::new(&file);
+        let list = lexer.lex();
+        let token_types = list.iter().map(|token| token.token_type).collect::<Vec<_>>();
+        assert_eq!(
+            token_types,
+            vec![
+                TokenType::Keyword(KeywordType::CHAR),
+                TokenType::Id,
+                TokenType::Assign,
+                TokenType::CharLiteral,
+                TokenType::Semi,
+                TokenType::Id,
+                TokenType::Lparen,
+                TokenType::StringLiteral,
+                TokenType::Comma,
+                TokenType::CharLiteral,
+                TokenType::Rparen,
+                TokenType::Semi,
+                TokenType::Error,
+            ]
+        );
+        assert_eq!(list[3].content, "'\\''");
+        assert_eq!(list[7].content, "\"a \\\"b\\\"\\n\"");
+        // an unterminated string stops at the end of line
+        assert_eq!(list[12].content, "\"open");
+        lex_test_helper(
+            &file,
+            list.into_iter().nth(9),
+            TokenType::CharLiteral,
+            Position::new(1, 19),
+            Position::new(1, 22),
+        );
+    }
+    #[test]
+    fn test_lex_bitwise_operators() {
+        let file = "a%b&c|d^~e<<f>>g&&h||i<=j>=k&".to_string();
+        let mut lexer = Lexer::new(&file);
+        let token_types = lexer
+            .lex()
+            .into_iter()
+            .filter(|token| token.token_type != TokenType::Id)
+            .map(|token| token.token_type)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            token_types,
+            vec![
+                TokenType::Mod,
+                TokenType::BitAnd,
+                TokenType::BitOr,
+                TokenType::BitXor,
+                TokenType::BitNot,
+                TokenType::Shl,
+                TokenType::Shr,
+                TokenType::And,
+                TokenType::Or,
+                TokenType::Le,
+                TokenType::Ge,
+                TokenType::BitAnd,
+            ]
+        );
+    }
+    #[test]
+    fn test_lex_compound_assignment() {
+        let file = "a+=b-=c*=d/=e%=f++ --g+-h/*c*/".to_string();
+        let mut lexer = Lexer::new(&file);
+        let token_types = lexer
+            .lex()
+            .into_iter()
+            .filter(|token| token.token_type != TokenType::Id)
+            .map(|token| token.token_type)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            token_types,
+            vec![
+                TokenType::PlusAssign,
+                TokenType::MinusAssign,
+                TokenType::MultiplyAssign,
+                TokenType::DivideAssign,
+                TokenType::ModAssign,
+                TokenType::Increment,
+                TokenType::Decrement,
+                TokenType::Plus,
+                TokenType::Minus,
+                TokenType::Comment,
+            ]
+        );
+    }
     // #[test]
     // fn test_lex_gcd() -> Result<(), std::io::Error> {
     //     let path = path::Path::new("tests/fixtures/lexer2.test.txt");
@@ -653,4 +743,76 @@ mod test_lex {
     //     );
     //     Ok(())
     // }
+
+    #[test]
+    fn test_lex_struct() {
+        let file = "struct P p; p.x".to_string();
+        let mut lexer = Lexer::new(&file);
+        let token_types = lexer
+            .lex()
+            .into_iter()
+            .map(|token| token.token_type)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            token_types,
+            vec![
+                TokenType::Keyword(KeywordType::STRUCT),
+                TokenType::Id,
+                TokenType::Id,
+                TokenType::Semi,
+                TokenType::Id,
+                TokenType::Dot,
+                TokenType::Id,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lex_switch() {
+        let file = "switch (x) { case -1: default: }".to_string();
+        let mut lexer = Lexer::new(&file);
+        let token_types = lexer
+            .lex()
+            .into_iter()
+            .map(|token| token.token_type)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            token_types,
+            vec![
+                TokenType::Keyword(KeywordType::SWITCH),
+                TokenType::Lparen,
+                TokenType::Id,
+                TokenType::Rparen,
+                TokenType::Lbrace,
+                TokenType::Keyword(KeywordType::CASE),
+                TokenType::Minus,
+                TokenType::NumberLiteral,
+                TokenType::Colon,
+                TokenType::Keyword(KeywordType::DEFAULT),
+                TokenType::Colon,
+                TokenType::Rbrace,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lex_conditional() {
+        let file = "a?b:c".to_string();
+        let mut lexer = Lexer::new(&file);
+        let token_types = lexer
+            .lex()
+            .into_iter()
+            .map(|token| token.token_type)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            token_types,
+            vec![
+                TokenType::Id,
+                TokenType::Question,
+                TokenType::Id,
+                TokenType::Colon,
+                TokenType::Id,
+            ]
+        );
+    }
 }
@@ -0,0 +1,145 @@
+#[cfg(test)]
+mod native {
+    use anyhow::Result;
+    use tinylang_rs::{
+        host::CapturedHost,
+        interpreter::{error::RuntimeErrorKind, Interpreter},
+        lexer::lex,
+        native::{NativeValue, Natives},
+        parser::{parse::Parser, Program},
+        semantic::{FunctionSignature, Resolver, Type, TypeChecker},
+        vm::{EmitOperationCode, Vm},
+    };
+
+    const SOURCE: &str = r#"
+    void main() {
+        int a;
+        a = abs(0 - 5);
+        assert(a == 5);
+        println(a);
+        assert(a == 6);
+    }
+    "#;
+
+    fn parse(content: &str) -> Program {
+        let mut lex = lex::Lexer::new(content);
+        let list = lex.lex();
+        let mut parser = Parser::new(list, "main.cm", content);
+        parser.parse_program().unwrap()
+    }
+
+    fn abs(args: &[NativeValue]) -> Result<NativeValue, String> {
+        match args {
+            [NativeValue::Int(value)] => Ok(NativeValue::Int(value.wrapping_abs())),
+            _ => unreachable!(),
+        }
+    }
+
+    fn assert(args: &[NativeValue]) -> Result<NativeValue, String> {
+        match args {
+            [NativeValue::Boolean(true)] => Ok(NativeValue::Void),
+            _ => Err("assertion failed".to_string()),
+        }
+    }
+
+    fn natives() -> Natives {
+        let mut natives = Natives::default();
+        natives.register("abs", FunctionSignature::new(vec![Type::Int], Type::Int), abs);
+        natives.register(
+            "assert",
+            FunctionSignature::new(vec![Type::Boolean], Type::Void),
+            assert,
+        );
+        natives
+    }
+
+    #[test]
+    fn test_semantic() {
+        let content = r#"
+        int abs(int a) {
+            return a;
+        }
+        void main() {
+            assert(abs(1));
+        }
+        "#;
+        let program = parse(content);
+        let natives = natives();
+        let mut resolver = Resolver::new("main.cm", content);
+        resolver.declare_natives(&natives);
+        assert!(resolver.resolve_program(&program).is_err());
+        let error = resolver.error_reporter.emit_string();
+        assert!(error.contains("`abs` has already defined in this scope"));
+        assert!(!error.contains("cannot find"));
+
+        let mut checker = TypeChecker::new("main.cm", content);
+        checker.declare_natives(&natives);
+        assert!(checker.check_program(&program).is_err());
+        let error = checker.error_reporter.emit_string();
+        assert!(error.contains("function `abs` has already defined"));
+        assert!(error.contains("mismatched types, expected `bool`, found `int`"));
+
+        let program = parse(SOURCE);
+        let mut resolver = Resolver::new("main.cm", SOURCE);
+        resolver.declare_natives(&natives);
+        assert!(resolver.resolve_program(&program).is_ok());
+        let mut checker = TypeChecker::new("main.cm", SOURCE);
+        checker.declare_natives(&natives);
+        assert!(checker.check_program(&program).is_ok());
+    }
+
+    #[test]
+    fn test_interpreter() {
+        let program = parse(SOURCE);
+        let mut interpreter = Interpreter::new();
+        interpreter.register_native("abs", FunctionSignature::new(vec![Type::Int], Type::Int), abs);
+        interpreter.register_native(
+            "assert",
+            FunctionSignature::new(vec![Type::Boolean], Type::Void),
+            assert,
+        );
+        let host = CapturedHost::default();
+        let error = interpreter
+            .interpret(&program, Box::new(host.clone()))
+            .unwrap_err();
+        assert_eq!(host.output(), "5\n");
+        assert_eq!(error.kind, RuntimeErrorKind::NativeError);
+        assert_eq!(error.message, "assertion failed");
+        assert_eq!(&SOURCE[error.start..error.end], "assert(a == 6)");
+
+        // the type checker is skipped, so the arguments are checked at run time
+        let program = parse("void main() { abs(true); }");
+        let error = interpreter
+            .interpret(&program, Box::new(CapturedHost::default()))
+            .unwrap_err();
+        assert_eq!(error.kind, RuntimeErrorKind::ArgumentMismatch);
+        assert_eq!(error.message, "mismatched types, expected `int`, found `bool`");
+    }
+
+    #[test]
+    fn test_vm() -> Result<()> {
+        let mut program = parse(SOURCE);
+        let host = CapturedHost::default();
+        let mut vm = Vm::with_host(Box::new(host.clone()));
+        vm.register_native("abs", FunctionSignature::new(vec![Type::Int], Type::Int), abs);
+        vm.register_native(
+            "assert",
+            FunctionSignature::new(vec![Type::Boolean], Type::Void),
+            assert,
+        );
+        program.emit(&mut vm)?;
+        let error = vm.exec().unwrap_err();
+        assert_eq!(host.output(), "5\n");
+        assert!(error.to_string().contains("assertion failed"));
+
+        let mut program = parse("void main() { abs(1, 2); }");
+        let mut vm = Vm::new();
+        vm.register_native("abs", FunctionSignature::new(vec![Type::Int], Type::Int), abs);
+        program.emit(&mut vm)?;
+        let error = vm.exec().unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("function `abs` takes 1 arguments but 2 were supplied"));
+        Ok(())
+    }
+}
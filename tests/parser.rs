@@ -0,0 +1,302 @@
+#[cfg(test)]
+mod parser {
+    use tinylang_rs::{
+        lexer::lex,
+        parser::{parse::Parser, visitor::AstPrinter},
+    };
+
+    fn new_parser(content: &str) -> Parser {
+        let mut lex = lex::Lexer::new(content);
+        let list = lex.lex();
+        Parser::new(list, "main.cm", content)
+    }
+
+    #[test]
+    fn test_multiple_errors() {
+        let content = r#"
+        int a
+        int b;
+        void main() {
+            int c;
+            c = ;
+            b = 1;
+            print(b +);
+        }
+        "#;
+        let mut parser = new_parser(content);
+        assert!(parser.parse_program().is_err());
+        assert_eq!(parser.error_reporter.diagnostics().len(), 3);
+        let error = parser.error_reporter.emit_string();
+        assert!(error.contains("expected `;`, found int"));
+        assert!(error.contains("expected `;`, found `=`"));
+        assert_eq!(error.matches("error:").count(), 3);
+    }
+
+    #[test]
+    fn test_partial_program() {
+        let content = r#"
+        int f(int a, ) {
+            return a;
+        }
+        void main() {
+            int x;
+            x = 1 +;
+            if (x) { x = 2; }
+        }
+        "#;
+        let mut parser = new_parser(content);
+        let program = parser.parse_partial_program();
+        assert_eq!(parser.error_reporter.diagnostics().len(), 2);
+        let ast = AstPrinter::default().print_ast(&program);
+        assert_eq!(ast.matches("ErrorNode").count(), 2);
+        // the function which can't be parsed is skipped as a whole
+        assert!(!ast.contains("FunctionDeclaration @9"));
+        assert!(ast.contains("SelectionStatement"));
+
+        let mut parser = new_parser("void main() { x = 1 }");
+        let program = parser.parse_partial_program();
+        assert_eq!(parser.error_reporter.diagnostics().len(), 1);
+        let ast = AstPrinter::default().print_ast(&program);
+        assert!(ast.contains("FunctionDeclaration"));
+        assert!(ast.contains("ErrorNode"));
+    }
+
+    #[test]
+    fn test_type_in_parentheses() {
+        // the statement is skipped past its parentheses, so the type isn't parsed again
+        for content in &[
+            "int main() { return (int)0; }",
+            "void main() { println((int)); }",
+            "void main() { int x; if ((char)) { x = 1; } }",
+        ] {
+            let mut parser = new_parser(content);
+            assert!(parser.parse_program().is_err());
+            assert_eq!(parser.error_reporter.diagnostics().len(), 1, "{}", content);
+            let error = parser.error_reporter.emit_string();
+            assert!(error.contains("expected `Identifier`, `NumberLiteral`, `(`"));
+        }
+    }
+
+    #[test]
+    fn test_valid_program() {
+        let content = "void main() { int a; a = 1; }";
+        let mut parser = new_parser(content);
+        assert!(parser.parse_program().is_ok());
+        assert!(parser.error_reporter.is_empty());
+    }
+
+    #[test]
+    fn test_jump_outside_of_loop() {
+        let content = r#"
+        void main() {
+            while (true) {
+                break;
+            }
+            break;
+            if (true) {
+                continue;
+            }
+        }
+        "#;
+        let mut parser = new_parser(content);
+        assert!(parser.parse_program().is_err());
+        let error = parser.error_reporter.emit_string();
+        assert!(error.contains("`break` outside of a loop"));
+        assert!(error.contains("`continue` outside of a loop"));
+        assert_eq!(parser.error_reporter.diagnostics().len(), 2);
+    }
+
+    #[test]
+    fn test_char_literal_errors() {
+        let content = r#"
+        void main() {
+            char a = 'ab';
+            char b = '';
+            print("\q");
+            a = '\n';
+        }
+        "#;
+        let mut parser = new_parser(content);
+        assert!(parser.parse_program().is_err());
+        let error = parser.error_reporter.emit_string();
+        assert_eq!(
+            error
+                .matches("character literal should contain one character")
+                .count(),
+            2
+        );
+        assert!(error.contains("unknown character escape `\\q`"));
+        assert_eq!(parser.error_reporter.diagnostics().len(), 3);
+    }
+
+    #[test]
+    fn test_multi_dimensional_array() {
+        let content = r#"
+        int m[2][3] = {{1, 2, 3}, {4}};
+        char names[2][4] = {"ab", "cd"};
+        int sum(int a[][3], int r[]) {
+            return a[1][0] + r[2];
+        }
+        void main() {
+            m[1][2] = sum(m, m[0]);
+        }
+        "#;
+        let mut parser = new_parser(content);
+        assert!(parser.parse_program().is_ok());
+
+        let mut parser = new_parser("int m[2][2] = {1, 2};");
+        assert!(parser.parse_program().is_err());
+        let error = parser.error_reporter.emit_string();
+        assert!(error.contains("expected `{`, found numberLiteral"));
+    }
+
+    #[test]
+    fn test_struct() {
+        let content = r#"
+        struct Point {
+            int x;
+            int y;
+        };
+        struct Line {
+            struct Point ends[2];
+            bool visible;
+        };
+        int length(struct Line l) {
+            return l.ends[1].x - l.ends[0].x;
+        }
+        void main() {
+            struct Line l;
+            l.ends[1].x = 2;
+        }
+        "#;
+        let mut parser = new_parser(content);
+        assert!(parser.parse_program().is_ok());
+
+        let mut parser = new_parser("struct P { int x = 1; };");
+        assert!(parser.parse_program().is_err());
+        let error = parser.error_reporter.emit_string();
+        assert!(error.contains("field `x` can't have an initializer"));
+    }
+
+    #[test]
+    fn test_pointer() {
+        let content = r#"
+        void swap(int *a, int *b) {
+            int t = *a;
+            *a = *b;
+            *b = t;
+        }
+        void main() {
+            int a[3];
+            int x;
+            int **pp;
+            int *p = &a[1];
+            pp = &p;
+            swap(&x, *pp + 1);
+            *p++ = 1;
+            ++*p;
+        }
+        "#;
+        let mut parser = new_parser(content);
+        assert!(parser.parse_program().is_ok());
+
+        let mut parser = new_parser("void main() { int *p = &1; }");
+        assert!(parser.parse_program().is_err());
+    }
+
+    #[test]
+    fn test_switch() {
+        let content = r#"
+        void main() {
+            int x;
+            switch (x + 1) {
+                case -1:
+                case 2:
+                    x = 1;
+                    break;
+                default: {
+                    int y;
+                    x = y;
+                }
+                case 3:
+            }
+        }
+        "#;
+        let mut parser = new_parser(content);
+        assert!(parser.parse_program().is_ok());
+
+        let mut parser = new_parser(
+            "void main() { switch (1) { case 1: break; case 1: default: default: } continue; }",
+        );
+        assert!(parser.parse_program().is_err());
+        let error = parser.error_reporter.emit_string();
+        assert!(error.contains("duplicate case value `1`"));
+        assert!(error.contains("duplicate `default` label"));
+        assert!(error.contains("`continue` outside of a loop"));
+    }
+
+    #[test]
+    fn test_conditional() {
+        let content = "void main() { int x; x = x > 0 ? x : x < 0 ? -x : 1; }";
+        let mut parser = new_parser(content);
+        let program = parser.parse_program().unwrap();
+        let ast = AstPrinter::default().print_ast(&program);
+        // the alternative of the first `?:` is the second one
+        let first = ast.find("ConditionalExpression").unwrap();
+        assert!(ast[first + 1..].contains("ConditionalExpression"));
+
+        let mut parser = new_parser("void main() { int x; print(x > 0 ? 1); }");
+        assert!(parser.parse_program().is_err());
+        let error = parser.error_reporter.emit_string();
+        assert!(error.contains("`:`"));
+    }
+
+    #[test]
+    fn test_function_prototype() {
+        let content = "int f(int x, char s[]);\nvoid g(void);\nvoid main() {}";
+        let mut parser = new_parser(content);
+        let program = parser.parse_program().unwrap();
+        let ast = AstPrinter::default().print_ast(&program);
+        assert_eq!(ast.matches("FunctionPrototype").count(), 2);
+        // `(void)` is printed with the span of the keyword
+        let start = content.find("(void)").unwrap() + 1;
+        assert!(ast.contains(&format!("Void @{}..{}", start, start + 4)), "{}", ast);
+
+        let mut parser = new_parser("void main() { int f(int x); }");
+        assert!(parser.parse_program().is_err());
+    }
+
+    #[test]
+    fn test_const() {
+        let content = r#"
+        const int N = 3;
+        const int M = -(N);
+        const char NAME[5] = "name";
+        int a[N][2];
+        void main() {
+            const bool flag = true;
+            int b[N];
+        }
+        "#;
+        let mut parser = new_parser(content);
+        let program = parser.parse_program().unwrap();
+        let ast = AstPrinter::default().print_ast(&program);
+        // the size `N` of `a` is the name, it's resolved by the resolver
+        let start = content.find("N][2]").unwrap();
+        assert!(ast.contains(&format!("Identifier(N) @{}..{}", start, start + 1)), "{}", ast);
+
+        // a name which isn't a constant is still a size for the parser
+        let mut parser = new_parser("int n = 2; int a[n]; void f(int m[][n]) {}");
+        assert!(parser.parse_program().is_ok());
+
+        for (content, message) in &[
+            ("const int N;", "const `N` should be initialized"),
+            ("const int *p = 0;", "`const` pointers aren't supported"),
+        ] {
+            let mut parser = new_parser(content);
+            assert!(parser.parse_program().is_err());
+            let error = parser.error_reporter.emit_string();
+            assert!(error.contains(message), "{}", error);
+        }
+    }
+}
@@ -0,0 +1,155 @@
+#[cfg(test)]
+mod preprocess {
+    use tinylang_rs::{
+        host::CapturedHost,
+        interpreter,
+        lexer::{
+            lex,
+            preprocess::{MemoryLoader, Preprocessed, Preprocessor},
+        },
+        parser::parse::Parser,
+    };
+
+    fn preprocess_with(loader: MemoryLoader, content: &str) -> Result<Preprocessed, String> {
+        let mut preprocessor = Preprocessor::new(Box::new(loader));
+        preprocessor
+            .preprocess("main.cm", content)
+            .map_err(|_| preprocessor.error_reporter.emit_string())
+    }
+
+    fn preprocess(content: &str) -> Result<Preprocessed, String> {
+        preprocess_with(MemoryLoader::default(), content)
+    }
+
+    #[test]
+    fn test_define() {
+        let content = r#"#define N 10
+#define M N + 1
+#define ITSELF ITSELF
+int a[N]; /* N */ char s[] = "N"; int M1 = M * ITSELF;
+double d = 1e5;
+#define e5 ITSELF
+double f = 1e5;
+"#;
+        let preprocessed = preprocess(content).unwrap();
+        assert_eq!(
+            preprocessed.source,
+            "\n\n\nint a[10]; /* N */ char s[] = \"N\"; int M1 = 10 + 1 * ITSELF;\n\
+             double d = 1e5;\n\ndouble f = 1e5;\n"
+        );
+        // the lines keep their numbers, so a span on a line maps back to the same line
+        let start = preprocessed.source.find("10 + 1").unwrap();
+        let (file, range) = preprocessed
+            .source_map
+            .locate(start..start + "10 + 1".len())
+            .unwrap();
+        assert_eq!(preprocessed.source_map.file_name(file), Some("main.cm"));
+        assert_eq!(&content[range], "M");
+
+        let start = preprocessed.source.find("int M1").unwrap();
+        let (_, range) = preprocessed
+            .source_map
+            .locate(start..start + "int M1 = 10 + 1 * ITSELF".len())
+            .unwrap();
+        assert_eq!(&content[range], "int M1 = M * ITSELF");
+    }
+
+    #[test]
+    fn test_ifdef() {
+        let content = r#"#define DEBUG
+#ifdef DEBUG
+int debug = 1;
+#ifndef DEBUG
+int nested = 1;
+#else
+int nested = 2;
+#endif
+#else
+int debug = 0;
+#endif
+#ifndef RELEASE
+  #define RELEASE 0
+#endif
+#ifdef UNDEFINED
+#unknown directives are skipped
+#endif
+int release = RELEASE;
+"#;
+        let source = preprocess(content).unwrap().source;
+        let lines: Vec<&str> = source.lines().filter(|line| !line.is_empty()).collect();
+        assert_eq!(lines, vec!["int debug = 1;", "int nested = 2;", "int release = 0;"]);
+        assert_eq!(source.lines().count(), content.lines().count());
+
+        let error = preprocess("#ifdef A\nint a;\n").unwrap_err();
+        assert!(error.contains("unterminated `#ifdef`"));
+        let error = preprocess("int a;\n#endif\n").unwrap_err();
+        assert!(error.contains("`#endif` without `#ifdef`"));
+        let error = preprocess("#ifdef A\n#else\n#else\n#endif\n").unwrap_err();
+        assert!(error.contains("`#else` after `#else`"));
+        let error = preprocess("#pragma once\n").unwrap_err();
+        assert!(error.contains("unknown directive `#pragma`"));
+        assert!(error.contains("main.cm:1:1"));
+        let error = preprocess("#define 1 2\n").unwrap_err();
+        assert!(error.contains("expected a macro name after `#define`"));
+    }
+
+    #[test]
+    fn test_redefine() {
+        let mut preprocessor = Preprocessor::new(Box::new(MemoryLoader::default()));
+        let content = "#define N 1\n#define N 1\n#define N 2\nint a = N;\n";
+        let preprocessed = preprocessor.preprocess("main.cm", content).unwrap();
+        assert_eq!(preprocessed.source, "\n\n\nint a = 2;\n");
+        assert_eq!(preprocessor.error_reporter.len(), 1);
+        let warning = preprocessor.error_reporter.emit_string();
+        assert!(warning.contains("warning: macro `N` is redefined"));
+        assert!(warning.contains("main.cm:3:1"));
+    }
+
+    #[test]
+    fn test_include() {
+        let mut loader = MemoryLoader::default();
+        let math = "#include \"consts.cm\"\nint square(int a) { return a * a; }\n";
+        loader.add_file("lib/math.cm", math);
+        loader.add_file("lib/consts.cm", "#define TEN 10\n");
+        let content = "#include \"lib/math.cm\"\nvoid main() { println(square(TEN)); }\n";
+        let preprocessed = preprocess_with(loader, content).unwrap();
+        let mut lex = lex::Lexer::new(&preprocessed.source);
+        let mut parser = Parser::new(lex.lex(), "main.cm", &preprocessed.source);
+        let mut program = parser.parse_program().unwrap();
+        let host = CapturedHost::default();
+        interpreter::interpret(&mut program, Box::new(host.clone())).unwrap();
+        assert_eq!(host.output(), "100\n");
+
+        let mut loader = MemoryLoader::default();
+        loader.add_file("broken.cm", "\nint broken( { }\n");
+        let content = "#include \"broken.cm\"\nvoid main() { }\n";
+        let preprocessed = preprocess_with(loader, content).unwrap();
+        let mut lex = lex::Lexer::new(&preprocessed.source);
+        let mut parser = Parser::new(lex.lex(), "main.cm", &preprocessed.source);
+        assert!(parser.parse_program().is_err());
+        preprocessed.source_map.remap(&mut parser.error_reporter);
+        let error = parser.error_reporter.emit_string();
+        assert!(error.contains("broken.cm:2:"), "{}", error);
+        assert!(!error.contains("main.cm:"), "{}", error);
+    }
+
+    #[test]
+    fn test_include_error() {
+        let mut loader = MemoryLoader::default();
+        loader.add_file("a.cm", "#include \"b.cm\"\n");
+        loader.add_file("b.cm", "int b;\n#include \"a.cm\"\n");
+        let error = preprocess_with(loader, "#include \"a.cm\"\n").unwrap_err();
+        assert!(error.contains("include cycle: a.cm -> b.cm -> a.cm"), "{}", error);
+        assert!(error.contains("b.cm:2:1"), "{}", error);
+
+        let error = preprocess("#include \"main.cm\"\n").unwrap_err();
+        assert!(error.contains("include cycle: main.cm -> main.cm"));
+
+        let error = preprocess("\n#include \"missing.cm\"\n").unwrap_err();
+        assert!(error.contains("can't read the included file `missing.cm`"));
+        assert!(error.contains("main.cm:2:1"));
+
+        let error = preprocess("#include <stdio.h>\n").unwrap_err();
+        assert!(error.contains("expected `\"file\"` after `#include`"));
+    }
+}
@@ -0,0 +1,124 @@
+#[cfg(test)]
+mod repl {
+    use tinylang_rs::{host::CapturedHost, repl::Repl};
+
+    fn repl() -> (Repl, CapturedHost) {
+        let host = CapturedHost::default();
+        (Repl::new(Box::new(host.clone())), host)
+    }
+
+    #[test]
+    fn test_eval() {
+        let (mut repl, host) = repl();
+        assert_eq!(repl.eval("int square(int x) { return x * x; }"), "");
+        assert_eq!(repl.eval("square(4)"), "16\n");
+        assert_eq!(repl.eval("int g = 2"), "");
+        assert_eq!(repl.eval("g = g + square(3);"), "11\n");
+        assert_eq!(repl.eval("void show() { println(\"g = \", g); }"), "");
+        // a call to a void function prints nothing, the output of the program goes to the host
+        assert_eq!(repl.eval("show();"), "");
+        assert_eq!(host.output(), "g = 11\n");
+        assert_eq!(repl.eval("g > 10 ? g * 2 : 0"), "22\n");
+        assert_eq!(repl.eval("1 + 2; true; 'c'"), "3\ntrue\nc\n");
+        assert_eq!(repl.eval("  "), "");
+
+        // entering a function again replaces it
+        assert_eq!(repl.eval("int square(int x) { return x * x * x; }"), "");
+        assert_eq!(repl.eval("square(2)"), "8\n");
+        assert_eq!(repl.eval("int g = 1;"), "");
+        assert_eq!(repl.eval("g"), "1\n");
+
+        // every value can be printed, a pointer is printed like the vm does
+        assert_eq!(repl.eval("int x;"), "");
+        assert_eq!(repl.eval("&x"), "pointer\n");
+        assert_eq!(repl.eval("int *p = &x;"), "");
+        assert_eq!(repl.eval("*p = 3; p"), "3\npointer\n");
+        // a function isn't a value
+        assert!(repl.eval("square").contains("cannot find value `square` in this scope"));
+    }
+
+    #[test]
+    fn test_error() {
+        let (mut repl, _) = repl();
+        let error = repl.eval("x + 1");
+        assert!(error.contains("cannot find `x` in this scope"), "{}", error);
+        assert!(error.contains("<repl>:1:1"));
+        let error = repl.eval("int f( {");
+        assert!(error.contains("expected `int` or `void` or `bool`, found `{`"));
+        assert!(!error.contains("{;"));
+
+        // a runtime error in a call doesn't leave its scopes behind
+        repl.eval("int a[2];");
+        repl.eval("int get(int i) { int local; local = i; return a[i]; }");
+        let error = repl.eval("get(5)");
+        assert!(error.contains("index out of bounds"));
+        // the error is shown in the input which defined `get`, the call in the input with it
+        assert!(error.contains("<repl>:1:47"), "{}", error);
+        assert!(error.contains("1 │ int get(int i) { int local; local = i; return a[i]; }"));
+        assert!(error.contains("<repl>:1:1"), "{}", error);
+        assert!(error.contains("1 │ get(5);"), "{}", error);
+        let error = repl.eval("local");
+        assert!(error.contains("cannot find `local` in this scope"), "{}", error);
+        assert_eq!(repl.eval("get(1)"), "0\n");
+
+        // the input is checked with the declarations entered before it
+        assert_eq!(repl.eval("const int C = 1;"), "");
+        let error = repl.eval("C = 5");
+        assert!(error.contains("can't assign to const `C`"), "{}", error);
+        let error = repl.eval("get(true)");
+        assert!(error.contains("mismatched types, expected `int`, found `bool`"), "{}", error);
+        let error = repl.eval("return C;");
+        assert!(error.contains("can't return a value"), "{}", error);
+        assert_eq!(repl.eval("C"), "1\n");
+        // a declaration entered again replaces the one before it
+        assert_eq!(repl.eval("bool C = true;"), "");
+        assert_eq!(repl.eval("C = false"), "false\n");
+    }
+
+    #[test]
+    fn test_is_complete() {
+        assert!(Repl::is_complete("1 + 2"));
+        assert!(!Repl::is_complete("int f() {"));
+        assert!(!Repl::is_complete("int f() {\n  return g(1,\n"));
+        assert!(Repl::is_complete("int f() {\n  return 1;\n}\n"));
+        assert!(Repl::is_complete(":ast int f() {"));
+        // a brace in a string or a comment doesn't count
+        assert!(Repl::is_complete("println(\"{\"); /* ( */"));
+    }
+
+    #[test]
+    fn test_meta_command() {
+        let (mut repl, _) = repl();
+        let ast = repl.eval(":ast g + 1");
+        assert!(ast.starts_with("BinaryExpression @0..5\n"), "{}", ast);
+        assert!(ast.contains("Identifier(g) @0..1"));
+        let ast = repl.eval(":ast !a && -b < ~c");
+        assert!(ast.starts_with("LogicExpression @0..13\n"), "{}", ast);
+        assert!(ast.contains("UnaryExpression @"), "{}", ast);
+        let ast = repl.eval(":ast int g;");
+        assert!(ast.starts_with("Program @"));
+        assert!(ast.ends_with("\n"), "{}", ast);
+
+        let tokens = repl.eval(":tokens g+1");
+        let lines: Vec<&str> = tokens.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("1:2"));
+        assert!(lines[1].ends_with("\"+\""));
+
+        repl.eval("int g;");
+        repl.eval("int twice(int x) { return x * 2; }");
+        let disasm = repl.eval(":disasm");
+        assert!(disasm.starts_with("twice:\n"), "{}", disasm);
+        assert!(disasm.contains("MultiplyI32"));
+        let disasm = repl.eval(":disasm g = twice(3)");
+        assert!(disasm.starts_with("main:\n"), "{}", disasm);
+        assert!(disasm.contains("SetGlobal(\"g\")"));
+        assert!(!disasm.contains("MultiplyI32"));
+        let disasm = repl.eval(":disasm int half(int x) { return x / 2; }");
+        assert!(disasm.starts_with("half:\n"), "{}", disasm);
+        assert!(disasm.contains("DivideI32"));
+
+        assert!(repl.eval(":help").contains(":disasm"));
+        assert_eq!(repl.eval(":frob"), "unknown command `:frob`, try `:help`\n");
+    }
+}
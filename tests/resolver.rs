@@ -0,0 +1,246 @@
+#[cfg(test)]
+mod resolver {
+    use std::{fs::read_to_string, path};
+
+    use tinylang_rs::{
+        lexer::lex,
+        parser::parse::Parser,
+        semantic::{self, Resolver, SymbolKind},
+    };
+    fn resolve(content: &str) -> Resolver {
+        let mut lex = lex::Lexer::new(content);
+        let list = lex.lex();
+        let mut parser = Parser::new(list, "main.cm", content);
+        let program = parser.parse_program().unwrap();
+        let mut resolver = Resolver::new("main.cm", content);
+        let _ = resolver.resolve_program(&program);
+        resolver
+    }
+
+    #[test]
+    fn test_fixtures() {
+        for fixture in &[
+            "tests/fixtures/interpreter/fib.txt",
+            "tests/fixtures/interpreter/bubble_sort.txt",
+            "tests/fixtures/interpreter/insert_sort.txt",
+            "benches/corpus/fibonacci.cm",
+        ] {
+            let content = read_to_string(path::Path::new(fixture)).unwrap();
+            let resolver = resolve(&content);
+            assert!(resolver.error_reporter.is_empty(), "{}", fixture);
+        }
+    }
+
+    #[test]
+    fn test_slot() {
+        let content = r#"
+        int total;
+        int sum(int a[], int len) {
+            int i = 0;
+            {
+                int x = 1;
+            }
+            {
+                int y = 2;
+            }
+            total = len;
+            return sum(a, i);
+        }
+        void main() {}
+        "#;
+        let resolver = resolve(content);
+        assert!(resolver.error_reporter.is_empty());
+        let table = &resolver.symbol_table;
+        let kind_at = |pattern: &str| {
+            let start = content.find(pattern).unwrap();
+            table.symbol(table.lookup(start).unwrap()).kind.clone()
+        };
+        assert_eq!(kind_at("total;"), SymbolKind::Global);
+        assert_eq!(kind_at("sum("), SymbolKind::Function);
+        assert_eq!(kind_at("a[]"), SymbolKind::Parameter { slot: 0 });
+        assert_eq!(kind_at("len)"), SymbolKind::Parameter { slot: 1 });
+        assert_eq!(kind_at("i = 0"), SymbolKind::Local { slot: 2 });
+        assert_eq!(kind_at("x = 1"), SymbolKind::Local { slot: 3 });
+        assert_eq!(kind_at("y = 2"), SymbolKind::Local { slot: 3 });
+        // references point to the same declaration
+        let reference = content.find("total = len").unwrap();
+        let declaration = content.find("total;").unwrap();
+        assert_eq!(table.lookup(reference), table.lookup(declaration));
+        let reference = content.rfind("sum(a, i)").unwrap();
+        assert_eq!(table.lookup(reference), table.lookup(content.find("sum(").unwrap()));
+    }
+
+    #[test]
+    fn test_diagnostic() {
+        let content = r#"
+        int a;
+        void main(int b) {
+            int b;
+            int c;
+            {
+                int c;
+            }
+            d = 1;
+            undefined();
+        }
+        "#;
+        let resolver = resolve(content);
+        let error = resolver.error_reporter.emit_string();
+        assert!(error.contains("`b` has already defined in this scope"));
+        assert!(error.contains("cannot find `d` in this scope"));
+        assert!(error.contains("cannot find `undefined` in this scope"));
+        let warning = resolver.warning_reporter.emit_string();
+        assert!(warning.starts_with("warning"));
+        assert!(warning.contains("`c` shadows a declaration in outer scope"));
+    }
+
+    #[test]
+    fn test_function_prototype() {
+        let content = r#"
+        int odd(int n);
+        int even(int n) {
+            return n == 0 ? 1 : odd(n - 1);
+        }
+        int odd(int n) {
+            return n == 0 ? 0 : even(n - 1);
+        }
+        void main() {}
+        "#;
+        let resolver = resolve(content);
+        assert!(resolver.error_reporter.is_empty());
+        // the prototype, the definition and the call share one symbol
+        let table = &resolver.symbol_table;
+        let prototype = table.lookup(content.find("odd").unwrap());
+        assert_eq!(prototype, table.lookup(content.find("odd(n - 1)").unwrap()));
+        assert_eq!(prototype, table.lookup(content.rfind("odd(int").unwrap()));
+
+        let resolver = resolve("int f(); int f() { return 1; } int f() { return 2; }");
+        let error = resolver.error_reporter.emit_string();
+        assert!(error.contains("`f` has already defined in this scope"));
+    }
+
+    #[test]
+    fn test_const() {
+        let content = r#"
+        const int N = 2;
+        const int a[N] = {1, 2};
+        const int m[N][N] = {{1, 2}, {3, 4}};
+        int sum(int a[]) {
+            return a[0];
+        }
+        void main() {
+            const int x = N;
+            int *p;
+            N = 3;
+            a[1] += 1;
+            x++;
+            p = &x;
+            sum(a);
+            sum(m[1]);
+            print(a, m[1][0], x);
+        }
+        "#;
+        let resolver = resolve(content);
+        let error = resolver.error_reporter.emit_string();
+        assert!(error.contains("can't assign to const `N`"));
+        assert!(error.contains("can't assign to const `a`"));
+        assert!(error.contains("can't assign to const `x`"));
+        assert!(error.contains("can't take the address of const `x`"));
+        assert!(error.contains("const array `a` can't be passed to function `sum`"));
+        assert!(error.contains("const array `m` can't be passed to function `sum`"));
+        assert_eq!(error.matches("error").count(), 6);
+        let table = &resolver.symbol_table;
+        let symbol = table.symbol(table.lookup(content.find("N = 2").unwrap()).unwrap());
+        assert!(symbol.is_const);
+
+        // the elements of a const array can't be written through a pointer to them
+        for content in &[
+            "int *p; void main() { p = A; *p = 5; }",
+            "void main() { int *p = A; }",
+            "void main() { *(A + 1) = 7; }",
+            "void main() { *A = 7; }",
+            "int *q; void main() { int *p = true ? M[0] : q; }",
+        ] {
+            let content = format!(
+                "const int A[2] = {{1, 2}}; const int M[2][2] = {{{{1}}}}; {}",
+                content
+            );
+            let error = resolve(&content).error_reporter.emit_string();
+            assert!(error.contains("can't be converted to a pointer"), "{}", error);
+            assert_eq!(error.matches("error").count(), 1, "{}", error);
+        }
+        let error = resolve("const int A[2] = {1, 2}; void f(int *p) {} void main() { f(A + 0); }")
+            .error_reporter
+            .emit_string();
+        assert!(error.contains("const array `A` can't be converted to a pointer"), "{}", error);
+        let error = resolve("const int A[2] = {1, 2}; void f(int *p) {} void main() { f(A); }")
+            .error_reporter
+            .emit_string();
+        assert!(error.contains("const array `A` can't be passed to function `f`"), "{}", error);
+        let error = resolve("const char S[3] = \"hi\"; void main() { println(S, S[0]); }")
+            .error_reporter
+            .emit_string();
+        assert!(error.is_empty(), "{}", error);
+    }
+
+    #[test]
+    fn test_array_size() {
+        let content = "const int N = 3; const int M = N * 2 + 1; const int K = (M - 1) << 1 | 1;
+            int a[M][K]; struct S { int b[N]; }; void f(int m[][N]) {}";
+        let resolver = resolve(content);
+        assert!(resolver.error_reporter.is_empty());
+        // the size is a reference to the constant
+        let table = &resolver.symbol_table;
+        let symbol = table.symbol(table.lookup(content.find("N]; }").unwrap()).unwrap());
+        assert_eq!((symbol.name.as_str(), symbol.start), ("N", content.find("N =").unwrap()));
+
+        for (content, message) in &[
+            ("int n = 2; int a[n];", "`n` isn't a global `const int`"),
+            ("const int Z = 0; const int D = 1 / Z; int a[D];", "`D` isn't a global `const int`"),
+            ("void main() { const int n = 2; int a[n]; }", "`n` isn't a global `const int`"),
+            ("const int N = 2; void f(int N, int m[][N]) {}", "`N` isn't a global `const int`"),
+            ("struct S { int a[N]; };", "`N` isn't a global `const int`"),
+        ] {
+            let error = resolve(content).error_reporter.emit_string();
+            assert!(error.contains(message), "{}", error);
+            assert!(error.contains("array size should be a constant"), "{}", error);
+        }
+        for (content, message) in &[
+            ("int a[0];", "array size should be positive, found `0`"),
+            ("const int N = -1; int a[N];", "array size should be positive, found `-1`"),
+            ("const int N = 0 - 1; int a[2][N];", "array size should be positive, found `-1`"),
+            ("const int N = 0; void f(int m[][N]) {}", "array size should be positive"),
+            ("const int N = 0; struct S { int a[N]; };", "array size should be positive"),
+        ] {
+            let error = resolve(content).error_reporter.emit_string();
+            assert!(error.contains(message), "{}", error);
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let check = |content: &str| {
+            let program = Parser::new(lex::Lexer::new(content).lex(), "main.cm", content)
+                .parse_program()
+                .unwrap();
+            let (warning_reporter, checked) = semantic::check("main.cm", content, &program);
+            let errors = checked.err().map(|error_reporter| error_reporter.emit_string());
+            (warning_reporter.emit_string(), errors)
+        };
+        let (warning, error) = check("void main() { int n; { int n; n = 2; } }");
+        assert!(warning.contains("`n` shadows a declaration in outer scope"));
+        assert_eq!(error, None);
+        // the types aren't checked when a name can't be resolved
+        let (_, error) = check("const int N = 1; void main() { N = 2; N = true; }");
+        let error = error.unwrap();
+        assert!(error.contains("can't assign to const `N`"), "{}", error);
+        assert!(!error.contains("mismatched types"), "{}", error);
+        let (_, error) = check("int n; void main() { n = true; }");
+        assert!(error.unwrap().contains("mismatched types, expected `int`, found `bool`"));
+        // a size naming a constant is its value for the type checker
+        let (_, error) =
+            check("const int N = 3; const int M = N + 4; int a[M][N]; void f() { bool b = a; }");
+        let error = error.unwrap();
+        assert!(error.contains("found `int[7][3]`"), "{}", error);
+    }
+}
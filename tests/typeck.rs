@@ -0,0 +1,445 @@
+#[cfg(test)]
+mod typeck {
+    use std::{fs::read_to_string, path};
+
+    use tinylang_rs::{lexer::lex, parser::parse::Parser, semantic::TypeChecker};
+    fn check(content: &str) -> Result<(), String> {
+        let mut lex = lex::Lexer::new(content);
+        let list = lex.lex();
+        let mut parser = Parser::new(list, "main.cm", content);
+        let program = parser
+            .parse_program()
+            .map_err(|_| parser.error_reporter.emit_string())?;
+        let mut checker = TypeChecker::new("main.cm", content);
+        checker
+            .check_program(&program)
+            .map_err(|_| checker.error_reporter.emit_string())
+    }
+
+    #[test]
+    fn test_fixtures() {
+        for fixture in &[
+            "tests/fixtures/interpreter/fib.txt",
+            "tests/fixtures/interpreter/bubble_sort.txt",
+            "tests/fixtures/interpreter/insert_sort.txt",
+            "tests/fixtures/parser/test.txt",
+            "benches/corpus/fibonacci.cm",
+        ] {
+            let content = read_to_string(path::Path::new(fixture)).unwrap();
+            assert_eq!(check(&content), Ok(()), "{}", fixture);
+        }
+    }
+
+    #[test]
+    fn test_mismatched_operand() {
+        let error = check(
+            r#"
+        void main() {
+            int a = 1 + true;
+            bool b = a && false;
+            if (a) {}
+            while (a > b) {}
+        }
+        "#,
+        )
+        .unwrap_err();
+        assert!(error.contains("mismatched types, expected `int`, found `bool`"));
+        assert!(error.contains("mismatched types, expected `bool`, found `int`"));
+        assert_eq!(error.matches("mismatched types").count(), 4);
+    }
+
+    #[test]
+    fn test_call_expression() {
+        let error = check(
+            r#"
+        int sum(int a[], int len) {
+            return 0;
+        }
+        void main() {
+            int a[3];
+            sum(a);
+            sum(1, 3);
+            missing();
+        }
+        "#,
+        )
+        .unwrap_err();
+        assert!(error.contains("function `sum` takes 2 arguments but 1 were supplied"));
+        assert!(error.contains("mismatched types, expected `int[]`, found `int`"));
+        assert!(error.contains("cannot find function `missing`"));
+    }
+
+    #[test]
+    fn test_array_misuse() {
+        let error = check(
+            r#"
+        void main() {
+            int a[3];
+            int b;
+            a = 1;
+            b[0] = 1;
+            a[true] = 2;
+        }
+        "#,
+        )
+        .unwrap_err();
+        assert!(error.contains("array `a` can't be assigned"));
+        assert!(error.contains("cannot index into a value of type `int`"));
+        assert!(error.contains("mismatched types, expected `int`, found `bool`"));
+    }
+
+    #[test]
+    fn test_return_type() {
+        let error = check(
+            r#"
+        bool positive(int a) {
+            return a;
+        }
+        void log() {
+            return 1;
+        }
+        int get() {
+            return;
+        }
+        void main() {}
+        "#,
+        )
+        .unwrap_err();
+        assert!(error.contains("mismatched types, expected `bool`, found `int`"));
+        assert!(error.contains("function with `void` return type can't return a value"));
+        assert!(error.contains("expected a return value of `int`"));
+    }
+
+    #[test]
+    fn test_logical_not() {
+        assert_eq!(
+            check("void main() { bool a = !(1 < 2) && !false; }"),
+            Ok(())
+        );
+        let error = check("void main() { int a = !1; }").unwrap_err();
+        assert!(error.contains("mismatched types, expected `bool`, found `int`"));
+        assert!(error.contains("mismatched types, expected `int`, found `bool`"));
+    }
+
+    #[test]
+    fn test_multi_dimensional_array() {
+        assert_eq!(
+            check(
+                r#"
+        int sum(int a[][3], int r[]) {
+            return a[1][0] + r[2];
+        }
+        void main() {
+            int m[2][3] = {{1, 2, 3}, {4}};
+            m[1][2] += sum(m, m[0]);
+        }
+        "#
+            ),
+            Ok(())
+        );
+        let error = check(
+            r#"
+        int first(int r[]) {
+            return r[0];
+        }
+        void main() {
+            int m[2][3] = {{1, true}};
+            m[0] = 1;
+            m[0][1][2] = 1;
+            first(m);
+        }
+        "#,
+        )
+        .unwrap_err();
+        assert!(error.contains("mismatched types, expected `int`, found `bool`"));
+        assert!(error.contains("array `m` can't be assigned"));
+        assert!(error.contains("cannot index into a value of type `int`"));
+        assert!(error.contains("mismatched types, expected `int[]`, found `int[2][3]`"));
+
+        // only the outermost dimension of a parameter is unsized, the others should match
+        assert_eq!(
+            check("int f(int m[][4]) { return m[0][0]; } void main() { int m[2][4]; f(m); }"),
+            Ok(())
+        );
+        let error = check(
+            r#"
+        int f(int m[][4]) {
+            return m[0][0];
+        }
+        int g(int m[][4]);
+        int g(int m[][5]) {
+            return f(m);
+        }
+        void main() {
+            int m[3][5];
+            f(m);
+        }
+        "#,
+        )
+        .unwrap_err();
+        assert!(error.contains("expected `int[][4]`, found `int[][5]`"), "{}", error);
+        assert!(error.contains("expected `int[][4]`, found `int[3][5]`"), "{}", error);
+        assert!(error.contains("conflicting types for function `g`"), "{}", error);
+    }
+
+    #[test]
+    fn test_excess_initializers() {
+        assert_eq!(
+            check(r#"void main() { int m[2][3] = {{1, 2, 3}, {4}}; char s[6] = "hello"; }"#),
+            Ok(())
+        );
+        let content = r#"
+        void main() {
+            int a[2] = {1, 2, 3};
+            int m[2][3] = {{1, 2, 3, 4}};
+            int n[2][1] = {{1}, {2}, {3}};
+            char s[2] = "hello";
+        }
+        "#;
+        let error = check(content).unwrap_err();
+        assert_eq!(error.matches("error:").count(), 4, "{}", error);
+        assert!(error.contains("too many initializers for `int[2]`"));
+        assert!(error.contains("too many initializers for `int[3]`"));
+        assert!(error.contains("too many initializers for `int[2][1]`"));
+        assert!(error.contains("too many initializers for `char[2]`"));
+        assert!(error.contains("main.cm:3:31"), "{}", error);
+    }
+
+    #[test]
+    fn test_compound_assignment() {
+        assert_eq!(
+            check("void main() { int a[2]; int i; i += a[i++] * 2; --a[0]; }"),
+            Ok(())
+        );
+        let error = check(
+            r#"
+        void main() {
+            bool b;
+            int a[2];
+            b += 1;
+            b++;
+            a -= 1;
+            a++;
+        }
+        "#,
+        )
+        .unwrap_err();
+        assert!(error.contains("`+=` can't be applied to `bool`"));
+        assert!(error.contains("`++` can't be applied to `bool`"));
+        assert!(error.contains("array `a` can't be assigned"));
+        assert!(error.contains("`++` can't be applied to `int[2]`"));
+    }
+
+    #[test]
+    fn test_char() {
+        let content = r#"
+        void main() {
+            char s[6] = "hello";
+            char c = s[0];
+            print(c >= 'a', "!");
+        }
+        "#;
+        assert_eq!(check(content), Ok(()));
+        let error = check(
+            r#"
+        void main() {
+            char c = 1;
+            bool b = c < 2;
+            int a[2] = "a";
+        }
+        "#,
+        )
+        .unwrap_err();
+        assert!(error.contains("mismatched types, expected `char`, found `int`"));
+        assert!(error.contains("mismatched types, expected `int`, found `char`"));
+        assert_eq!(error.matches("mismatched types").count(), 4);
+    }
+
+    #[test]
+    fn test_struct() {
+        let content = r#"
+        struct Point {
+            int x;
+            bool ok;
+        };
+        struct Path {
+            struct Point points[2];
+        };
+        int x(struct Point p) {
+            return p.x;
+        }
+        void main() {
+            struct Path path;
+            struct Point p = path.points[1];
+            path.points[0].x += x(p);
+            p.ok = !p.ok;
+        }
+        "#;
+        assert_eq!(check(content), Ok(()));
+        let error = check(
+            r#"
+        struct P {
+            int x;
+        };
+        struct Q {
+            int x;
+        };
+        void f(struct P p) {}
+        void main() {
+            struct P p;
+            struct Q q;
+            struct R r;
+            int i;
+            p.y = 1;
+            i.x = 1;
+            f(q);
+            print(p == p);
+        }
+        "#,
+        )
+        .unwrap_err();
+        assert!(error.contains("cannot find struct `R` in this scope"));
+        assert!(error.contains("no field `y` on type `struct P`"));
+        assert!(error.contains("no field `x` on type `int`"));
+        assert!(error.contains("mismatched types, expected `struct P`, found `struct Q`"));
+        assert!(error.contains("`struct P` can't be compared"));
+    }
+
+    #[test]
+    fn test_pointer() {
+        let content = r#"
+        void swap(int *a, int *b) {
+            int t = *a;
+            *a = *b;
+            *b = t;
+        }
+        int *first(int a[]) {
+            return a;
+        }
+        void main() {
+            int a[3];
+            int m[2][2];
+            int x;
+            int *p = a;
+            int **pp = &p;
+            swap(&x, *pp + 1);
+            for (p = first(a); p < a + 3; p++) {
+                x += *p;
+            }
+            p -= 2;
+            x = p - a;
+            *m[1] = *(*(m + 1) + 1);
+            print(p == &a[2], x);
+        }
+        "#;
+        assert_eq!(check(content), Ok(()));
+        let error = check(
+            r#"
+        struct P {
+            int x;
+        };
+        void main() {
+            int x;
+            char c;
+            int *p = &c;
+            struct P s;
+            int *q[2];
+            p = &s.x;
+            x = *x;
+            p *= 2;
+            x = p + p;
+            print(p);
+        }
+        "#,
+        )
+        .unwrap_err();
+        assert!(error.contains("mismatched types, expected `int*`, found `char*`"));
+        assert!(error.contains("array of `int*` isn't supported"));
+        assert!(error.contains("can't take the address of field `x`"));
+        assert!(error.contains("type `int` can't be dereferenced"));
+        assert!(error.contains("`*=` can't be applied to `int*`"));
+        assert!(error.contains("mismatched types, expected `int`, found `int*`"));
+        assert!(error.contains("`int*` can't be printed"));
+    }
+
+    #[test]
+    fn test_switch() {
+        let content = r#"
+        void main() {
+            int x;
+            switch (x) {
+                case 1:
+                    x = 2;
+                default:
+                    break;
+            }
+        }
+        "#;
+        assert_eq!(check(content), Ok(()));
+        let error = check("void main() { switch (true) { case 1: break; } }").unwrap_err();
+        assert!(error.contains("mismatched types, expected `int`, found `bool`"));
+    }
+
+    #[test]
+    fn test_conditional() {
+        let content = r#"
+        void main() {
+            int a[2];
+            int *p;
+            int x = a[0] > 0 ? 1 : 2;
+            char c = x == 1 ? 'a' : 'b';
+            p = x > 1 ? a : p;
+        }
+        "#;
+        assert_eq!(check(content), Ok(()));
+        let error = check(
+            "void main() { int x; bool b; x = b ? 1 : true; x = 1 ? 1 : 2; }",
+        )
+        .unwrap_err();
+        assert!(error.contains("`?:` arms have mismatched types `int` and `bool`"));
+        assert!(error.contains("mismatched types, expected `bool`, found `int`"));
+    }
+
+    #[test]
+    fn test_function_prototype() {
+        let content = r#"
+        bool odd(int n);
+        bool even(int n) {
+            return n == 0 || odd(n - 1);
+        }
+        bool odd(int n);
+        bool odd(int n) {
+            return n != 0 && even(n - 1);
+        }
+        void main() {
+            print(odd(3));
+        }
+        "#;
+        assert_eq!(check(content), Ok(()));
+        let error = check(
+            r#"
+        int f(int x);
+        bool g(int a[]);
+        void h(void);
+        int f(char x) {
+            return 1;
+        }
+        bool g(int a[]) {
+            return true;
+        }
+        int g(int a[]) {
+            return 1;
+        }
+        void main() {
+            h();
+        }
+        "#,
+        )
+        .unwrap_err();
+        assert!(error.contains(
+            "conflicting types for function `f`, declared as `int(int)`, found `int(char)`"
+        ));
+        assert!(error.contains("function `g` has already defined"));
+        assert!(error.contains("function `h` is declared but never defined"));
+    }
+}
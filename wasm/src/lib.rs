@@ -1,12 +1,18 @@
 mod utils;
 
 use tinylang_rs::{
+    fmt::Formatter,
+    host::CapturedHost,
     interpreter,
     lexer::lex::Lexer,
     parser::{parse::Parser, visitor::AstPrinter},
+    semantic,
 };
 use wasm_bindgen::prelude::*;
 
+// the playground has a single editor, diagnostics refer to it by this name
+const FILE_NAME: &str = "main.cm";
+
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
 // allocator.
 #[cfg(feature = "wee_alloc")]
@@ -17,7 +23,7 @@ static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 pub fn parse(source_code: String) -> String {
     let mut lex = Lexer::new(&source_code);
     let list = lex.lex();
-    let mut parser = Parser::new(list, &source_code);
+    let mut parser = Parser::new(list, FILE_NAME, &source_code);
     let res = parser.parse_program();
     match res {
         Ok(program) => {
@@ -38,19 +44,34 @@ pub fn tokenize(source_code: String) -> String {
         .join("\n")
 }
 
+/// the formatted source, or the syntax errors
+#[wasm_bindgen]
+pub fn format(source_code: String) -> String {
+    let mut formatter = Formatter::new(FILE_NAME, &source_code);
+    match formatter.format() {
+        Ok(formatted) => formatted,
+        Err(_) => formatter.error_reporter.emit_string(),
+    }
+}
+
 #[wasm_bindgen]
 pub fn interpret(source_code: String) -> String {
     let mut lex = Lexer::new(&source_code);
     let list = lex.lex();
-    let mut parser = Parser::new(list, &source_code);
+    let mut parser = Parser::new(list, FILE_NAME, &source_code);
     let res = parser.parse_program();
     match res {
-        Ok(mut program) => match interpreter::interpret(&mut program, false) {
-            Ok(env) => env.get_std_simulator_string(),
-            Err(_) => {
-                format!("interpreter error",)
+        Ok(mut program) => {
+            let (warning_reporter, checked) = semantic::check(FILE_NAME, &source_code, &program);
+            if let Err(error_reporter) = checked {
+                return warning_reporter.emit_string() + &error_reporter.emit_string();
+            }
+            let host = CapturedHost::default();
+            match interpreter::interpret(&mut program, Box::new(host.clone())) {
+                Ok(_) => host.output(),
+                Err(error) => error.report(FILE_NAME, &source_code).emit_string(),
             }
-        },
+        }
         Err(_) => parser.error_reporter.emit_string(),
     }
 }
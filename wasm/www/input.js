@@ -1,4 +1,4 @@
-import { interpret, parse, tokenize } from 'wasm';
+import { format, interpret, parse, tokenize } from 'wasm';
 import { EditorState } from '@codemirror/next/state';
 import { cpp } from '@codemirror/next/lang-cpp';
 import { EditorView, basicSetup } from '@codemirror/next/basic-setup';
@@ -26,6 +26,7 @@ const editor = new EditorView({
 const parse_button = document.getElementById('parse_button');
 const tokenize_button = document.getElementById('tokenize_button');
 const interpret_button = document.getElementById('interpret_button');
+const format_button = document.getElementById('format_button');
 const codeSelect = document.getElementById('code-select');
 const result = document.getElementById('result');
 const interactiveAst = document.getElementById('interactive-ast');
@@ -51,6 +52,11 @@ interpret_button.addEventListener('click', function () {
   const interpretResult = interpret(editor.state.doc.text.join('\n'));
   result.textContent = interpretResult;
 });
+format_button.addEventListener('click', function () {
+  controlVisibility('format');
+  const formatResult = format(editor.state.doc.text.join('\n'));
+  result.textContent = formatResult;
+});
 
 codeSelect.addEventListener('change', function () {
   const len = editor.state.doc.toString().length;